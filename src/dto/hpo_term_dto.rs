@@ -10,6 +10,7 @@ use ontolius::TermId;
 use serde::{de, Deserialize, Deserializer, Serialize};
 use crate::template::excel::read_excel_to_dataframe;
 use crate::error::{Error, Result};
+use crate::hpo::age_util;
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
@@ -18,7 +19,8 @@ pub struct HpoTermDto {
     term_id: String,
     /// Corresponding HPO label, e.g., Parasomnia
     term_label: String,
-    /// Entry: can be observed, excluded, na, or a time String
+    /// Entry: can be observed, excluded, na, or a time String. Observed entries may carry severity and
+    /// resolution modifiers, e.g., P3Y;severity=Severe;resolution=P5Y
     entry: String,
 }
 
@@ -59,20 +61,51 @@ impl HpoTermDto {
         self.term_label.clone()
     }
 
+    /// Add a severity modifier (label of an HPO severity term, e.g., Mild) to an observed entry
+    pub fn with_severity(mut self, severity: &str) -> Self {
+        self.entry = format!("{};severity={}", self.entry, severity);
+        self
+    }
+
+    /// Add the age at which an observed feature resolved, e.g., P5Y
+    pub fn with_resolution(mut self, resolution: &str) -> Self {
+        self.entry = format!("{};resolution={}", self.entry, resolution);
+        self
+    }
+
+    /// The entry without modifiers: observed, excluded, na, or a time String
+    fn value(&self) -> &str {
+        age_util::hpo_cell_value(&self.entry)
+    }
+
     pub fn is_excluded(&self) -> bool {
-        self.entry == "excluded"
+        self.value() == "excluded"
     }
 
     pub fn is_observed(&self) -> bool {
-        self.entry == "observed"
+        self.value() == "observed"
     }
 
     pub fn is_ascertained(&self) -> bool {
-        self.entry != "na"
+        self.value() != "na"
     }
 
     pub fn is_not_ascertained(&self) -> bool {
-        self.entry == "na"
+        self.value() == "na"
+    }
+
+    /// Label of the severity modifier (e.g., Mild), if any
+    pub fn severity(&self) -> Option<String> {
+        age_util::parse_hpo_cell(&self.entry).ok()
+            .and_then(|cell| cell.severity)
+            .map(str::to_string)
+    }
+
+    /// Age at which the feature resolved, if any
+    pub fn resolution(&self) -> Option<String> {
+        age_util::parse_hpo_cell(&self.entry).ok()
+            .and_then(|cell| cell.resolution)
+            .map(str::to_string)
     }
 
     pub fn has_onset(&self) -> bool {
@@ -81,7 +114,7 @@ impl HpoTermDto {
 
     pub fn onset(&self) -> Result<String> {
         match self.has_onset() {
-            true => Ok(self.entry.clone()),
+            true => Ok(self.value().to_string()),
             false => Err(Error::TemplateError{msg: "Attempt to get onset but DTO does not have onset".to_string()})
         }
     }
@@ -90,6 +123,17 @@ impl HpoTermDto {
         &self.entry
     }
 
+    /// Check the onset of the feature, if any (see [`age_util::check_onset_string`])
+    pub fn check_onset(&self) -> Result<()> {
+        if ! self.has_onset() {
+            return Ok(());
+        }
+        age_util::check_onset_string(self.value())
+            .map_err(|e| Error::AgeParseError { 
+                msg: format!("Invalid onset for {} ({}): {}", self.term_label, self.term_id, e) 
+            })
+    }
+
 }
 
 
@@ -138,4 +182,30 @@ mod test {
 
     }
 
+    #[rstest]
+    fn test_check_onset() {
+        assert!(HpoTermDto::new("HP:0001250", "Seizure", "Infantile onset").check_onset().is_ok());
+        assert!(HpoTermDto::new("HP:0001250", "Seizure", "P3Y;severity=Mild").check_onset().is_ok());
+        assert!(HpoTermDto::new("HP:0001250", "Seizure", "excluded").check_onset().is_ok());
+        let err = HpoTermDto::new("HP:0001250", "Seizure", "Infancy").check_onset().unwrap_err();
+        assert_eq!("Invalid onset for Seizure (HP:0001250): 'Infancy' is neither an HPO onset term nor an ISO 8601 or gestational age", 
+            err.to_string());
+    }
+
+    #[rstest]
+    fn test_severity_and_resolution() {
+        let dto = HpoTermDto::new("HP:0001250", "Seizure", "P3Y")
+            .with_severity("Severe")
+            .with_resolution("P5Y");
+        assert_eq!("P3Y;severity=Severe;resolution=P5Y", dto.entry());
+        assert!(dto.has_onset());
+        assert_eq!("P3Y", dto.onset().unwrap());
+        assert_eq!(Some("Severe".to_string()), dto.severity());
+        assert_eq!(Some("P5Y".to_string()), dto.resolution());
+        let dto = HpoTermDto::new("HP:0001250", "Seizure", "observed").with_severity("Mild");
+        assert!(dto.is_observed());
+        assert!(! dto.has_onset());
+        assert_eq!(None, dto.resolution());
+    }
+
 }
@@ -1,6 +1,7 @@
 pub mod case_dto;
 pub mod hgvs_dto;
 pub mod hpo_term_dto;
+pub mod phetools_error;
 pub mod template_dto;
 pub mod validation_errors;
 pub mod variant_dto;
\ No newline at end of file
@@ -0,0 +1,77 @@
+//! PheToolsError
+//!
+//! Most methods of PheTools translate errors into Strings. Applications that need to react differently to
+//! specific kinds of errors (e.g., an obsolete HPO term vs. a malformed CURIE vs. a network error) can use the
+//! `*_typed` methods, which return this cloneable public mirror of the internal error type.
+
+use std::fmt;
+
+use serde::Serialize;
+
+use crate::error::Error;
+
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase", tag = "kind")]
+pub enum PheToolsError {
+    /// The HPO identifier is not in the ontology
+    HpIdNotFound { id: String },
+    /// The HPO identifier is an obsolete (alternate) identifier of the term with the `replacement` id
+    ObsoleteTermId { id: String, replacement: String },
+    /// A malformed identifier (CURIE or TermId)
+    CurieError { msg: String },
+    /// Incorrect structure or content of the template (e.g., unknown column, row out of bounds)
+    TemplateError { msg: String },
+    /// A remote service could not be reached
+    NetworkError { msg: String },
+    /// One or more validation errors
+    Validation { errors: Vec<String> },
+    /// Any other error
+    Other { msg: String },
+}
+
+impl From<Error> for PheToolsError {
+    fn from(e: Error) -> Self {
+        match e {
+            Error::HpIdNotFound { id } => PheToolsError::HpIdNotFound { id },
+            Error::ObsoleteTermId { id, replacement } => PheToolsError::ObsoleteTermId { id, replacement },
+            Error::CurieError { msg } | Error::TermIdError { msg } => PheToolsError::CurieError { msg },
+            Error::TemplateError { msg } => PheToolsError::TemplateError { msg },
+            Error::NetworkError { msg } => PheToolsError::NetworkError { msg },
+            Error::ValidationErrors(verrs) => PheToolsError::Validation { errors: verrs.errors() },
+            other => PheToolsError::Other { msg: other.to_string() },
+        }
+    }
+}
+
+impl fmt::Display for PheToolsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PheToolsError::HpIdNotFound { id } => write!(f, "Not able to find HPO TermId: {id}"),
+            PheToolsError::ObsoleteTermId { id, replacement } => write!(f, "Obsolete HPO TermId: {id}; replace with {replacement}."),
+            PheToolsError::Validation { errors } => write!(f, "{}", errors.join("; ")),
+            PheToolsError::CurieError { msg }
+            | PheToolsError::TemplateError { msg }
+            | PheToolsError::NetworkError { msg }
+            | PheToolsError::Other { msg } => write!(f, "{msg}"),
+        }
+    }
+}
+
+impl std::error::Error for PheToolsError {}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_error() {
+        let e = PheToolsError::from(Error::ObsoleteTermId { id: "HP:0000001".to_string(), replacement: "HP:0000002".to_string() });
+        assert!(matches!(e, PheToolsError::ObsoleteTermId { .. }));
+        let e = PheToolsError::from(Error::termid_parse_error("HP0001250"));
+        assert_eq!(PheToolsError::CurieError { msg: "Failed to parse TermId: HP0001250".to_string() }, e);
+        let e = PheToolsError::from(Error::network_unreachable("VariantValidator", "timeout"));
+        assert!(matches!(e, PheToolsError::NetworkError { .. }));
+    }
+}
@@ -22,7 +22,19 @@ pub struct IndividualBundleDto {
     pub age_of_onset: String,
     pub age_at_last_encounter: String,
     pub deceased: String,
-    pub sex: String
+    pub sex: String,
+    /// Reported ancestry (HANCESTRO label or "na"); None if the template has no ancestry column
+    #[serde(default)]
+    pub ancestry: Option<String>,
+    /// Age at death (e.g., P3Y) of a deceased individual; None if not recorded
+    #[serde(default)]
+    pub age_at_death: Option<String>,
+    /// Cause of death of a deceased individual as a disease identifier and label; None if not recorded
+    #[serde(default)]
+    pub cause_of_death: Option<DiseaseDto>,
+    /// Karyotypic sex (e.g., XXY or 47,XXY); None if not recorded
+    #[serde(default)]
+    pub karyotypic_sex: Option<String>,
 }
 
 impl IndividualBundleDto {
@@ -44,6 +56,10 @@ impl IndividualBundleDto {
                 age_at_last_encounter: age_at_last_encounter.to_string(),
                 deceased: deceased.to_string(),
                 sex: sex.to_string(),
+                ancestry: None,
+                age_at_death: None,
+                cause_of_death: None,
+                karyotypic_sex: None,
             }
     }
 }
@@ -173,7 +189,10 @@ pub struct RowDto {
     pub individual_dto: IndividualBundleDto,
     pub disease_dto_list: Vec<DiseaseDto>,
     pub gene_var_dto_list: Vec<GeneVariantBundleDto>,
-    pub hpo_data: Vec<CellDto>
+    pub hpo_data: Vec<CellDto>,
+    /// Values of the columns of the trailing metadata section (if any), preserved verbatim
+    #[serde(default)]
+    pub trailing_metadata: Vec<String>,
 }
 
 impl RowDto {
@@ -181,7 +200,8 @@ impl RowDto {
         Self { individual_dto: ppkt_row.get_individual_dto(), 
             disease_dto_list: ppkt_row.get_disease_dto_list(), 
             gene_var_dto_list: ppkt_row.get_gene_var_dto_list(), 
-            hpo_data: ppkt_row.get_hpo_value_list()
+            hpo_data: ppkt_row.get_hpo_value_list(),
+            trailing_metadata: ppkt_row.trailing_metadata().to_vec()
         }
     }
 }
@@ -209,6 +229,24 @@ impl HeaderDupletDto {
         HpoTermDuplet::new(self.h1.clone(), self.h2.clone())
     }
 }
+/// Kind of value that a column of the template holds, e.g., to choose an editor (text field, dropdown, age picker)
+/// for a cell in a GUI
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase", rename_all_fields = "camelCase", tag = "kind")]
+pub enum CellKindDto {
+    /// Arbitrary text, e.g., title, comment, or allele
+    FreeText,
+    /// Exactly one of the allowed values, e.g., yes/no/na for deceased
+    Enumerated { allowed_values: Vec<String> },
+    /// An ISO 8601 age (e.g., P3Y), a gestational age (e.g., G32w2d), one of the onset labels, or "na"
+    Age { allowed_labels: Vec<String> },
+    /// A CURIE, e.g., HGNC:171; `prefix` is set if only one prefix is allowed
+    Curie { prefix: Option<String> },
+    /// An HPO column: one of the allowed values or an age (ISO 8601, gestational, or onset label), which may
+    /// be followed by severity and resolution modifiers
+    HpoStatus { allowed_values: Vec<String>, allowed_labels: Vec<String> },
+}
+
 /// convert from DupletItem using into()
 impl From<DupletItem> for HeaderDupletDto {
     fn from(duplet: DupletItem) -> Self {
@@ -229,12 +267,24 @@ impl From<DupletItem> for HeaderDupletDto {
 pub struct TemplateDto {
     pub cohort_type: TemplateType,
     pub hpo_headers: Vec<HeaderDupletDto>,
-    pub rows: Vec<RowDto>
+    pub rows: Vec<RowDto>,
+    /// Headers of the trailing metadata section (columns after the metadata marker), if any
+    #[serde(default)]
+    pub trailing_metadata_headers: Vec<HeaderDupletDto>,
+    /// True if the template has the optional ancestry column, even if there are no rows yet
+    #[serde(default)]
+    pub ancestry_column: bool,
+    /// True if the template has the optional age at death and cause of death columns, even if there are no rows yet
+    #[serde(default)]
+    pub vital_status_columns: bool,
+    /// True if the template has the optional karyotypic sex column, even if there are no rows yet
+    #[serde(default)]
+    pub karyotypic_sex_column: bool,
 }
 
 impl TemplateDto {
     pub fn mendelian(hpo_headers: Vec<HeaderDupletDto>, rows: Vec<RowDto>) -> Self {
-        Self { cohort_type: TemplateType::Mendelian, hpo_headers, rows }
+        Self { cohort_type: TemplateType::Mendelian, hpo_headers, rows, trailing_metadata_headers: vec![], ancestry_column: false, vital_status_columns: false, karyotypic_sex_column: false }
     }
 
     pub fn template_type(&self) -> TemplateType {
@@ -245,6 +295,23 @@ impl TemplateDto {
         self.template_type() == TemplateType::Mendelian
     }
 
+    /// True if the template has the optional ancestry column (i.e., the flag is set or any row has an ancestry value)
+    pub fn has_ancestry(&self) -> bool {
+        self.ancestry_column || self.rows.iter().any(|row| row.individual_dto.ancestry.is_some())
+    }
+
+    /// True if the template has the optional age at death and cause of death columns (i.e., the flag is set
+    /// or any row has an age or cause of death)
+    pub fn has_vital_status(&self) -> bool {
+        self.vital_status_columns || self.rows.iter().any(|row| 
+            row.individual_dto.age_at_death.is_some() || row.individual_dto.cause_of_death.is_some())
+    }
+
+    /// True if the template has the optional karyotypic sex column (i.e., the flag is set or any row has a karyotypic sex)
+    pub fn has_karyotypic_sex(&self) -> bool {
+        self.karyotypic_sex_column || self.rows.iter().any(|row| row.individual_dto.karyotypic_sex.is_some())
+    }
+
 
     pub fn get_disease_dto_list(&self) -> std::result::Result<Vec<DiseaseDto>, String> {
         if ! self.is_mendelian() {
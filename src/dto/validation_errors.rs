@@ -93,6 +93,41 @@ impl ValidationErrors {
     }
 }
 
+/// Severity of a [`QcIssue`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+/// A Q/C issue with the location of the affected cell (if known), so that a UI can highlight it.
+/// Rows and columns are zero-based indices into the template matrix (including the two header rows).
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QcIssue {
+    pub row: Option<usize>,
+    pub col: Option<usize>,
+    pub message: String,
+    pub severity: Severity,
+}
+
+impl QcIssue {
+    pub fn cell_error(row: usize, col: usize, message: impl Into<String>) -> Self {
+        Self { row: Some(row), col: Some(col), message: message.into(), severity: Severity::Error }
+    }
+
+    /// An error that concerns a row as a whole (e.g., inconsistent ages) rather than a single cell
+    pub fn row_error(row: usize, message: impl Into<String>) -> Self {
+        Self { row: Some(row), col: None, message: message.into(), severity: Severity::Error }
+    }
+
+    /// An error that could not be attributed to a specific cell (e.g., a malformed header)
+    pub fn unlocated_error(message: impl Into<String>) -> Self {
+        Self { row: None, col: None, message: message.into(), severity: Severity::Error }
+    }
+}
+
 impl std::fmt::Display for ValidationErrors {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "Validation errors: {:?}", self.errors)
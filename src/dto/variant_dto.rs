@@ -24,6 +24,9 @@ pub struct VariantDto {
     /// Have we validated this variant in the backend?
     validated: bool,
     is_structural: bool,
+    /// Predicted protein consequence of a validated HGVS variant, e.g., p.(Arg206His), for display
+    #[serde(default)]
+    protein_hgvs: Option<String>,
 }
 
 impl VariantDto {
@@ -39,7 +42,8 @@ impl VariantDto {
             hgnc_id: hgnc_id.into(), 
             gene_symbol: gene_symbol.into(),
             validated: false,
-            is_structural: false
+            is_structural: false,
+            protein_hgvs: None
         }
     }
 
@@ -55,7 +59,8 @@ impl VariantDto {
             hgnc_id: hgnc_id.into(), 
             gene_symbol: gene_symbol.into(),
             validated: false,
-            is_structural: true
+            is_structural: true,
+            protein_hgvs: None
         }
     }
 
@@ -83,6 +88,16 @@ impl VariantDto {
         self.is_structural
     }
 
+    pub fn protein_hgvs(&self) -> Option<&str> {
+        self.protein_hgvs.as_deref()
+    }
+
+    /// Set the predicted protein consequence, e.g., p.(Arg206His), of the validated variant
+    pub fn with_protein_hgvs(mut self, protein_hgvs: Option<String>) -> Self {
+        self.protein_hgvs = protein_hgvs;
+        self
+    }
+
     pub fn clone_validated(&self) -> Self {
         Self { 
             variant_string:  self.variant_string.clone(), 
@@ -90,7 +105,8 @@ impl VariantDto {
             hgnc_id: self.hgnc_id.clone(), 
             gene_symbol: self.gene_symbol.clone(), 
             validated: true, 
-            is_structural: self.is_structural
+            is_structural: self.is_structural,
+            protein_hgvs: self.protein_hgvs.clone()
         }
     }
 
@@ -101,7 +117,8 @@ impl VariantDto {
             hgnc_id: self.hgnc_id.clone(), 
             gene_symbol: self.gene_symbol.clone(), 
             validated: false, 
-            is_structural: self.is_structural
+            is_structural: self.is_structural,
+            protein_hgvs: self.protein_hgvs.clone()
         }
     }
 
@@ -157,4 +174,46 @@ impl VariantListDto {
         Self { variant_dto_list: dto_list }
     }
     
-}
\ No newline at end of file
+}
+
+
+/// A variant that could not be validated, together with the reason
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct FailedVariantDto {
+    pub variant_string: String,
+    pub transcript: String,
+    pub reason: String,
+}
+
+/// Summary of the validation of a list of variants, e.g., all variants of a cohort.
+/// The report can be serialized to JSON to keep a history of validation runs.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct VariantValidationReport {
+    /// Total number of variants that were evaluated
+    pub total: usize,
+    /// Number of variants that were successfully validated (including variants already in the cache)
+    pub validated: usize,
+    /// Variants that could not be validated
+    pub failed: Vec<FailedVariantDto>,
+    /// Time at which validation started (RFC 3339)
+    pub started_at: String,
+    /// Duration of the validation run in milliseconds
+    pub elapsed_ms: u128,
+    /// The evaluated variants, with the validated flag set accordingly
+    pub variant_dto_list: Vec<VariantDto>,
+    /// Error while writing the variant cache to disk (the validation results are correct, but were not cached)
+    #[serde(default)]
+    pub cache_error: Option<String>,
+}
+
+/// Alleles of a template that are spelled differently but resolve to the same genomic coordinates
+/// after validation, e.g., c.76_78del and c.76_78delACG
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct DuplicateAlleleGroupDto {
+    /// Genomic coordinates shared by the alleles (assembly, chromosome, position, ref, alt)
+    pub vcf: String,
+    /// Spelling that will be used for all alleles of the group if the curator confirms the merge
+    pub canonical: String,
+    /// The other spellings found in the template
+    pub duplicates: Vec<String>,
+}
@@ -16,6 +16,10 @@ use crate::dto::validation_errors::ValidationErrors;
 
 pub type Result<T> = core::result::Result<T, Error>;
 
+/// Messages of errors caused by an unreachable remote service start with this prefix, so that
+/// applications can tell the user to check their connection rather than to fix their input.
+pub const NETWORK_ERROR_PREFIX: &str = "Network error";
+
 
 #[derive(Debug, From, Serialize)]
 pub enum Error {
@@ -117,7 +121,11 @@ pub enum Error {
     }, 
     VariantCacheError {
         msg: String
-    }
+    },
+    /// A remote service (e.g., VariantValidator) could not be reached (DNS, timeout, connection refused)
+    NetworkError {
+        msg: String
+    },
 }
 
 impl Error {
@@ -180,6 +188,12 @@ impl Error {
         }
     }
 
+    pub fn empty_template(n_rows: usize) -> Self {
+        Error::TemplateError {
+            msg: format!("Empty template - must have two header rows and at least one data row but had {n_rows}"),
+        }
+    }
+
     pub fn short_label<T>(value: T, actual: usize, min: usize) -> Self
     where
         T: Into<String>,
@@ -199,6 +213,11 @@ impl Error {
         Self::TranscriptError { msg: msg }
     }
 
+    pub fn gene_symbol_mismatch(tx: &str, expected: &str, observed: &str) -> Self {
+        let msg = format!("Transcript '{tx}' belongs to gene {observed} according to VariantValidator but the template gene is {expected}");
+        Self::TranscriptError { msg }
+    }
+
     pub fn unrecognized_transcript_prefix<T>(tx: T) -> Self
     where
         T: Into<String>,
@@ -230,6 +249,15 @@ impl Error {
         }
     }
 
+    pub fn karyotypic_sex_error<T>(val: T) -> Self
+    where
+        T: Into<String>,
+    {
+        Error::SexFieldError {
+            msg: format!("Malformed karyotypic sex '{}'", val.into()),
+        }
+    }
+
     pub fn separator<T>(val: T) -> Self
     where
         T: Into<String>,
@@ -269,6 +297,11 @@ impl Error {
         Error::VariantError { msg: msg.to_string() }
     }
 
+    pub fn network_unreachable(service: &str, reason: impl std::fmt::Display) -> Self {
+        let msg = format!("Could not reach {service} ({reason}). Please check your internet connection");
+        Error::NetworkError { msg }
+    }
+
 }
 
 impl From<&str> for Error {
@@ -304,6 +337,9 @@ impl core::fmt::Display for Error {
             Error::EmptyField { field_name } => {
                 write!(fmt, "{field_name} field is empty")
             },
+            Error::NetworkError { msg } => {
+                write!(fmt, "{NETWORK_ERROR_PREFIX}: {msg}")
+            },
            
 
             Error::AgeParseError { msg }
@@ -111,12 +111,84 @@ pub fn check_valid_hgvs(value: &str) -> std::result::Result<(), String> {
 }
 
 
+/// Zero-width characters that are invisible but are not considered to be whitespace.
+/// They are often introduced when alleles are copied from PDF files.
+fn is_zero_width_char(c: char) -> bool {
+    matches!(c, '\u{200B}' | '\u{200C}' | '\u{200D}' | '\u{2060}' | '\u{FEFF}' | '\u{00AD}')
+}
+
+/// Space characters that look like an ordinary space (e.g., non-breaking space)
+fn is_nonstandard_space(c: char) -> bool {
+    matches!(c, '\u{00A0}' | '\u{2007}' | '\u{2009}' | '\u{200A}' | '\u{202F}')
+}
+
+/// Code points (e.g., U+00A0) of the invisible or non-standard space characters in an allele
+pub fn invisible_char_codes(allele: &str) -> Vec<String> {
+    allele.chars()
+        .filter(|c| is_zero_width_char(*c) || is_nonstandard_space(*c))
+        .map(|c| format!("U+{:04X}", c as u32))
+        .collect()
+}
+
+/// Normalize invisible whitespace in an allele such as c.2737C>T.
+/// - zero-width characters are removed
+/// - leading and trailing non-standard spaces (e.g., NBSP) are removed
+/// - internal non-standard spaces are replaced by an ordinary space, which is then reported as a malformed allele by the QC
+/// Returns None if the allele did not need to be normalized.
+pub fn normalize_allele(allele: &str) -> Option<String> {
+    let normalized: String = allele
+        .trim_matches(|c| is_zero_width_char(c) || is_nonstandard_space(c))
+        .chars()
+        .filter(|c| ! is_zero_width_char(*c))
+        .map(|c| if is_nonstandard_space(c) { ' ' } else { c })
+        .collect();
+    if normalized == allele {
+        None
+    } else {
+        Some(normalized)
+    }
+}
+
+
+/// A structural variant has one of the allowed prefixes, a colon, and a free-text description, e.g., DEL: exon 5.
+/// The description may itself contain ":".
 pub fn check_valid_structural(value: &str) -> bool {
-    let parts: Vec<&str> = value.split(':').collect();
-    let prefix = parts[0];
-    let suffix = parts[1..].join(":"); // in case the original string contains ":"
-    let structural_var = suffix.trim();
-    return  ALLOWED_STRUCTURAL_PREFIX.contains(prefix)
+    match value.split_once(':') {
+        Some((prefix, description)) => {
+            ALLOWED_STRUCTURAL_PREFIX.contains(prefix) && ! description.trim().is_empty()
+        },
+        None => false,
+    }
+}
+
+/// Kind of an allele string as determined locally, i.e., without querying VariantValidator
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AlleleKind {
+    /// A plausible HGVS string such as c.8242G>T (to be validated with VariantValidator)
+    Hgvs,
+    /// A structural variant such as DEL: exon 5
+    Structural,
+    /// "na", e.g., allele_2 of a monoallelic genotype
+    NotAvailable,
+    Malformed,
+}
+
+/// Classify an allele so that the caller can choose the validation path (HGVS or structural) before
+/// sending anything to VariantValidator.
+pub fn classify_allele(allele: &str) -> AlleleKind {
+    if allele == "na" {
+        AlleleKind::NotAvailable
+    } else if allele.starts_with("c.") || allele.starts_with("n.") {
+        if is_plausible_hgvs(allele) {
+            AlleleKind::Hgvs
+        } else {
+            AlleleKind::Malformed
+        }
+    } else if check_valid_structural(allele) {
+        AlleleKind::Structural
+    } else {
+        AlleleKind::Malformed
+    }
 }
 
 
@@ -145,6 +217,53 @@ mod tests {
         assert_eq!(result, should_pass, "Failed on input: {}", input);
     }
 
+    #[rstest]
+    #[case("c.2737C>T", None)]
+    #[case("c.2737C>T\u{00A0}", Some("c.2737C>T"))]
+    #[case("\u{FEFF}c.2737C>T", Some("c.2737C>T"))]
+    #[case("c.2737\u{200B}C>T", Some("c.2737C>T"))]
+    #[case("c.2737C\u{00A0}>T", Some("c.2737C >T"))]
+    #[case("DEL:\u{00A0}exon 5", Some("DEL: exon 5"))]
+    fn test_normalize_allele(#[case] input: &str, #[case] expected: Option<&str>) {
+        let result = normalize_allele(input);
+        assert_eq!(result.as_deref(), expected, "Failed on input: {:?}", input);
+    }
+
+    #[rstest]
+    #[case("DEL: deletion exon 5", true)]
+    #[case("INV: chr7:73,000,000-74,000,000", true)]
+    #[case("DEL", false)]
+    #[case("DEL:", false)]
+    #[case("", false)]
+    #[case(":", false)]
+    #[case("randomtext", false)]
+    #[case("del: exon 5", false)]
+    fn test_check_valid_structural(#[case] input: &str, #[case] should_pass: bool) {
+        assert_eq!(should_pass, check_valid_structural(input), "Failed on input: {}", input);
+    }
+
+    #[rstest]
+    #[case("c.8242G>T", AlleleKind::Hgvs)]
+    #[case("n.1A>G", AlleleKind::Hgvs)]
+    #[case("c.76_78ins", AlleleKind::Malformed)]
+    #[case("DEL: deletion exon 5", AlleleKind::Structural)]
+    #[case("TRANSL: t(2;7)(q33;q11.23) breakpoint at chr7:73,000,000", AlleleKind::Structural)]
+    #[case("DEL", AlleleKind::Malformed)]
+    #[case("DEL:", AlleleKind::Malformed)]
+    #[case("DELETION: exon 5", AlleleKind::Malformed)]
+    #[case("na", AlleleKind::NotAvailable)]
+    #[case("g.48411364C>A", AlleleKind::Malformed)]
+    #[case("", AlleleKind::Malformed)]
+    fn test_classify_allele(#[case] input: &str, #[case] expected: AlleleKind) {
+        assert_eq!(expected, classify_allele(input), "Failed on input: {}", input);
+    }
+
+    #[rstest]
+    fn test_invisible_char_codes() {
+        let codes = invisible_char_codes("c.2737\u{200B}C>T\u{00A0}");
+        assert_eq!(vec!["U+200B".to_string(), "U+00A0".to_string()], codes);
+    }
+
 
    
     
@@ -0,0 +1,70 @@
+//! Controlled vocabulary for the optional ancestry column
+//!
+//! Reported ancestry is recorded with the labels of the top-level ancestry categories of the
+//! Human Ancestry Ontology (HANCESTRO), e.g., "European".
+
+use std::collections::HashMap;
+
+use once_cell::sync::Lazy;
+
+/// HANCESTRO ancestry categories (label to identifier)
+static HANCESTRO_TERMS: Lazy<HashMap<&'static str, &'static str>> = Lazy::new(|| {
+    [
+        ("European", "HANCESTRO:0005"),
+        ("South Asian", "HANCESTRO:0006"),
+        ("Asian unspecified", "HANCESTRO:0008"),
+        ("East Asian", "HANCESTRO:0009"),
+        ("African", "HANCESTRO:0010"),
+        ("Hispanic or Latin American", "HANCESTRO:0014"),
+        ("Greater Middle Eastern", "HANCESTRO:0015"),
+        ("African American or Afro-Caribbean", "HANCESTRO:0016"),
+        ("Oceanian", "HANCESTRO:0017"),
+    ]
+    .into_iter()
+    .collect()
+});
+
+/// Get the HANCESTRO identifier for an ancestry label, e.g., HANCESTRO:0005 for European
+pub fn ancestry_term_id(label: &str) -> Option<&'static str> {
+    HANCESTRO_TERMS.get(label).copied()
+}
+
+/// Labels of the HANCESTRO ancestry categories, in alphabetical order
+pub fn ancestry_labels() -> Vec<&'static str> {
+    let mut labels: Vec<&str> = HANCESTRO_TERMS.keys().copied().collect();
+    labels.sort();
+    labels
+}
+
+/// Check the contents of an ancestry cell, which must be "na" or the label of a HANCESTRO ancestry category
+pub fn check_ancestry(value: &str) -> Result<(), String> {
+    if value == "na" || HANCESTRO_TERMS.contains_key(value) {
+        Ok(())
+    } else {
+        Err(format!("Unrecognized ancestry '{value}' (allowed: na, {})", ancestry_labels().join(", ")))
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case("European", true)]
+    #[case("East Asian", true)]
+    #[case("na", true)]
+    #[case("european", false)]
+    #[case("HANCESTRO:0005", false)]
+    #[case("", false)]
+    fn test_check_ancestry(#[case] value: &str, #[case] valid: bool) {
+        assert_eq!(valid, check_ancestry(value).is_ok());
+    }
+
+    #[rstest]
+    fn test_ancestry_term_id() {
+        assert_eq!(Some("HANCESTRO:0005"), ancestry_term_id("European"));
+        assert_eq!(None, ancestry_term_id("na"));
+    }
+}
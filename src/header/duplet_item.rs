@@ -10,7 +10,7 @@ use std::{collections::HashSet, fmt::format};
 use once_cell::sync::Lazy;
 use regex::Regex;
 
-use crate::{dto::template_dto::HeaderDupletDto, header::allele_util, hpo::age_util};
+use crate::{dto::template_dto::{CellKindDto, HeaderDupletDto}, error::Error, header::{allele_util, ancestry_util, individual_header}, hpo::age_util, template::curie};
 
 
 
@@ -21,6 +21,9 @@ static FORBIDDEN_CHARS: Lazy<HashSet<char>> = Lazy::new(|| {
 
 
 
+/// Prefixes of the RefSeq (coding, non-coding, predicted) and Ensembl transcripts that can be used in the transcript column
+pub const ALLOWED_TRANSCRIPT_PREFIXES: [&str; 4] = ["NM_", "NR_", "XM_", "ENST"];
+
 pub static ALLOWED_STRUCTURAL_PREFIX: Lazy<HashSet<String>> = Lazy::new(|| {
     ["DEL", "DUP", "INV", "INS", "TRANSL"]
         .iter()
@@ -54,16 +57,18 @@ pub static ALLOWED_AGE_LABELS: Lazy<HashSet<String>> = Lazy::new(|| {
     .collect()
 });
 
+/// The onset labels that can be used instead of an ISO 8601 age, in alphabetical order
+pub fn age_labels() -> Vec<String> {
+    let mut labels: Vec<String> = ALLOWED_AGE_LABELS.iter().cloned().collect();
+    labels.sort();
+    labels
+}
+
 /// Regex for ISO 8601 durations
 pub static ISO8601_RE: Lazy<Regex> = Lazy::new(|| {
     Regex::new(r"^P(?:(\d+)Y)?(?:(\d+)M)?(?:(\d+)D)?$").expect("valid ISO 8601 regex")
 });
 
-/// Regex for gestational age format
-pub static GESTATIONAL_AGE_RE: Lazy<Regex> = Lazy::new(|| {
-    Regex::new(r"G\d+w[0-6]d").expect("valid gestational age regex")
-});
-
 pub static ALLOWED_DECEASED_ITEMS: Lazy<HashSet<String>> = Lazy::new(||{
     let mut hset = HashSet::new();
     hset.insert("yes".to_string());
@@ -100,6 +105,12 @@ pub enum DupletType {
     DECEASED,
     SEX,
     HpoSeparator,
+    ANCESTRY,
+    AGEATDEATH,
+    CAUSEOFDEATHID,
+    CAUSEOFDEATHLABEL,
+    KARYOTYPICSEX,
+    MetadataSeparator,
 }
 
 
@@ -223,11 +234,7 @@ impl DupletItem {
 
 
     fn check_valid_structural(value: &str) -> Result<(), String>  {
-        let parts: Vec<&str> = value.split(':').collect();
-        let prefix = parts[0];
-        let suffix = parts[1..].join(":"); // in case the original string contains ":"
-        let structural_var = suffix.trim();
-        match  ALLOWED_STRUCTURAL_PREFIX.contains(prefix) {
+        match allele_util::check_valid_structural(value) {
             true => Ok(()),
             false => Err(format!("Malformed structural variant '{value}'")),
         }
@@ -246,7 +253,7 @@ impl DupletItem {
         if ISO8601_RE.is_match(cell_value) {
             return Ok(());
         } 
-        if GESTATIONAL_AGE_RE.is_match(cell_value) {
+        if age_util::is_valid_gestational_age(cell_value) {
             return Ok(());
         }
         Err(format!("Malformed age string '{}'", cell_value))
@@ -281,13 +288,7 @@ impl DupletItem {
 
     fn check_disease_id(&self, cell_contents: &str) -> Result<(), String> {
         Self::check_valid_curie(cell_contents)?;
-        if !(cell_contents.starts_with("OMIM") || cell_contents.starts_with("MONDO")) {
-            return Err(format!("Disease id has invalid prefix: '{}'", cell_contents));
-        }
-        if cell_contents.starts_with("OMIM:") && cell_contents.len() != 11 {
-            return Err(format!("OMIM identifiers must have 6 digits: '{}'", cell_contents));
-        }
-        Ok(())
+        curie::check_disease_id_prefix(cell_contents)
     }
 
     fn check_disease_label(&self, cell_contents: &str) -> Result<(), String> {
@@ -313,13 +314,14 @@ impl DupletItem {
         Ok(())
     }
 
+    /// RefSeq (NM_, NR_, XM_) or Ensembl (ENST) transcript with a version, e.g., NM_001111067.4 or ENST00000357033.8
     fn check_transcript(&self, cell_contents: &str) -> Result<(), String> {
         Self::check_empty(cell_contents)?;
-        if ! cell_contents.starts_with("ENST") && ! cell_contents.starts_with("NM_") {
-            return Err(format!("Unrecognized transcript prefix '{cell_contents}'"));
+        if ! ALLOWED_TRANSCRIPT_PREFIXES.iter().any(|prefix| cell_contents.starts_with(prefix)) {
+            return Err(Error::unrecognized_transcript_prefix(cell_contents).to_string());
         }  
         if ! cell_contents.contains(".") {
-            return Err(format!("Transcript '{}' is missing a version", cell_contents));
+            return Err(Error::lacks_transcript_version(cell_contents).to_string());
         } 
         if let Some((before_last, last)) = cell_contents.rsplit_once('.') {
             if before_last.is_empty() {
@@ -332,10 +334,11 @@ impl DupletItem {
         Ok(())
     }
 
-    fn check_allele1(&self, cell_contents: &str) -> Result<(), String> {
+    /// Shared check for allele_1 and allele_2: an HGVS string (c. or n.) or a structural variant (e.g., DEL: ...)
+    fn check_allele(cell_contents: &str) -> Result<(), String> {
         Self::check_empty(cell_contents)?;
         Self::check_white_space(cell_contents)?;
-        if cell_contents.starts_with("c.")|| cell_contents.starts_with("n."){
+        if cell_contents.starts_with("c.") || cell_contents.starts_with("n.") {
             if ! allele_util::is_plausible_hgvs(cell_contents) {
                 return Err(format!("Malformed HGVS string '{cell_contents}'"));
             }
@@ -345,17 +348,16 @@ impl DupletItem {
         Ok(())
     }
 
+    fn check_allele1(&self, cell_contents: &str) -> Result<(), String> {
+        Self::check_allele(cell_contents)
+    }
+
+    /// allele_2 is checked in the same way as allele_1, but may also be "na" (e.g., for monoallelic genotypes)
     fn check_allele2(&self, cell_contents: &str) -> Result<(), String> {
-        Self::check_empty(cell_contents)?;
-        Self::check_white_space(cell_contents)?;
         if cell_contents == "na" {
             return Ok(());
-        } else if cell_contents.starts_with("c.") || cell_contents.starts_with("n."){
-            allele_util::check_valid_hgvs(cell_contents)?;
-        } else {
-            Self::check_valid_structural(cell_contents)?;
         }
-        Ok(())
+        Self::check_allele(cell_contents)
     }
     
     fn check_variant_comment(&self, cell_contents: &str) -> Result<(), String> {
@@ -377,11 +379,39 @@ impl DupletItem {
         }
     }
 
+    fn check_ancestry(&self, cell_contents: &str) -> Result<(), String> {
+        ancestry_util::check_ancestry(cell_contents)
+    }
+
+    /// The cause of death is "na" or a disease identifier (as in the disease_id column)
+    fn check_cause_of_death_id(&self, cell_contents: &str) -> Result<(), String> {
+        if cell_contents == "na" {
+            return Ok(());
+        }
+        self.check_disease_id(cell_contents)
+    }
+
+    fn check_cause_of_death_label(&self, cell_contents: &str) -> Result<(), String> {
+        if cell_contents == "na" {
+            return Ok(());
+        }
+        self.check_disease_label(cell_contents)
+    }
+
+    /// The karyotypic sex is "na" or a karyotype that can be exported, e.g., 47,XXY
+    fn check_karyotypic_sex(&self, cell_contents: &str) -> Result<(), String> {
+        individual_header::parse_karyotypic_sex(cell_contents)
+            .map(|_| ())
+            .map_err(|e| e.to_string())
+    }
+
     fn check_separator(&self, cell_contents: &str) -> Result<(), String> {
-        if cell_contents != "na" {
-            Err(format!("Separator value must be 'na' but was '{}'", cell_contents))
-        } else {
-            Ok(())
+        match cell_contents {
+            "na" => Ok(()),
+            // a paste that is offset by one column moves the first HPO value into the separator column
+            "observed" | "excluded" => Err(format!(
+                "Separator value must be 'na' but was '{}' (data appears shifted by one column)", cell_contents)),
+            _ => Err(format!("Separator value must be 'na' but was '{}'", cell_contents)),
         }
     }
     
@@ -405,11 +435,70 @@ impl DupletItem {
             DupletType::DECEASED => self.check_deceased(cell_contents)?,
             DupletType::SEX => self.check_sex(cell_contents)?,
             DupletType::HpoSeparator => self.check_separator(cell_contents)?,
+            DupletType::ANCESTRY => self.check_ancestry(cell_contents)?,
+            DupletType::AGEATDEATH => Self::check_valid_age_string(cell_contents)?,
+            DupletType::CAUSEOFDEATHID => self.check_cause_of_death_id(cell_contents)?,
+            DupletType::CAUSEOFDEATHLABEL => self.check_cause_of_death_label(cell_contents)?,
+            DupletType::KARYOTYPICSEX => self.check_karyotypic_sex(cell_contents)?,
+            DupletType::MetadataSeparator => self.check_separator(cell_contents)?,
         };
         Ok(())
     }
 
 
+    /// True for the columns that may be empty (comment and variant.comment)
+    pub fn is_optional(&self) -> bool {
+        matches!(self.duplet_type, DupletType::COMMENT | DupletType::VARIANTCOMMENT)
+    }
+
+    /// True for columns with identifiers or HGVS strings (PMID, disease_id, HGNC_id, transcript, allele_1, allele_2),
+    /// from which all whitespace can be removed
+    pub fn is_whitespace_free(&self) -> bool {
+        matches!(self.duplet_type, 
+            DupletType::PMID 
+            | DupletType::DISEASEID 
+            | DupletType::HGNCID 
+            | DupletType::TRANSCRIPT 
+            | DupletType::ALLELE1 
+            | DupletType::ALLELE2)
+    }
+
+    /// Kind of value of this column, including the allowed values of columns with a fixed vocabulary
+    pub fn cell_kind(&self) -> CellKindDto {
+        let enumerated = |values: &[&str]| CellKindDto::Enumerated {
+            allowed_values: values.iter().map(|v| v.to_string()).collect()
+        };
+        match self.duplet_type {
+            DupletType::PMID => CellKindDto::Curie { prefix: Some("PMID".to_string()) },
+            DupletType::DISEASEID => CellKindDto::Curie { prefix: None },
+            DupletType::HGNCID => CellKindDto::Curie { prefix: Some("HGNC".to_string()) },
+            DupletType::TITLE
+            | DupletType::INDIVIDUALID
+            | DupletType::COMMENT
+            | DupletType::DISEASELABEL
+            | DupletType::GENESYMBOL
+            | DupletType::TRANSCRIPT
+            | DupletType::ALLELE1
+            | DupletType::ALLELE2
+            | DupletType::VARIANTCOMMENT
+            | DupletType::CAUSEOFDEATHLABEL
+            | DupletType::KARYOTYPICSEX => CellKindDto::FreeText,
+            DupletType::CAUSEOFDEATHID => CellKindDto::Curie { prefix: None },
+            DupletType::AGEOFONSET
+            | DupletType::AGEATLASTENCOUNTER
+            | DupletType::AGEATDEATH => CellKindDto::Age { allowed_labels: age_labels() },
+            DupletType::DECEASED => enumerated(&["yes", "no", "na"]),
+            DupletType::SEX => enumerated(&["M", "F", "O", "U"]),
+            DupletType::HpoSeparator
+            | DupletType::MetadataSeparator => enumerated(&["na"]),
+            DupletType::ANCESTRY => {
+                let mut allowed = vec!["na"];
+                allowed.extend(ancestry_util::ancestry_labels());
+                enumerated(&allowed)
+            },
+        }
+    }
+
     fn get_column_name(&self) -> &str {
         match self.duplet_type {
             DupletType::PMID => "PMID",
@@ -429,6 +518,12 @@ impl DupletItem {
             DupletType::DECEASED => "deceased",
             DupletType::SEX => "sex",
             DupletType::HpoSeparator => "HPO",
+            DupletType::ANCESTRY => "ancestry",
+            DupletType::AGEATDEATH => "age_at_death",
+            DupletType::CAUSEOFDEATHID => "cause_of_death_id",
+            DupletType::CAUSEOFDEATHLABEL => "cause_of_death_label",
+            DupletType::KARYOTYPICSEX => "karyotypic_sex",
+            DupletType::MetadataSeparator => "metadata",
         }
     }
 
@@ -506,6 +601,47 @@ impl DupletItem {
         DupletItem::new("sex", "M:F:O:U", DupletType::SEX)
     }
 
+    /// The "HPO"/"na" column that separates the constant part of the template from the HPO columns
+    pub fn hpo_separator() -> Self {
+        DupletItem::new("HPO", "na", DupletType::HpoSeparator)
+    }
+
+    /// Optional column with the reported ancestry (HANCESTRO category), placed after the HPO columns
+    pub fn ancestry() -> Self {
+        DupletItem::new("ancestry", "optional", DupletType::ANCESTRY)
+    }
+
+    /// Optional column with the age at death of a deceased individual, placed after the HPO (and ancestry) columns
+    pub fn age_at_death() -> Self {
+        DupletItem::new("age_at_death", "optional", DupletType::AGEATDEATH)
+    }
+
+    /// Optional column with the disease identifier of the cause of death (follows the age_at_death column)
+    pub fn cause_of_death_id() -> Self {
+        DupletItem::new("cause_of_death_id", "optional", DupletType::CAUSEOFDEATHID)
+    }
+
+    /// Optional column with the disease label of the cause of death (follows the cause_of_death_id column)
+    pub fn cause_of_death_label() -> Self {
+        DupletItem::new("cause_of_death_label", "optional", DupletType::CAUSEOFDEATHLABEL)
+    }
+
+    /// Optional column with the karyotypic sex (e.g., 47,XXY), placed after the vital status columns
+    pub fn karyotypic_sex() -> Self {
+        DupletItem::new("karyotypic_sex", "optional", DupletType::KARYOTYPICSEX)
+    }
+
+    /// Marker column that starts the trailing metadata section. Columns after the marker are not part
+    /// of the schema (e.g., curator notes) and are preserved verbatim.
+    pub fn metadata_separator() -> Self {
+        DupletItem::new("metadata", "na", DupletType::MetadataSeparator)
+    }
+
+    /// Two duplets are equivalent if they have the same labels in both header rows
+    pub fn same_labels(&self, other: &DupletItem) -> bool {
+        self.row1 == other.row1 && self.row2 == other.row2
+    }
+
 }
 
 
@@ -550,6 +686,38 @@ mod tests {
     }
 
 
+    #[rstest]
+    #[case("c.617G>A", Ok(()))]
+    #[case("c.1234+1G>A", Ok(()))]
+    #[case("DEL: deletion of exons 1-3", Ok(()))]
+    #[case("", Err("Value must not be empty".to_string()))]
+    #[case("nan", Err("Malformed structural variant 'nan'".to_string()))]
+    #[case("DEL", Err("Malformed structural variant 'DEL'".to_string()))]
+    #[case("c.76_78ins", Err("Malformed HGVS string 'c.76_78ins'".to_string()))]
+    fn test_allele1_and_allele2_consistent(#[case] input: &str, #[case] expected: Result<(), String>) {
+        assert_eq!(expected, DupletItem::allele1().qc_data(input));
+        assert_eq!(expected, DupletItem::allele2().qc_data(input));
+    }
+
+    #[rstest]
+    #[case("NM_001111067.4", Ok(()))]
+    #[case("NR_003051.4", Ok(()))]
+    #[case("XM_011511234.2", Ok(()))]
+    #[case("ENST00000357033.8", Ok(()))]
+    #[case("NR_003051", Err("Transcript 'NR_003051' is missing a version".to_string()))]
+    #[case("ENST00000357033", Err("Transcript 'ENST00000357033' is missing a version".to_string()))]
+    #[case("NG_012345.1", Err("Unrecognized transcript prefix 'NG_012345.1'".to_string()))]
+    #[case("NM_001111067.x", Err("Malformed transcript version: 'NM_001111067.x'".to_string()))]
+    fn test_check_transcript(#[case] input: &str, #[case] expected: Result<(), String>) {
+        assert_eq!(expected, DupletItem::transcript().qc_data(input));
+    }
+
+    #[test]
+    fn test_allele2_na() {
+        assert!(DupletItem::allele2().qc_data("na").is_ok());
+        assert!(DupletItem::allele1().qc_data("na").is_err());
+    }
+
     #[test]
     fn wtf() {
         let re = Regex::new(r"^(c|n)\.\d+(?:_\d+)?dup$").unwrap();
@@ -0,0 +1,124 @@
+//! Consistency of HGNC identifiers and gene symbols
+//!
+//! The hgnc_id and gene_symbol columns are checked independently by the header Q/C. A [`HgncSymbolMap`] can be
+//! used to check in addition that the gene symbol of a row is the approved symbol of its HGNC identifier, e.g.,
+//! to flag a row with HGNC:29316 (ZSWIM6) and the gene symbol FBN1.
+
+use std::collections::HashMap;
+use std::fs;
+
+use crate::error::{Error, Result};
+
+/// A small set of approved symbols that is available without a download. Use [`HgncSymbolMap::from_tsv`]
+/// with the HGNC complete set for other genes.
+const EMBEDDED_SYMBOLS: [(&str, &str); 14] = [
+    ("HGNC:171", "ACVR1"),
+    ("HGNC:1100", "BRCA1"),
+    ("HGNC:1101", "BRCA2"),
+    ("HGNC:1884", "CFTR"),
+    ("HGNC:2928", "DMD"),
+    ("HGNC:3603", "FBN1"),
+    ("HGNC:3604", "FBN2"),
+    ("HGNC:3754", "FLNA"),
+    ("HGNC:6990", "MECP2"),
+    ("HGNC:7765", "NF1"),
+    ("HGNC:9588", "PTEN"),
+    ("HGNC:11998", "TP53"),
+    ("HGNC:27015", "CMPK2"),
+    ("HGNC:29316", "ZSWIM6"),
+];
+
+/// Approved gene symbols keyed by HGNC identifier (e.g., HGNC:3603 to FBN1)
+#[derive(Clone, Debug, Default)]
+pub struct HgncSymbolMap {
+    symbols: HashMap<String, String>,
+}
+
+impl HgncSymbolMap {
+    /// The map with the embedded set of genes
+    pub fn embedded() -> Self {
+        let symbols = EMBEDDED_SYMBOLS.iter()
+            .map(|(hgnc_id, symbol)| (hgnc_id.to_string(), symbol.to_string()))
+            .collect();
+        Self { symbols }
+    }
+
+    /// Load a tab-separated table whose first two columns are the HGNC identifier and the approved symbol,
+    /// such as the HGNC complete set (hgnc_complete_set.txt). Lines that do not start with an HGNC
+    /// identifier (e.g., the header line) are skipped.
+    pub fn from_tsv(file_path: &str) -> Result<Self> {
+        let contents = fs::read_to_string(file_path)
+            .map_err(|e| Error::custom(format!("Could not open HGNC table at '{}': {}", file_path, e)))?;
+        let symbols = contents.lines()
+            .filter_map(|line| {
+                let mut fields = line.split('\t');
+                match (fields.next(), fields.next()) {
+                    (Some(hgnc_id), Some(symbol)) if hgnc_id.starts_with("HGNC:") && !symbol.is_empty() => {
+                        Some((hgnc_id.to_string(), symbol.to_string()))
+                    },
+                    _ => None,
+                }
+            })
+            .collect();
+        Ok(Self { symbols })
+    }
+
+    /// Approved symbol of the gene, if the HGNC identifier is in the map
+    pub fn symbol(&self, hgnc_id: &str) -> Option<&str> {
+        self.symbols.get(hgnc_id).map(String::as_str)
+    }
+
+    pub fn len(&self) -> usize {
+        self.symbols.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.symbols.is_empty()
+    }
+
+    /// Check that the gene symbol is the approved symbol of the HGNC identifier.
+    /// Identifiers that are not in the map cannot be checked and are accepted.
+    pub fn check_symbol(&self, hgnc_id: &str, gene_symbol: &str) -> Result<()> {
+        match self.symbol(hgnc_id) {
+            Some(expected) if expected != gene_symbol => Err(Error::HgncError {
+                msg: format!("Gene symbol mismatch for {hgnc_id}: expected '{expected}' but found '{gene_symbol}'")
+            }),
+            _ => Ok(()),
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case("HGNC:3603", "FBN1", true)]
+    #[case("HGNC:29316", "ZSWIM6", true)]
+    #[case("HGNC:29316", "FBN1", false)]
+    #[case("HGNC:3603", "fbn1", false)]
+    #[case("HGNC:99999999", "FBN1", true)]
+    fn test_check_symbol(#[case] hgnc_id: &str, #[case] symbol: &str, #[case] valid: bool) {
+        assert_eq!(valid, HgncSymbolMap::embedded().check_symbol(hgnc_id, symbol).is_ok());
+    }
+
+    #[rstest]
+    fn test_mismatch_message() {
+        let err = HgncSymbolMap::embedded().check_symbol("HGNC:29316", "FBN1").unwrap_err();
+        assert!(matches!(err, Error::HgncError { .. }));
+        assert_eq!("Gene symbol mismatch for HGNC:29316: expected 'ZSWIM6' but found 'FBN1'", err.to_string());
+    }
+
+    #[rstest]
+    fn test_from_tsv() {
+        let path = std::env::temp_dir().join(format!("rphetools-hgnc-{}.tsv", std::process::id()));
+        fs::write(&path, "hgnc_id\tsymbol\tname\nHGNC:5\tA1BG\talpha-1-B glycoprotein\nHGNC:37133\tA1BG-AS1\tA1BG antisense RNA 1\n").unwrap();
+        let symbol_map = HgncSymbolMap::from_tsv(path.to_str().unwrap()).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(2, symbol_map.len());
+        assert_eq!(Some("A1BG"), symbol_map.symbol("HGNC:5"));
+        assert!(symbol_map.check_symbol("HGNC:37133", "A1BG").is_err());
+    }
+}
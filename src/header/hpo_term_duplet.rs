@@ -8,7 +8,8 @@ use std::str::FromStr;
 use lazy_static::lazy_static;
 use ontolius::TermId;
 
-use crate::dto::template_dto::{HeaderDupletDto};
+use crate::dto::template_dto::{CellKindDto, HeaderDupletDto};
+use crate::header::duplet_item;
 use crate::template::curie;
 use crate::error::{self, Error, Result};
 use crate::hpo::age_util;
@@ -79,6 +80,14 @@ impl HpoTermDuplet {
         let tid = TermId::from_str(&self.hpo_id).map_err(|_| format!("Could not create TermId from {}", self.hpo_id()))?;
         Ok(tid)
     }
+
+    /// HPO cells hold observed/excluded/na or an age of onset
+    pub fn cell_kind(&self) -> CellKindDto {
+        CellKindDto::HpoStatus {
+            allowed_values: vec!["observed".to_string(), "excluded".to_string(), "na".to_string()],
+            allowed_labels: duplet_item::age_labels(),
+        }
+    }
     
 }
 
@@ -87,7 +96,7 @@ impl  HpoTermDuplet {
 
     /// An HPO cell can be empty, or contain observed/expected/na or an age string
     /// We plan to enforce that HPO cells cannot be empty (they will need to have na for not-available data)
-    /// We also plan to allow some modifiers (e.g., "Mild") in this field, indicating "observed" with modifier
+    /// Observed entries can carry severity and resolution modifiers, e.g., observed;severity=Mild
     fn qc_cell(&self, cell_contents: &str) -> Result<()> {
         if cell_contents.is_empty() {
             return Ok(());
@@ -98,6 +107,9 @@ impl  HpoTermDuplet {
         if age_util::is_valid_age_string(cell_contents) {
             return Ok(());
         }
+        if cell_contents.contains(';') && age_util::parse_hpo_cell(cell_contents).is_ok() {
+            return Ok(());
+        }
         Err(Error::malformed_hpo_entry(&self.row1(), &self.row2(), cell_contents))
     }
 
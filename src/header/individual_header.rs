@@ -1,4 +1,6 @@
-use crate::{dto::{template_dto::IndividualBundleDto, validation_errors::ValidationErrors}, header::duplet_item::DupletItem, template::individual_bundle::IndividualBundle};
+use phenopackets::schema::v2::core::KaryotypicSex;
+
+use crate::{dto::{template_dto::{DiseaseDto, IndividualBundleDto}, validation_errors::ValidationErrors}, error::Error, header::duplet_item::DupletItem, hpo::age_util, template::individual_bundle::IndividualBundle};
 
 
 
@@ -11,7 +13,8 @@ pub struct IndividualHeader {
     pub age_of_onset: DupletItem,
     pub age_at_last_encounter: DupletItem,
     pub deceased: DupletItem,
-    pub sex: DupletItem
+    pub sex: DupletItem,
+    pub ancestry: DupletItem
 }
 
 
@@ -25,7 +28,8 @@ impl IndividualHeader {
             age_of_onset: DupletItem::age_of_onset(), 
             age_at_last_encounter: DupletItem::age_at_last_encounter() ,
             deceased: DupletItem::deceased(),
-            sex: DupletItem::sex()
+            sex: DupletItem::sex(),
+            ancestry: DupletItem::ancestry()
         }
     }
 
@@ -60,12 +64,81 @@ impl IndividualHeader {
 
     /// Check an individual bundle for errors.
     pub fn qc_dto(&self, dto: IndividualBundleDto) -> Result<(), ValidationErrors> {
-        self.qc_data(&dto.pmid, &dto.title, &dto.individual_id, &dto.comment, &dto.age_of_onset, &dto.age_at_last_encounter, &dto.deceased, &dto.sex)
+        let mut verrors = ValidationErrors::new();
+        verrors.push_verr_result(self.qc_data(&dto.pmid, &dto.title, &dto.individual_id, &dto.comment, &dto.age_of_onset, &dto.age_at_last_encounter, &dto.deceased, &dto.sex));
+        verrors.push_result(self.qc_ancestry(dto.ancestry.as_deref()));
+        verrors.add_errors(self.qc_vital_status(&dto.deceased, &dto.age_of_onset, &dto.age_at_last_encounter, 
+            dto.age_at_death.as_deref(), dto.cause_of_death.as_ref()));
+        verrors.push_result(Self::qc_karyotypic_sex(dto.karyotypic_sex.as_deref()));
+        verrors.ok()
     }
 
      /// Check an individual bundle for errors.
     pub fn qc_bundle(&self, bundle: &IndividualBundle) -> Result<(), ValidationErrors> {
-        self.qc_data(&bundle.pmid, &bundle.title, &bundle.individual_id, &bundle.comment, &bundle.age_of_onset, &bundle.age_at_last_encounter, &bundle.deceased, &bundle.sex)
+        let mut verrors = ValidationErrors::new();
+        verrors.push_verr_result(self.qc_data(&bundle.pmid, &bundle.title, &bundle.individual_id, &bundle.comment, &bundle.age_of_onset, &bundle.age_at_last_encounter, &bundle.deceased, &bundle.sex));
+        verrors.push_result(self.qc_ancestry(bundle.ancestry()));
+        verrors.add_errors(self.qc_vital_status(&bundle.deceased, &bundle.age_of_onset, &bundle.age_at_last_encounter, 
+            bundle.age_at_death(), bundle.cause_of_death()));
+        verrors.push_result(Self::qc_karyotypic_sex(bundle.karyotypic_sex()));
+        verrors.ok()
+    }
+
+    /// Age at death and cause of death are optional; if present, they must be valid and the individual must be deceased,
+    /// i.e., they contradict a deceased column of "no" and require "yes" instead of "na".
+    /// The age at death cannot be earlier than the age of onset or the age at last encounter.
+    fn qc_vital_status(
+        &self, 
+        deceased: &str, 
+        age_of_onset: &str,
+        age_at_last_encounter: &str,
+        age_at_death: Option<&str>, 
+        cause_of_death: Option<&DiseaseDto>
+    ) -> Vec<String> {
+        let mut errors: Vec<String> = Vec::new();
+        if let Some(age) = age_at_death {
+            if let Err(e) = self.age_at_last_encounter.qc_data(age) {
+                errors.push(format!("Invalid age at death: {e}"));
+            }
+            if let Err(age_errors) = age_util::check_age_at_death(age_of_onset, age_at_last_encounter, age) {
+                errors.extend(age_errors);
+            }
+        }
+        if let Some(dx) = cause_of_death {
+            if let Err(e) = DupletItem::disease_id().qc_data(&dx.disease_id) {
+                errors.push(format!("Invalid cause of death: {e}"));
+            }
+        }
+        let vital_status: Vec<String> = age_at_death
+            .filter(|age| *age != "na")
+            .map(|age| format!("age at death '{age}'"))
+            .into_iter()
+            .chain(cause_of_death.map(|dx| format!("cause of death '{}'", dx.disease_id)))
+            .collect();
+        if !vital_status.is_empty() {
+            match deceased {
+                "yes" => {},
+                "no" => errors.push(format!("deceased: 'no' contradicts {}", vital_status.join(" and "))),
+                _ => errors.push(format!("deceased: '{deceased}' must be 'yes' if {} is given", vital_status.join(" and "))),
+            }
+        }
+        errors
+    }
+
+    /// The karyotypic sex is optional; if present, it must be "na" or a karyotype that can be exported
+    fn qc_karyotypic_sex(karyotypic_sex: Option<&str>) -> Result<(), String> {
+        match karyotypic_sex {
+            Some(value) => parse_karyotypic_sex(value).map(|_| ()).map_err(|e| e.to_string()),
+            None => Ok(()),
+        }
+    }
+
+    /// The ancestry column is optional; if present, its value must be "na" or a HANCESTRO ancestry category
+    fn qc_ancestry(&self, ancestry: Option<&str>) -> Result<(), String> {
+        match ancestry {
+            Some(value) => self.ancestry.qc_data(value),
+            None => Ok(()),
+        }
     }
 
     #[allow(clippy::too_many_arguments)]
@@ -95,4 +168,76 @@ impl IndividualHeader {
         }
     }
 
-}
\ No newline at end of file
+}
+
+
+/// Parse a karyotypic sex given as the sex chromosomes (e.g., XXY) or with the chromosome count (e.g., 47,XXY or 45,X).
+/// "na" is mapped to UNKNOWN_KARYOTYPE.
+pub fn parse_karyotypic_sex(value: &str) -> crate::error::Result<KaryotypicSex> {
+    if value == "na" {
+        return Ok(KaryotypicSex::UnknownKaryotype);
+    }
+    let sex_chromosomes = match value.split_once(',') {
+        Some((count, chromosomes)) => {
+            let chromosomes = chromosomes.trim();
+            // 44 autosomes plus the sex chromosomes
+            match count.trim().parse::<usize>() {
+                Ok(n) if n == 44 + chromosomes.len() => chromosomes,
+                _ => return Err(Error::karyotypic_sex_error(value)),
+            }
+        },
+        None => value.trim(),
+    };
+    let karyotype = match sex_chromosomes {
+        "X" => Some(KaryotypicSex::Xo),
+        other => KaryotypicSex::from_str_name(other),
+    };
+    karyotype.ok_or_else(|| Error::karyotypic_sex_error(value))
+}
+
+
+
+// region:    --- Tests
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    fn individual_dto(age_of_onset: &str, age_at_last_encounter: &str, deceased: &str) -> IndividualBundleDto {
+        IndividualBundleDto::new("PMID:29482508", "FOP case report", "individual A", "", 
+            age_of_onset, age_at_last_encounter, deceased, "M")
+    }
+
+    #[rstest]
+    #[case("no", Some("P20Y"), None, "deceased: 'no' contradicts age at death 'P20Y'")]
+    #[case("no", None, Some("OMIM:135100"), "deceased: 'no' contradicts cause of death 'OMIM:135100'")]
+    #[case("na", Some("P20Y"), None, "deceased: 'na' must be 'yes' if age at death 'P20Y' is given")]
+    #[case("na", Some("P20Y"), Some("OMIM:135100"), 
+        "deceased: 'na' must be 'yes' if age at death 'P20Y' and cause of death 'OMIM:135100' is given")]
+    #[case("yes", Some("P5Y"), None, "Age of onset 'P9Y' is later than age at death 'P5Y'")]
+    fn test_qc_vital_status_contradictions(
+        #[case] deceased: &str, 
+        #[case] age_at_death: Option<&str>, 
+        #[case] cause_of_death: Option<&str>,
+        #[case] expected: &str) {
+        let mut dto = individual_dto("P9Y", "na", deceased);
+        dto.age_at_death = age_at_death.map(str::to_string);
+        dto.cause_of_death = cause_of_death.map(|id| DiseaseDto::new(id, "Fibrodysplasia ossificans progressiva"));
+        let errors = IndividualHeader::new().qc_dto(dto).unwrap_err().errors();
+        assert_eq!(vec![expected.to_string()], errors);
+    }
+
+    #[rstest]
+    #[case("yes", Some("P20Y"))]
+    #[case("yes", Some("na"))]
+    #[case("no", Some("na"))]
+    #[case("na", None)]
+    fn test_qc_vital_status_consistent(#[case] deceased: &str, #[case] age_at_death: Option<&str>) {
+        let mut dto = individual_dto("P9Y", "P16Y", deceased);
+        dto.age_at_death = age_at_death.map(str::to_string);
+        assert!(IndividualHeader::new().qc_dto(dto).is_ok());
+    }
+}
+
+// endregion: --- Tests
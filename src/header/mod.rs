@@ -7,8 +7,10 @@
 
 
 mod allele_util;
+pub mod ancestry_util;
 pub mod duplet_item;
 pub mod disease_header;
 pub mod gene_variant_header;
+pub mod hgnc_util;
 pub mod hpo_term_duplet;
 pub mod individual_header;
\ No newline at end of file
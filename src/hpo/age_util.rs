@@ -1,4 +1,5 @@
-use std::{cell, collections::{HashMap, HashSet}};
+use std::{cell, collections::{HashMap, HashSet}, str::FromStr};
+use ontolius::TermId;
 use regex::Regex;
 use once_cell::sync::Lazy;
 
@@ -32,14 +33,20 @@ static ALLOWED_AGE_LABELS: Lazy<HashSet<String>> = Lazy::new(||{
         set
 });
 
+/// Map from the labels of the HPO Age of onset terms to their identifiers
+static AGE_TERM_D: Lazy<HashMap<String, String>> = Lazy::new(create_age_term_d);
+
 static ISO8601_RE: Lazy<Regex> = Lazy::new(|| {
     Regex::new(r"^P(?:(\d+)Y)?(?:(\d+)M)?(?:(\d+)D)?$").unwrap()
 });
 
 static GESTATIONAL_AGE_RE: Lazy<Regex> = Lazy::new(|| {
-    Regex::new(r"G\d+w[0-6]d").unwrap()
+    Regex::new(r"^G(\d{1,2})w([0-6])d$").unwrap()
 });
 
+/// Gestational ages with more weeks than this are not plausible (a pregnancy rarely goes beyond 42 weeks)
+pub const MAX_GESTATIONAL_AGE_WEEKS: u32 = 44;
+
 
 static ALLOWABLE_HPO_GENERIC_ENTRIES: Lazy<HashSet<String>> = Lazy::new(||{
     let mut set = HashSet::new();
@@ -50,15 +57,76 @@ static ALLOWABLE_HPO_GENERIC_ENTRIES: Lazy<HashSet<String>> = Lazy::new(||{
 });
 
 
+/// Severity modifiers of observed HPO terms (labels of the subclasses of HP:0012824 Severity)
+static SEVERITY_TERMS: Lazy<HashMap<&'static str, &'static str>> = Lazy::new(|| {
+    [
+        ("Borderline", "HP:0012827"),
+        ("Mild", "HP:0012825"),
+        ("Moderate", "HP:0012826"),
+        ("Severe", "HP:0012828"),
+        ("Profound", "HP:0012829"),
+    ]
+    .into_iter()
+    .collect()
+});
+
+/// An HPO cell in the richer syntax, e.g., `P3Y;severity=Severe;resolution=P5Y` for a feature with onset at
+/// three years, severe, and resolved at five years. The modifiers are optional and can be given in any order.
+#[derive(Clone, Debug, PartialEq)]
+pub struct HpoCell<'a> {
+    /// observed or an age of onset
+    pub value: &'a str,
+    /// Label of an HPO severity term, e.g., Mild
+    pub severity: Option<&'a str>,
+    /// Age at which the feature resolved
+    pub resolution: Option<&'a str>,
+}
+
+/// Get the identifier of an HPO severity term from its label, e.g., HP:0012825 for Mild
+pub fn severity_term_id(label: &str) -> Option<&'static str> {
+    SEVERITY_TERMS.get(label).copied()
+}
+
+/// The value of an HPO cell without the modifiers, e.g., P3Y for `P3Y;severity=Severe`
+pub fn hpo_cell_value(cell_value: &str) -> &str {
+    cell_value.split(';').next().unwrap_or(cell_value)
+}
+
+/// Parse an HPO cell with optional severity and resolution modifiers (see [`HpoCell`]).
+/// Modifiers can only be given for observed terms (observed or an age of onset), not for excluded or na.
+pub fn parse_hpo_cell(cell_value: &str) -> Result<HpoCell<'_>, String> {
+    let mut parts = cell_value.split(';');
+    let value = parts.next().unwrap_or(cell_value);
+    let mut cell = HpoCell { value, severity: None, resolution: None };
+    for modifier in parts {
+        match modifier.split_once('=') {
+            Some(("severity", label)) if SEVERITY_TERMS.contains_key(label) => cell.severity = Some(label),
+            Some(("resolution", age)) if age != "na" && is_valid_age_string(age) => cell.resolution = Some(age),
+            _ => {
+                let mut severities: Vec<&str> = SEVERITY_TERMS.keys().copied().collect();
+                severities.sort();
+                return Err(format!("Invalid modifier '{modifier}' in '{cell_value}' (allowed: severity={}, resolution=<age>)", 
+                    severities.join("|")));
+            }
+        }
+    }
+    if value != "observed" && (value == "na" || !is_valid_age_string(value)) {
+        return Err(format!("Invalid HPO entry '{cell_value}' (modifiers require observed or an age of onset)"));
+    }
+    Ok(cell)
+}
+
 /// TODO 
 /// In the existing templates, we have allowed "na" or empty cell for HPO entries that are na.
 /// In the future, we will allow only na. 
-/// For now, we need to allow empty strings as a valid vale
+/// For now, we need to allow empty strings as a valid vale.
+/// Observed entries may carry severity and resolution modifiers (see [`parse_hpo_cell`]).
 pub fn check_hpo_table_cell(cell_value: &str) -> Result<(), String> {
     if cell_value.is_empty() {
         Ok(())
-    }
-    else if ALLOWABLE_HPO_GENERIC_ENTRIES.contains(cell_value) || is_valid_age_string(cell_value) {
+    } else if cell_value.contains(';') {
+        parse_hpo_cell(cell_value).map(|_| ())
+    } else if ALLOWABLE_HPO_GENERIC_ENTRIES.contains(cell_value) || is_valid_age_string(cell_value) {
         Ok(())
     } else {
         Err(format!("Invalid age string '{cell_value}'"))
@@ -84,13 +152,156 @@ pub fn is_valid_age_string(cell_value: &str) -> bool {
         return true;
     }
 
-    if GESTATIONAL_AGE_RE.is_match(cell_value) {
+    if is_valid_gestational_age(cell_value) {
         return true;
     }
 
     false
 }
 
+/// Check a gestational age such as G12w3d (weeks and days, with 0-6 days and at most [`MAX_GESTATIONAL_AGE_WEEKS`] weeks)
+pub fn is_valid_gestational_age(cell_value: &str) -> bool {
+    parse_gestational_age(cell_value).is_some()
+}
+
+/// Get the weeks and days of a valid gestational age, e.g., (30, 2) for G30w2d
+pub fn parse_gestational_age(cell_value: &str) -> Option<(u32, u32)> {
+    let caps = GESTATIONAL_AGE_RE.captures(cell_value)?;
+    let weeks = caps[1].parse::<u32>().ok().filter(|&weeks| weeks <= MAX_GESTATIONAL_AGE_WEEKS)?;
+    let days = caps[2].parse::<u32>().ok()?;
+    Some((weeks, days))
+}
+
+/// Ages above this value are almost certainly data-entry errors (e.g., P160Y instead of P16Y)
+pub const MAX_PLAUSIBLE_AGE_YEARS: u32 = 120;
+
+/// Average length of a year in days (accounting for leap years)
+const DAYS_PER_YEAR: f64 = 365.25;
+
+/// Average length of a month in days (about 365.25 / 12)
+const DAYS_PER_MONTH: f64 = 30.44;
+
+/// Convert an ISO 8601 age (e.g., P3Y2M) to an approximate number of days, rounded to the nearest day
+/// (one year = 365.25 days, one month = 30.44 days).
+/// Returns None if the string is not an ISO 8601 age (including the bare string "P").
+pub fn iso_age_to_days(age: &str) -> Option<u32> {
+    let caps = ISO8601_RE.captures(age)?;
+    if caps.get(1).is_none() && caps.get(2).is_none() && caps.get(3).is_none() {
+        return None; // "P" alone is not an age
+    }
+    let years: u32 = caps.get(1).map_or(Some(0), |m| m.as_str().parse().ok())?;
+    let months: u32 = caps.get(2).map_or(Some(0), |m| m.as_str().parse().ok())?;
+    let days: u32 = caps.get(3).map_or(Some(0), |m| m.as_str().parse().ok())?;
+    let total_days = (f64::from(years) * DAYS_PER_YEAR + f64::from(months) * DAYS_PER_MONTH + f64::from(days)).round();
+    if total_days > f64::from(u32::MAX) {
+        return None;
+    }
+    Some(total_days as u32)
+}
+
+/// Check that an ISO 8601 age does not exceed [`MAX_PLAUSIBLE_AGE_YEARS`].
+/// Other strings (onset labels, gestational ages, na) are not checked here.
+/// This check is intended to produce a warning rather than an error, since extreme ages may be genuine.
+pub fn check_plausible_age(age: &str) -> Result<(), String> {
+    if ! ISO8601_RE.is_match(age) {
+        return Ok(());
+    }
+    match iso_age_to_days(age) {
+        Some(days) if f64::from(days) <= f64::from(MAX_PLAUSIBLE_AGE_YEARS) * DAYS_PER_YEAR => Ok(()),
+        _ => Err(format!("Implausible age '{age}' (more than {MAX_PLAUSIBLE_AGE_YEARS} years)")),
+    }
+}
+
+/// Same as [`iso_age_to_days`], as a floating-point number for age arithmetic. Returns None for strings that are
+/// not ISO 8601 ages, including gestational ages and HPO onset labels, which cannot be compared in this way.
+pub fn iso_age_in_days(age: &str) -> Option<f64> {
+    iso_age_to_days(age).map(f64::from)
+}
+
+/// Check that the age of onset is not later than the age at last encounter.
+/// The check is skipped (Ok) unless both values are ISO 8601 ages, e.g., for na, gestational ages, or onset labels.
+pub fn check_onset_before_last_encounter(age_of_onset: &str, age_at_last_encounter: &str) -> Result<(), String> {
+    match (iso_age_in_days(age_of_onset), iso_age_in_days(age_at_last_encounter)) {
+        (Some(onset), Some(last_encounter)) if onset > last_encounter => {
+            Err(format!("Age of onset '{age_of_onset}' is later than age at last encounter '{age_at_last_encounter}'"))
+        },
+        _ => Ok(()),
+    }
+}
+
+/// Check that the age at death is neither earlier than the age of onset nor earlier than the age at last encounter
+/// (an encounter cannot take place after death). As above, only ISO 8601 ages are compared.
+pub fn check_age_at_death(age_of_onset: &str, age_at_last_encounter: &str, age_at_death: &str) -> Result<(), Vec<String>> {
+    let mut errors: Vec<String> = Vec::new();
+    if let Some(death) = iso_age_in_days(age_at_death) {
+        if iso_age_in_days(age_of_onset).is_some_and(|onset| onset > death) {
+            errors.push(format!("Age of onset '{age_of_onset}' is later than age at death '{age_at_death}'"));
+        }
+        if iso_age_in_days(age_at_last_encounter).is_some_and(|last_encounter| last_encounter > death) {
+            errors.push(format!("Age at last encounter '{age_at_last_encounter}' is later than age at death '{age_at_death}'"));
+        }
+    }
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
+
+/// Check the onset of a phenotypic feature, which must be the label of an HPO Age of onset term (e.g., Infantile onset),
+/// an ISO 8601 age (e.g., P3Y2M), or a gestational age (e.g., G12w2d)
+pub fn check_onset_string(onset: &str) -> Result<(), String> {
+    if AGE_TERM_D.contains_key(onset) || iso_age_to_days(onset).is_some() || is_valid_gestational_age(onset) {
+        Ok(())
+    } else {
+        Err(format!("'{onset}' is neither an HPO onset term nor an ISO 8601 or gestational age"))
+    }
+}
+
+/// Get the identifier of an HPO onset term from its label, e.g., HP:0003593 for Infantile onset.
+/// Returns None if the label is not one of the HPO Age of onset terms.
+pub fn onset_label_to_term_id(label: &str) -> Option<TermId> {
+    AGE_TERM_D.get(label)
+        .and_then(|id| TermId::from_str(id).ok())
+}
+
+/// Map an age string to the label of an HPO onset category.
+/// HPO onset labels are returned unchanged. ISO 8601 ages (e.g., P3Y) and gestational ages (e.g., G12w2d)
+/// are assigned to the category whose age range contains them, e.g., P3Y is Childhood onset.
+/// Returns None for "na" and for strings that cannot be interpreted as an age.
+pub fn onset_category(age: &str) -> Option<String> {
+    if ALLOWED_AGE_LABELS.contains(age) {
+        return Some(age.to_string());
+    }
+    if ISO8601_RE.is_match(age) {
+        let total_days = iso_age_to_days(age)?;
+        let years = total_days / 365;
+        let label = if total_days == 0 {
+            "Congenital onset"
+        } else if total_days <= 28 {
+            "Neonatal onset"
+        } else if years < 1 {
+            "Infantile onset"
+        } else if years < 5 {
+            "Childhood onset"
+        } else if years < 16 {
+            "Juvenile onset"
+        } else if years < 40 {
+            "Young adult onset"
+        } else if years < 60 {
+            "Middle age onset"
+        } else {
+            "Late onset"
+        };
+        return Some(label.to_string());
+    }
+    if let Some((weeks, _days)) = parse_gestational_age(age) {
+        let label = if weeks < 8 { "Embryonal onset" } else { "Fetal onset" };
+        return Some(label.to_string());
+    }
+    None
+}
+
  /// Create a dictionary with all HPO Age of onset terms
  fn create_age_term_d() -> HashMap<String, String> {
     let mut age_term_d: HashMap<String, String> = HashMap::new();
@@ -118,4 +329,148 @@ pub fn is_valid_age_string(cell_value: &str) -> bool {
         age_term_d.insert(tup.1.to_string(), tup.0.to_string());
     }
     return age_term_d;
-}
\ No newline at end of file
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case("G30w2d", Some((30, 2)))]
+    #[case("G4w0d", Some((4, 0)))]
+    #[case("G30w7d", None)]
+    #[case("G99w0d", None)]
+    #[case("P30W", None)]
+    fn test_parse_gestational_age(#[case] input: &str, #[case] expected: Option<(u32, u32)>) {
+        assert_eq!(expected, parse_gestational_age(input), "Failed on input: {}", input);
+    }
+
+    #[rstest]
+    #[case("Infantile onset", true)]
+    #[case("P3Y2M", true)]
+    #[case("G12w2d", true)]
+    #[case("infantile onset", false)]
+    #[case("Infancy", false)]
+    #[case("P", false)]
+    #[case("3 years", false)]
+    fn test_check_onset_string(#[case] onset: &str, #[case] valid: bool) {
+        assert_eq!(valid, check_onset_string(onset).is_ok(), "Failed on input: {}", onset);
+    }
+
+    #[rstest]
+    #[case("P0D", Some("Congenital onset"))]
+    #[case("P10D", Some("Neonatal onset"))]
+    #[case("P4M", Some("Infantile onset"))]
+    #[case("P3Y", Some("Childhood onset"))]
+    #[case("P12Y6M", Some("Juvenile onset"))]
+    #[case("P25Y", Some("Young adult onset"))]
+    #[case("P45Y", Some("Middle age onset"))]
+    #[case("P70Y", Some("Late onset"))]
+    #[case("G6w2d", Some("Embryonal onset"))]
+    #[case("G20w0d", Some("Fetal onset"))]
+    #[case("G50w0d", None)]
+    #[case("Infantile onset", Some("Infantile onset"))]
+    #[case("na", None)]
+    #[case("P", None)]
+    fn test_onset_category(#[case] age: &str, #[case] expected: Option<&str>) {
+        assert_eq!(expected.map(|s| s.to_string()), onset_category(age));
+    }
+
+    #[rstest]
+    #[case("P16Y", Some(5844))]
+    #[case("P1Y2M3D", Some(429))]
+    // with 365 days per year, the leap days of 40 years (ten days) would be lost
+    #[case("P40Y", Some(14610))]
+    #[case("P3Y11M", Some(1431))]
+    #[case("P0D", Some(0))]
+    #[case("P", None)]
+    #[case("G20w0d", None)]
+    #[case("Infantile onset", None)]
+    fn test_iso_age_to_days(#[case] age: &str, #[case] expected: Option<u32>) {
+        assert_eq!(expected, iso_age_to_days(age));
+    }
+
+    #[rstest]
+    #[case("Infantile onset", Some("HP:0003593"))]
+    #[case("Third trimester onset", Some("HP:0034197"))]
+    #[case("Infantile", None)]
+    #[case("P3Y", None)]
+    fn test_onset_label_to_term_id(#[case] label: &str, #[case] expected: Option<&str>) {
+        assert_eq!(expected.map(|s| s.to_string()), onset_label_to_term_id(label).map(|tid| tid.to_string()));
+    }
+
+    #[rstest]
+    #[case("G40w0d", true)]
+    #[case("G6w2d", true)]
+    #[case("G12w7d", false)]
+    #[case("G60w0d", false)]
+    #[case("xxG12w3dyy", false)]
+    #[case("G12w3dyy", false)]
+    #[case("xxG12w3d", false)]
+    #[case("P3Y", true)]
+    #[case("Fetal onset", true)]
+    fn test_is_valid_age_string(#[case] age: &str, #[case] valid: bool) {
+        assert_eq!(valid, is_valid_age_string(age));
+    }
+
+    #[rstest]
+    #[case("P16Y", true)]
+    #[case("P120Y", true)]
+    #[case("P160Y", false)]
+    #[case("P1000M", true)]
+    #[case("P2000M", false)]
+    #[case("Late onset", true)]
+    #[case("na", true)]
+    fn test_check_plausible_age(#[case] age: &str, #[case] plausible: bool) {
+        assert_eq!(plausible, check_plausible_age(age).is_ok());
+    }
+
+    #[rstest]
+    #[case("P3Y", "P16Y", true)]
+    #[case("P16Y", "P16Y", true)]
+    #[case("P16Y", "P3Y", false)]
+    #[case("P13M", "P1Y", false)]
+    #[case("P1Y", "P1Y2D", true)]
+    #[case("G20w0d", "P1Y", true)]
+    #[case("Childhood onset", "P1Y", true)]
+    #[case("P16Y", "na", true)]
+    fn test_check_onset_before_last_encounter(#[case] onset: &str, #[case] last_encounter: &str, #[case] ok: bool) {
+        assert_eq!(ok, check_onset_before_last_encounter(onset, last_encounter).is_ok());
+    }
+
+    #[rstest]
+    #[case("observed", true)]
+    #[case("P3Y;severity=Severe;resolution=P5Y", true)]
+    #[case("observed;resolution=Childhood onset", true)]
+    #[case("Infantile onset;severity=Mild", true)]
+    #[case("observed;severity=mild", false)]
+    #[case("excluded;severity=Mild", false)]
+    #[case("na;severity=Mild", false)]
+    #[case("observed;resolution=na", false)]
+    #[case("observed;Mild", false)]
+    #[case("observed;", false)]
+    fn test_check_hpo_table_cell_modifiers(#[case] cell: &str, #[case] valid: bool) {
+        assert_eq!(valid, check_hpo_table_cell(cell).is_ok());
+    }
+
+    #[rstest]
+    fn test_parse_hpo_cell() {
+        let cell = parse_hpo_cell("P3Y;resolution=P5Y;severity=Severe").unwrap();
+        assert_eq!(HpoCell { value: "P3Y", severity: Some("Severe"), resolution: Some("P5Y") }, cell);
+        assert_eq!("P3Y", hpo_cell_value("P3Y;severity=Severe"));
+        assert_eq!(Some("HP:0012828"), severity_term_id("Severe"));
+    }
+
+    #[rstest]
+    #[case("P3Y", "P16Y", "P16Y", 0)]
+    #[case("P3Y", "na", "P2Y", 1)]
+    #[case("na", "P16Y", "P10Y", 1)]
+    #[case("P12Y", "P16Y", "P10Y", 2)]
+    #[case("Childhood onset", "P16Y", "Juvenile onset", 0)]
+    fn test_check_age_at_death(#[case] onset: &str, #[case] last_encounter: &str, #[case] death: &str, #[case] n_errors: usize) {
+        let n = check_age_at_death(onset, last_encounter, death).err().map_or(0, |errors| errors.len());
+        assert_eq!(n_errors, n);
+    }
+}
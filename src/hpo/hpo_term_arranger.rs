@@ -5,16 +5,31 @@
 //! Objects of this class are created to perform a DSF to find a good way of arranging HPO term columns
 //! We do not need to take ownership of the ontology, therefore indicate explicit lifetime
 use std::{collections::HashSet, str::FromStr, sync::Arc};
-use crate::{dto::validation_errors::ValidationErrors, error::{self, Error, Result}};
+use crate::{dto::validation_errors::ValidationErrors, error::{self, Error, Result}, hpo::hpo_util};
 
 use ontolius::{
-    common::hpo::PHENOTYPIC_ABNORMALITY, ontology::{csr::FullCsrOntology, HierarchyQueries, HierarchyWalks, OntologyTerms}, term::{simple::{SimpleMinimalTerm, SimpleTerm}, Term}, TermId
+    common::hpo::PHENOTYPIC_ABNORMALITY, ontology::{csr::FullCsrOntology, HierarchyQueries, HierarchyWalks, OntologyTerms}, term::{simple::{SimpleMinimalTerm, SimpleTerm}, MinimalTerm, Term}, TermId
 };
 
+/// Strategy used to order the HPO columns of a template
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ArrangementStrategy {
+    /// DFS over the phenotypic abnormality subhierarchy, with all Neoplasm terms kept together at the end
+    #[default]
+    DfsWithNeoplasm,
+    /// DFS over the phenotypic abnormality subhierarchy (by organ system) without special treatment of Neoplasm
+    PlainDfs,
+    /// Alphabetical order of the term labels
+    AlphabeticalByLabel,
+}
+
 /// Arranges HPO terms into a meaningful order for curation using DFS.
 pub struct HpoTermArranger {
     hpo: Arc<FullCsrOntology>,
     hpo_curation_term_id_set: HashSet<TermId>,
+    /// Phenotypic abnormality and its descendants (shared cache, see [`hpo_util::phenotypic_abnormality_descendants`])
+    phenotypic_abnormality_set: Arc<HashSet<TermId>>,
+    strategy: ArrangementStrategy,
     errors: Vec<String>,
 }
 
@@ -27,13 +42,29 @@ impl HpoTermArranger {
     ///
     /// * `ontology` - reference to an Ontolius HPO ontology.
     pub fn new(ontology: Arc<FullCsrOntology>) -> Self {
+        let phenotypic_abnormality_set = hpo_util::phenotypic_abnormality_descendants(&ontology);
         Self {
             hpo: ontology,
             hpo_curation_term_id_set: HashSet::new(),
+            phenotypic_abnormality_set,
+            strategy: ArrangementStrategy::default(),
             errors: Vec::new(),
         }
     }
 
+    /// Problems found by the last call to [`Self::arrange_term_ids`] or [`Self::arrange_terms`], in particular
+    /// input terms that are not in the phenotypic abnormality subhierarchy (e.g., onset or mode-of-inheritance terms)
+    /// and are therefore missing from the arranged list
+    pub fn errors(&self) -> &[String] {
+        &self.errors
+    }
+
+    /// Choose how the terms are ordered (the default is [`ArrangementStrategy::DfsWithNeoplasm`])
+    pub fn with_strategy(mut self, strategy: ArrangementStrategy) -> Self {
+        self.strategy = strategy;
+        self
+    }
+
     /// Perform a depth-first search to arrange the terms for curation into an order that
     /// tends to keep related terms together
     /// We only store the terms we are interested in in ordered_tids.
@@ -49,10 +80,7 @@ impl HpoTermArranger {
 
         visited.insert(start_tid.clone());
 
-        if !self
-            .hpo
-            .is_equal_or_descendant_of(start_tid, &PHENOTYPIC_ABNORMALITY)
-        {
+        if !self.phenotypic_abnormality_set.contains(start_tid) {
             self.errors.push(format!(
                 "TermId {} does not belong to phenotypic abnormality subhierarchy",
                 start_tid
@@ -70,9 +98,9 @@ impl HpoTermArranger {
         }
     }
 
-    /// Arrange the terms chosen for the pyphetools curation template using Depth-First Search (DFS)
+    /// Arrange the terms chosen for the pyphetools curation template according to the strategy of this arranger.
     ///
-    /// Perform separate DFS for Neoplasm to arrange all neoplasm terms together
+    /// By default, we use Depth-First Search (DFS) and perform a separate DFS for Neoplasm to arrange all neoplasm terms together
     ///
     /// # Arguments
     ///
@@ -84,19 +112,45 @@ impl HpoTermArranger {
     pub fn arrange_term_ids(&mut self, hpo_terms_for_curation: &Vec<TermId>) 
     -> Vec<TermId> {
         self.hpo_curation_term_id_set.clear();
+        self.errors.clear();
         for smt in hpo_terms_for_curation {
             self.hpo_curation_term_id_set.insert(smt.clone());
         }
 
-        let neoplasm = TermId::from_str("HP:0002664").unwrap();
         let mut visited: HashSet<TermId> = HashSet::new();
         let mut ordered_term_id_list: Vec<TermId> = Vec::new();
-        let mut neoplasm_terms = Vec::new();
-        // First get any Neoplasm terms
-        self.dfs(&neoplasm, &mut visited, &mut neoplasm_terms);
-        // then arrange the remaining terms according to organ system
-        self.dfs(&PHENOTYPIC_ABNORMALITY, &mut visited, &mut ordered_term_id_list);
-        ordered_term_id_list.extend(neoplasm_terms);
+        match self.strategy {
+            ArrangementStrategy::DfsWithNeoplasm => {
+                let neoplasm = TermId::from_str("HP:0002664").unwrap();
+                let mut neoplasm_terms = Vec::new();
+                // First get any Neoplasm terms
+                self.dfs(&neoplasm, &mut visited, &mut neoplasm_terms);
+                // then arrange the remaining terms according to organ system
+                self.dfs(&PHENOTYPIC_ABNORMALITY, &mut visited, &mut ordered_term_id_list);
+                ordered_term_id_list.extend(neoplasm_terms);
+            },
+            ArrangementStrategy::PlainDfs => {
+                self.dfs(&PHENOTYPIC_ABNORMALITY, &mut visited, &mut ordered_term_id_list);
+            },
+            ArrangementStrategy::AlphabeticalByLabel => {
+                let mut labelled: Vec<(String, TermId)> = Vec::with_capacity(self.hpo_curation_term_id_set.len());
+                // terms outside of the phenotypic abnormality subhierarchy are skipped as with the DFS strategies
+                for tid in self.hpo_curation_term_id_set.intersection(&self.phenotypic_abnormality_set) {
+                    match self.hpo.term_by_id(tid) {
+                        Some(term) => labelled.push((term.name().to_lowercase(), tid.clone())),
+                        None => self.errors.push(format!("Could not find label for {}", tid)),
+                    }
+                }
+                labelled.sort();
+                ordered_term_id_list.extend(labelled.into_iter().map(|(_, tid)| tid));
+            },
+        }
+        for tid in hpo_terms_for_curation {
+            if ! ordered_term_id_list.contains(tid) && ! self.phenotypic_abnormality_set.contains(tid) {
+                self.errors.push(format!(
+                    "TermId {} does not belong to phenotypic abnormality subhierarchy and was skipped", tid));
+            }
+        }
         ordered_term_id_list
     }
 
@@ -105,6 +159,7 @@ impl HpoTermArranger {
     -> std::result::Result<Vec<SimpleTerm>, ValidationErrors> {
         let arranged_tids = self.arrange_term_ids(hpo_terms_for_curation);
         let mut verrs = ValidationErrors::new();
+        verrs.add_errors(self.errors.clone());
         let mut arranged_terms: Vec<SimpleTerm> = Vec::new();
         for tid in arranged_tids {
             match self.hpo.term_by_id(&tid) {
@@ -136,6 +191,53 @@ mod tests {
     };
 
     use super::*;
+    use flate2::bufread::GzDecoder;
+    use rstest::rstest;
+    use std::{fs::File, io::BufReader};
+
+    #[rstest]
+    #[case(ArrangementStrategy::DfsWithNeoplasm)]
+    #[case(ArrangementStrategy::PlainDfs)]
+    #[case(ArrangementStrategy::AlphabeticalByLabel)]
+    fn test_arrangement_strategy(#[case] strategy: ArrangementStrategy) {
+        let reader = GzDecoder::new(BufReader::new(File::open("resources/hp.v2025-03-03.json.gz").unwrap()));
+        let loader = OntologyLoaderBuilder::new().obographs_parser().build();
+        let hpo: FullCsrOntology = loader.load_from_read(reader).unwrap();
+        let seizure = TermId::from_str("HP:0001250").unwrap();
+        let pain = TermId::from_str("HP:0012531").unwrap();
+        let hallux_valgus = TermId::from_str("HP:0001822").unwrap();
+        let renal_cell_carcinoma = TermId::from_str("HP:0005584").unwrap();
+        let term_list = vec![seizure.clone(), renal_cell_carcinoma.clone(), pain.clone(), hallux_valgus.clone()];
+        let mut arranger = HpoTermArranger::new(Arc::new(hpo)).with_strategy(strategy);
+        let ordered = arranger.arrange_term_ids(&term_list);
+        assert_eq!(term_list.len(), ordered.len());
+        match strategy {
+            ArrangementStrategy::DfsWithNeoplasm => assert_eq!(Some(&renal_cell_carcinoma), ordered.last()),
+            ArrangementStrategy::PlainDfs => assert!(term_list.iter().all(|tid| ordered.contains(tid))),
+            ArrangementStrategy::AlphabeticalByLabel => {
+                assert_eq!(vec![hallux_valgus, pain, renal_cell_carcinoma, seizure], ordered);
+            },
+        }
+    }
+
+    #[rstest]
+    #[case(ArrangementStrategy::DfsWithNeoplasm)]
+    #[case(ArrangementStrategy::PlainDfs)]
+    #[case(ArrangementStrategy::AlphabeticalByLabel)]
+    fn test_skipped_terms_are_reported(#[case] strategy: ArrangementStrategy) {
+        let reader = GzDecoder::new(BufReader::new(File::open("resources/hp.v2025-03-03.json.gz").unwrap()));
+        let loader = OntologyLoaderBuilder::new().obographs_parser().build();
+        let hpo: FullCsrOntology = loader.load_from_read(reader).unwrap();
+        let seizure = TermId::from_str("HP:0001250").unwrap();
+        let infantile_onset = TermId::from_str("HP:0003593").unwrap();
+        let mut arranger = HpoTermArranger::new(Arc::new(hpo)).with_strategy(strategy);
+        let ordered = arranger.arrange_term_ids(&vec![seizure.clone(), infantile_onset]);
+        assert_eq!(vec![seizure.clone()], ordered);
+        assert_eq!(1, arranger.errors().len());
+        assert!(arranger.errors()[0].contains("HP:0003593"));
+        assert!(arranger.arrange_terms(&vec![seizure.clone()]).is_ok());
+        assert!(arranger.errors().is_empty());
+    }
 
     #[test]
     #[ignore]
@@ -4,15 +4,47 @@ use crate::dto::hpo_term_dto::{self, HpoTermDto};
 use crate::dto::validation_errors::ValidationErrors;
 use crate::error::{self, Error, Result};
 use crate::header::hpo_term_duplet::HpoTermDuplet;
+use crate::hpo::age_util;
 use ontolius::io::OntologyLoaderBuilder;
 use ontolius::ontology::csr::{FullCsrOntology, MinimalCsrOntology};
 use ontolius::ontology::OntologyTerms;
 use ontolius::term::simple::SimpleTerm;
 use ontolius::term::MinimalTerm;
 use ontolius::TermId;
-use std::collections::HashMap;
+use ontolius::common::hpo::PHENOTYPIC_ABNORMALITY;
+use ontolius::ontology::HierarchyWalks;
+use ontolius::ontology::HierarchyQueries;
+use once_cell::sync::Lazy;
+use std::collections::{HashMap, HashSet};
 use std::str::FromStr;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex, Weak};
+
+/// Onset (HP:0003674), the root of the HPO Age of onset terms
+static ONSET: Lazy<TermId> = Lazy::new(|| TermId::from_str("HP:0003674").unwrap());
+
+/// Descendants of Phenotypic abnormality (including the term itself), computed once per ontology.
+/// We hold a weak reference to the ontology so that the cache does not keep it alive and is not reused
+/// for a different ontology that happens to be allocated at the same address.
+static PHENOTYPIC_ABNORMALITY_CACHE: Lazy<Mutex<Vec<(Weak<FullCsrOntology>, Arc<HashSet<TermId>>)>>> = 
+    Lazy::new(|| Mutex::new(Vec::new()));
+
+/// Get the set of Phenotypic abnormality (HP:0000118) and all of its descendants. The set is computed the
+/// first time it is requested for an ontology and then shared, so that membership checks do not need to
+/// walk the hierarchy each time.
+pub fn phenotypic_abnormality_descendants(hpo: &Arc<FullCsrOntology>) -> Arc<HashSet<TermId>> {
+    let mut cache = PHENOTYPIC_ABNORMALITY_CACHE.lock().unwrap_or_else(|e| e.into_inner());
+    cache.retain(|(weak, _)| weak.strong_count() > 0);
+    if let Some((_, descendants)) = cache.iter()
+        .find(|(weak, _)| weak.upgrade().is_some_and(|cached| Arc::ptr_eq(&cached, hpo))) {
+        return descendants.clone();
+    }
+    let phenotypic_abnormality: &TermId = &PHENOTYPIC_ABNORMALITY;
+    let mut descendants: HashSet<TermId> = hpo.iter_descendant_ids(phenotypic_abnormality).cloned().collect();
+    descendants.insert(phenotypic_abnormality.clone());
+    let descendants = Arc::new(descendants);
+    cache.push((Arc::downgrade(hpo), descendants.clone()));
+    descendants
+}
 
 
 
@@ -116,6 +148,21 @@ impl HpoUtil {
                     ),
                 });
             }
+            self.check_onset(dto)?;
+        }
+        Ok(())
+    }
+
+    /// Check the onset of a feature (if any). Onset labels must denote a descendant of Onset (HP:0003674) in the
+    /// ontology, e.g., Infantile onset; other onsets must be well-formed ISO 8601 or gestational ages.
+    pub fn check_onset(&self, dto: &HpoTermDto) -> Result<()> {
+        dto.check_onset()?;
+        if let Some(onset_tid) = dto.onset().ok().and_then(|onset| age_util::onset_label_to_term_id(&onset)) {
+            if !self.hpo.is_descendant_of(&onset_tid, &ONSET) {
+                return Err(Error::AgeParseError {
+                    msg: format!("Invalid onset for {} ({}): {} is not an Onset term", dto.label(), dto.term_id(), onset_tid)
+                });
+            }
         }
         Ok(())
     }
@@ -128,6 +175,10 @@ impl HpoUtil {
             match TermId::from_str(&row2) {
                 Ok(tid) => {
                     match self.hpo.term_by_id(&tid) {
+                        Some(term) if term.identifier() != &tid => {
+                            let obsolete = Error::ObsoleteTermId { id: tid.to_string(), replacement: term.identifier().to_string() };
+                            verrs.push_str(obsolete.to_string());
+                        },
                         Some(term) => {
                             if term.name() != row1 {
                                 verrs.push_str(format!("Expected label '{}' but got '{}' for TermId '{}'",
@@ -147,4 +198,88 @@ impl HpoUtil {
         verrs.ok()
     }
 
+    /// Replace obsolete (alternate) HPO ids in the two header rows of a template matrix (labels in the first row,
+    /// ids in the second) by the current primary id and label. Returns the (obsolete id, primary id) pairs that were remapped.
+    pub fn remap_obsolete_ids(&self, matrix: &mut [Vec<String>]) -> Vec<(String, String)> {
+        let mut remapped: Vec<(String, String)> = Vec::new();
+        let [labels, ids, ..] = matrix else {
+            return remapped;
+        };
+        for (label, id) in labels.iter_mut().zip(ids.iter_mut()) {
+            let Ok(tid) = TermId::from_str(id) else {
+                continue;
+            };
+            if let Some(term) = self.hpo.term_by_id(&tid) {
+                if term.identifier() != &tid {
+                    let primary = term.identifier().to_string();
+                    remapped.push((id.clone(), primary.clone()));
+                    *id = primary;
+                    *label = term.name().to_string();
+                }
+            }
+        }
+        remapped
+    }
+
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::{fs::File, io::BufReader};
+
+    use flate2::bufread::GzDecoder;
+
+    use super::*;
+
+    #[test]
+    fn test_phenotypic_abnormality_descendants() {
+        let path = "resources/hp.v2025-03-03.json.gz";
+        let reader = GzDecoder::new(BufReader::new(File::open(path).unwrap()));
+        let loader = OntologyLoaderBuilder::new().obographs_parser().build();
+        let hpo: Arc<FullCsrOntology> = Arc::new(loader.load_from_read(reader).unwrap());
+        let descendants = phenotypic_abnormality_descendants(&hpo);
+        let phenotypic_abnormality: &TermId = &PHENOTYPIC_ABNORMALITY;
+        assert!(descendants.contains(phenotypic_abnormality));
+        // Short thumb
+        assert!(descendants.contains(&TermId::from_str("HP:0009778").unwrap()));
+        // Autosomal dominant inheritance is not a phenotypic abnormality
+        assert!(!descendants.contains(&TermId::from_str("HP:0000006").unwrap()));
+        // The second call returns the cached set
+        assert!(Arc::ptr_eq(&descendants, &phenotypic_abnormality_descendants(&hpo)));
+    }
+
+    #[test]
+    fn test_check_onset() {
+        let path = "resources/hp.v2025-03-03.json.gz";
+        let reader = GzDecoder::new(BufReader::new(File::open(path).unwrap()));
+        let loader = OntologyLoaderBuilder::new().obographs_parser().build();
+        let hpo_util = HpoUtil::new(Arc::new(loader.load_from_read(reader).unwrap()));
+        for onset in ["Infantile onset", "Late first trimester onset", "P2Y", "G20w1d"] {
+            let dto = HpoTermDto::new("HP:0001250", "Seizure", onset);
+            assert!(hpo_util.check_onset(&dto).is_ok(), "Failed on onset: {}", onset);
+        }
+        let dto = HpoTermDto::new("HP:0001250", "Seizure", "Onset in infancy");
+        assert!(matches!(hpo_util.check_onset(&dto), Err(Error::AgeParseError { .. })));
+    }
+
+    #[test]
+    fn test_obsolete_ids() {
+        let path = "resources/hp.v2025-03-03.json.gz";
+        let reader = GzDecoder::new(BufReader::new(File::open(path).unwrap()));
+        let loader = OntologyLoaderBuilder::new().obographs_parser().build();
+        let hpo_util = HpoUtil::new(Arc::new(loader.load_from_read(reader).unwrap()));
+        // HP:0004715 is an alternate id of Multicystic kidney dysplasia (HP:0000003)
+        let duplets = vec![HpoTermDuplet::new("Short thumb", "HP:0009778"), HpoTermDuplet::new("Multicystic kidney dysplasia", "HP:0004715")];
+        let errors = hpo_util.check_hpo_duplets(&duplets).unwrap_err().errors();
+        assert_eq!(vec!["Obsolete HPO TermId: HP:0004715; replace with HP:0000003.".to_string()], errors);
+        let mut matrix = vec![
+            vec!["HPO".to_string(), "Short thumb".to_string(), "Multicystic kidney dysplasia".to_string()],
+            vec!["na".to_string(), "HP:0009778".to_string(), "HP:0004715".to_string()],
+        ];
+        let remapped = hpo_util.remap_obsolete_ids(&mut matrix);
+        assert_eq!(vec![("HP:0004715".to_string(), "HP:0000003".to_string())], remapped);
+        assert_eq!("HP:0000003", matrix[1][2]);
+        assert_eq!("HP:0009778", matrix[1][1]);
+    }
 }
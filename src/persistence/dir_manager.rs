@@ -1,7 +1,7 @@
 
 use std::{collections::HashMap, fs::{self, File, OpenOptions}, path::{Path, PathBuf}, sync::{Arc, Mutex}};
 
-use crate::{dto::variant_dto::{VariantDto, VariantListDto}, variant::{hgvs_variant::HgvsVariant, structural_variant::StructuralVariant, variant_manager::VariantManager, variant_validator::VariantValidator}};
+use crate::{dto::variant_dto::{VariantDto, VariantListDto, VariantValidationReport}, variant::{hgvs_variant::HgvsVariant, structural_variant::StructuralVariant, variant_manager::VariantManager, variant_validator::{OfflineVariantApi, VariantApi, VariantValidator}}};
 
 use crate::variant::structural_variant::DELETION as DEL;
 use crate::variant::structural_variant::DUPLICATION as DUP;
@@ -36,6 +36,14 @@ impl DirManager {
         })
     }
 
+    /// Open or create the directory as with [`Self::new`], but encode HGVS variants with `variant_api`
+    /// (e.g., an [`OfflineVariantApi`] with canned variants) instead of querying VariantValidator
+    pub fn with_variant_api<P: AsRef<Path>>(dir_path: P, variant_api: Box<dyn VariantApi>) -> Result<Self, String> {
+        let mut dir_manager = Self::new(dir_path)?;
+        dir_manager.variant_manager = VariantManager::with_variant_api(&dir_manager.cache_dir_path, variant_api);
+        Ok(dir_manager)
+    }
+
     /// Check an HGVS or structural variant.
     /// If we validate, we return the same DTO (except that the validated flag is set to true)
     /// The cause of any error is returned as a string.
@@ -50,6 +58,21 @@ impl DirManager {
         self.variant_manager.validate_variant_dto_list(variant_dto_list)
     }
 
+    /// Validate a list of variants and return a report with the evaluated variants and the reasons for any failures
+    pub fn validate_variant_dto_list_with_report(&mut self, variant_dto_list: Vec<VariantDto>) -> VariantValidationReport {
+        self.variant_manager.validate_variant_dto_list_with_report(variant_dto_list)
+    }
+
+    /// Validate a batch of variants concurrently (at most `max_concurrency` requests at a time).
+    /// The evaluated DTOs of the report are in input order; failures do not abort the batch and are listed with the reason.
+    pub fn validate_variant_dto_list_concurrent(
+        &mut self, 
+        variant_dto_list: Vec<VariantDto>, 
+        max_concurrency: usize
+    ) -> VariantValidationReport {
+        self.variant_manager.validate_variant_dto_list_concurrent(variant_dto_list, max_concurrency)
+    }
+
     pub fn get_cohort_dir(&self) -> PathBuf {
         self.cache_dir_path.clone()
     }
@@ -65,6 +88,16 @@ impl DirManager {
     pub fn get_structural_dict(&self) -> &HashMap<String, StructuralVariant> {
         self.variant_manager.get_structural_dict()
     }
+
+    /// Genomic HGVS (e.g., NC_000015.10:g.48411364C>A) of a cached allele such as c.8242G>T.
+    /// Returns None if the allele has not been validated or if VariantValidator did not return a genomic description.
+    pub fn genomic_hgvs(&self, allele: &str) -> Option<String> {
+        self.variant_manager
+            .get_hgvs_dict()
+            .get(allele)
+            .and_then(|hgvs| hgvs.g_hgvs())
+            .map(str::to_string)
+    }
 }
 
 
@@ -76,7 +109,17 @@ mod tests {
      
 
     use super::*;
-
+    use crate::variant::vcf_var::VcfVar;
+    use rstest::{fixture, rstest};
+
+    /// Offline VariantValidator that knows the FBN1 variant NM_000138.5:c.8242G>T
+    #[fixture]
+    fn fbn1_variant_api() -> OfflineVariantApi {
+        let hgvs = HgvsVariant::new("hg38".to_string(), VcfVar::new("chr15", 48411364, "C", "A"), 
+            Some("FBN1".to_string()), Some("HGNC:3603".to_string()), Some("c.8242G>T".to_string()), 
+            Some("NM_000138.5:c.8242G>T".to_string()), Some("NC_000015.10:g.48411364C>A".to_string()), None, None);
+        OfflineVariantApi::new().with_variant("NM_000138.5", "c.8242G>T", hgvs)
+    }
 
     #[test]
     #[ignore]
@@ -92,7 +135,52 @@ mod tests {
         let updated_dto = updated_result.unwrap();
         assert!(updated_dto.validated());
     }
-    
+
+    #[rstest]
+    pub fn test_validate_variant_offline(fbn1_variant_api: OfflineVariantApi) {
+        let dir = std::env::temp_dir().join(format!("rphetools-offline-{}", std::process::id()));
+        let mut dirman = DirManager::with_variant_api(&dir, Box::new(fbn1_variant_api)).unwrap();
+        dirman.variant_manager.clear_cache();
+        let var_dto = VariantDto::new_hgvs("c.8242G>T", "NM_000138.5", "HGNC:3603", "FBN1");
+        assert!(dirman.validate_variant(&var_dto).unwrap().validated());
+        assert_eq!(Some("NC_000015.10:g.48411364C>A".to_string()), dirman.genomic_hgvs("c.8242G>T"));
+        let wrong_gene = VariantDto::new_hgvs("c.8242G>T", "NM_000138.5", "HGNC:171", "ACVR1");
+        dirman.variant_manager.clear_cache();
+        assert!(dirman.validate_variant(&wrong_gene).is_err());
+        let unknown = VariantDto::new_hgvs("c.1A>G", "NM_000138.5", "HGNC:3603", "FBN1");
+        assert!(dirman.validate_variant(&unknown).is_err());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[rstest]
+    pub fn test_validate_variant_dto_list_with_report(fbn1_variant_api: OfflineVariantApi) {
+        let dir = std::env::temp_dir().join(format!("rphetools-report-{}", std::process::id()));
+        let mut dirman = DirManager::with_variant_api(&dir, Box::new(fbn1_variant_api)).unwrap();
+        dirman.variant_manager.clear_cache();
+        let valid = VariantDto::new_hgvs("c.8242G>T", "NM_000138.5", "HGNC:3603", "FBN1");
+        let unknown = VariantDto::new_hgvs("c.1A>G", "NM_000138.5", "HGNC:3603", "FBN1");
+        let report = dirman.validate_variant_dto_list_with_report(vec![valid.clone(), unknown, valid]);
+        assert_eq!(3, report.total);
+        // the second copy of the valid variant is taken from the cache
+        assert_eq!(2, report.validated);
+        assert_eq!(1, report.failed.len());
+        assert_eq!("c.1A>G", report.failed[0].variant_string);
+        assert_eq!("NM_000138.5", report.failed[0].transcript);
+        assert_eq!("No offline variant for NM_000138.5:c.1A>G", report.failed[0].reason);
+        assert_eq!(3, report.variant_dto_list.len());
+        assert_eq!(2, report.variant_dto_list.iter().filter(|dto| dto.validated()).count());
+        assert!(report.cache_error.is_none());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    pub fn test_genomic_hgvs_not_cached() {
+        let dir = std::env::temp_dir().join(format!("rphetools-genomic-{}", std::process::id()));
+        let dirman = DirManager::new(&dir).unwrap();
+        assert_eq!(None, dirman.genomic_hgvs("c.8242G>T"));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
 }
 
 // endregion: --- Tests
\ No newline at end of file
@@ -1,4 +1,5 @@
 
 
 pub mod ppkt_exporter;
+pub mod ppkt_importer;
 pub mod ppkt_row;
@@ -1,22 +1,29 @@
 //! Module to export GA4GH Phenopackets from the information in the template.
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::process::id;
 use std::sync::Arc;
 
+use ontolius::term::simple::SimpleMinimalTerm;
+use ontolius::term::MinimalTerm;
+use ontolius::Identified;
 use phenopacket_tools::builders::time_elements::time_element_from_str;
 use phenopackets::ga4gh::vrsatile::v1::{Expression, GeneDescriptor, MoleculeContext, VariationDescriptor, VcfRecord};
 use phenopackets::schema::v2::core::genomic_interpretation::{Call, InterpretationStatus};
 use phenopackets::schema::v2::core::interpretation::ProgressStatus;
-use phenopackets::schema::v2::core::{Diagnosis, KaryotypicSex, OntologyClass};
+use phenopackets::schema::v2::core::{Diagnosis, GestationalAge, KaryotypicSex, OntologyClass};
 use phenopackets::schema::v2::core::vital_status::Status;
+use phenopackets::schema::v2::core::time_element;
 use phenopackets::schema::v2::core::{AcmgPathogenicityClassification, Disease, ExternalReference, GenomicInterpretation, Individual, Interpretation, MetaData, PhenotypicFeature, Sex, TherapeuticActionability, TimeElement, VariantInterpretation, VitalStatus};
 use phenopackets::schema::v2::Phenopacket;
 use prost_types::value;
 use regex::Regex;
 use crate::dto::template_dto::GeneVariantBundleDto;
 use crate::error::{self, Error, Result};
-use crate::hpo::hpo_util;
+use crate::header::ancestry_util;
+use crate::header::individual_header;
+use crate::header::hpo_term_duplet::HpoTermDuplet;
+use crate::hpo::{age_util, hpo_util};
 use crate::template::gene_variant_bundle::GeneVariantBundle;
 use crate::variant::hgvs_variant::HgvsVariant;
 use crate::variant::structural_variant::StructuralVariant;
@@ -32,6 +39,91 @@ const DEFAULT_HGNC_VERSION: &str =  "06/01/25";
 const DEFAULT_OMIM_VERSION: &str =  "06/01/25";
 const DEFAULT_SEQUENCE_ONTOLOGY_VERSION: &str =  "2024-11-18";
 const DEFAULT_GENO_VERSION: &str =  "2023-10-08";
+/// Creator of the MetaData if no curator was set
+const DEFAULT_CREATED_BY: &str = "Earnest B. Biocurator";
+
+/// Where the data of an exported phenopacket came from, e.g., the Excel template that was loaded
+#[derive(Clone, Debug)]
+pub struct TemplateProvenance {
+    /// Path to the source template file
+    source_path: String,
+    /// Time at which the template was loaded (RFC 3339)
+    loaded_at: String,
+}
+
+impl TemplateProvenance {
+    pub fn new(source_path: impl Into<String>, loaded_at: impl Into<String>) -> Self {
+        Self { source_path: source_path.into(), loaded_at: loaded_at.into() }
+    }
+
+    /// Record that the template at `source_path` was loaded just now
+    pub fn now(source_path: impl Into<String>) -> Self {
+        Self::new(source_path, chrono::Utc::now().to_rfc3339())
+    }
+
+    pub fn source_path(&self) -> &str {
+        &self.source_path
+    }
+
+    pub fn loaded_at(&self) -> &str {
+        &self.loaded_at
+    }
+
+    /// Represent the provenance as a MetaData external reference
+    pub fn to_external_reference(&self) -> ExternalReference {
+        let file_name = std::path::Path::new(&self.source_path)
+            .file_name()
+            .map(|f| f.to_string_lossy().to_string())
+            .unwrap_or_else(|| self.source_path.clone());
+        ExternalReference { 
+            id: file_name, 
+            reference: self.source_path.clone(), 
+            description: format!("Source template loaded at {}", self.loaded_at) 
+        }
+    }
+}
+
+/// Settings of an export that are not part of the template: the curator, the provenance of the template,
+/// and the X-linked genes. The default settings have no curator, in which case the MetaData is created by a placeholder.
+#[derive(Clone, Debug, Default)]
+pub struct ExportSettings {
+    /// ORCID of the curator, e.g., 0000-0002-0736-9199 or ORCID:0000-0002-0736-9199
+    creator_orcid: Option<String>,
+    /// Human-readable name of the curator; if not set, the ORCID is used as the creator
+    creator_name: Option<String>,
+    provenance: Option<TemplateProvenance>,
+    x_linked_genes: HashSet<String>,
+}
+
+impl ExportSettings {
+    pub fn new(creator_orcid: impl Into<String>) -> Self {
+        Self { 
+            creator_orcid: Some(creator_orcid.into()), 
+            creator_name: None, 
+            provenance: None, 
+            x_linked_genes: HashSet::new() 
+        }
+    }
+
+    pub fn with_creator_name(mut self, creator_name: Option<String>) -> Self {
+        self.creator_name = creator_name;
+        self
+    }
+
+    pub fn with_provenance(mut self, provenance: Option<TemplateProvenance>) -> Self {
+        self.provenance = provenance;
+        self
+    }
+
+    pub fn with_x_linked_genes(mut self, x_linked_genes: HashSet<String>) -> Self {
+        self.x_linked_genes = x_linked_genes;
+        self
+    }
+
+    pub fn creator_orcid(&self) -> Option<&str> {
+        self.creator_orcid.as_deref()
+    }
+}
 
 pub struct PpktExporter {
     hpo_version: String,
@@ -39,7 +131,16 @@ pub struct PpktExporter {
     geno_version: String,
     omim_version: String,
     hgnc_version: String,
-    orcid_id: String,
+    /// ORCID of the curator, e.g., 0000-0002-0736-9199 or ORCID:0000-0002-0736-9199
+    orcid_id: Option<String>,
+    /// Human-readable name of the curator; if not set, the ORCID is used as the creator
+    creator_name: Option<String>,
+    /// HPO OntologyClass messages keyed by term id, built once per cohort export
+    hpo_class_cache: HashMap<String, OntologyClass>,
+    /// Source of the template, if known; emitted as an external reference in the MetaData
+    provenance: Option<TemplateProvenance>,
+    /// HGNC ids or symbols of X-linked genes; single variants in males are exported as hemizygous
+    x_linked_genes: HashSet<String>,
 }
 
 impl Error {
@@ -78,11 +179,120 @@ impl PpktExporter {
             geno_version: geno_version.to_string(),
             omim_version: omim_version.to_string(), 
             hgnc_version: hgnc_version.to_string(),
-            orcid_id: creator_orcid.to_string(),
+            orcid_id: Some(creator_orcid.to_string()),
+            creator_name: None,
+            hpo_class_cache: HashMap::new(),
+            provenance: None,
+            x_linked_genes: HashSet::new(),
+        }
+    }
+
+    /// Create an exporter for the curator, provenance, and X-linked genes of the settings
+    pub fn from_settings(hpo_version: &str, settings: &ExportSettings) -> Self {
+        let mut exporter = Self::new(hpo_version, "");
+        exporter.orcid_id = settings.creator_orcid.clone();
+        exporter.creator_name = settings.creator_name.clone();
+        exporter.provenance = settings.provenance.clone();
+        exporter.x_linked_genes = settings.x_linked_genes.clone();
+        exporter
+    }
+
+    pub fn set_provenance(&mut self, provenance: Option<TemplateProvenance>) {
+        self.provenance = provenance;
+    }
+
+    /// Set the name of the curator, which is written to MetaData.created_by
+    pub fn set_creator_name(&mut self, creator_name: impl Into<String>) {
+        self.creator_name = Some(creator_name.into());
+    }
+
+    /// The ORCID of the curator as a CURIE, e.g., ORCID:0000-0002-0736-9199 (None if no curator was set)
+    pub fn orcid_curie(&self) -> Option<String> {
+        self.orcid_id.as_ref().map(|orcid_id| if orcid_id.starts_with("ORCID:") {
+            orcid_id.clone()
+        } else {
+            format!("ORCID:{}", orcid_id)
+        })
+    }
+
+    /// Set the genes (HGNC ids or symbols) that are X-linked, used to infer hemizygous allelic states for males
+    pub fn set_x_linked_genes(&mut self, x_linked_genes: HashSet<String>) {
+        self.x_linked_genes = x_linked_genes;
+    }
+
+    fn is_x_linked(&self, gvb: &GeneVariantBundleDto) -> bool {
+        self.x_linked_genes.contains(&gvb.hgnc_id) || self.x_linked_genes.contains(&gvb.gene_symbol)
+    }
+
+    /// Infer the GENO allelic state of the variants of a gene from the alleles and the sex of the individual.
+    /// A single variant (allele_2 is na) is heterozygous, two identical alleles are homozygous, and two distinct
+    /// alleles are (compound) heterozygous. For males with a variant in an X-linked gene, the state is hemizygous.
+    pub fn get_allelic_state(&self, gvb: &GeneVariantBundleDto, sex: &str) -> Option<OntologyClass> {
+        if gvb.allele1 == "na" {
+            return None;
+        }
+        let term: &SimpleMinimalTerm = if sex == "M" && self.is_x_linked(gvb) {
+            &*variant_util::HEMIZYGOUS
+        } else if gvb.allele1 == gvb.allele2 {
+            &*variant_util::HOMOZYGOUS
+        } else {
+            &*variant_util::HETEROZYGOUS
+        };
+        Some(OntologyClass { 
+            id: term.identifier().to_string(), 
+            label: term.name().to_string() 
+        })
+    }
+
+    /// Create OntologyClass messages for the HPO columns of the cohort once, so that we do not
+    /// need to parse the same CURIEs again for each phenopacket.
+    pub fn cache_hpo_terms(&mut self, hpo_duplets: &[HpoTermDuplet]) -> Result<()> {
+        for duplet in hpo_duplets {
+            if self.hpo_class_cache.contains_key(duplet.hpo_id()) {
+                continue;
+            }
+            let clz = Builder::ontology_class(duplet.hpo_id(), duplet.hpo_label())
+                .map_err(|_| Error::termid_parse_error(duplet.hpo_id()))?;
+            self.hpo_class_cache.insert(duplet.hpo_id().to_string(), clz);
+        }
+        Ok(())
+    }
+
+    /// Get an HPO OntologyClass from the cache, or create it if the term was not cached
+    fn get_hpo_ontology_class(&self, term_id: &str, label: &str) -> Result<OntologyClass> {
+        match self.hpo_class_cache.get(term_id) {
+            Some(clz) => Ok(clz.clone()),
+            None => Builder::ontology_class(term_id, label)
+                .map_err(|_| Error::termid_parse_error(term_id)),
         }
     }
 
 
+    /// Create a TimeElement from an age string. HPO onset labels (e.g., Infantile onset) become an OntologyClass with
+    /// the HPO id of the onset term, gestational ages (e.g., G30w2d) become a GestationalAge;
+    /// other strings (e.g., P3Y) are parsed by phenopacket_tools.
+    fn get_time_element(age: &str) -> Result<TimeElement> {
+        if let Some(tid) = age_util::onset_label_to_term_id(age) {
+            return Ok(TimeElement { 
+                element: Some(time_element::Element::OntologyClass(OntologyClass { 
+                    id: tid.to_string(), 
+                    label: age.to_string() 
+                })) 
+            });
+        }
+        if let Some((weeks, days)) = age_util::parse_gestational_age(age) {
+            return Ok(TimeElement { 
+                element: Some(time_element::Element::GestationalAge(GestationalAge { 
+                    weeks: weeks as i32, 
+                    days: days as i32 
+                })) 
+            });
+        }
+        time_element_from_str(age)
+            .map_err(|e| Error::malformed_time_element(e.to_string()))
+    }
+
+
     /// Create a GA4GH Individual message
     pub fn extract_individual(&self, ppkt_row: &PpktRow) -> Result<Individual> {
         let individual_dto = ppkt_row.get_individual_dto();
@@ -104,18 +314,31 @@ impl PpktExporter {
             _ => { return Err(Error::TemplateError { msg: format!("Did not recognize sex string '{}'", idvl.sex) });
             }
         };
+        if let Some(karyotype) = individual_dto.karyotypic_sex.as_deref() {
+            idvl.karyotypic_sex = individual_header::parse_karyotypic_sex(karyotype)?.into();
+        }
         let last_enc = individual_dto.age_at_last_encounter;
         if last_enc != "na" {
-            let age = time_element_from_str(&last_enc)
-                .map_err(|e| Error::malformed_time_element(e.to_string()))?;
+            let age = Self::get_time_element(&last_enc)?;
             idvl.time_at_last_encounter = Some(age);
         }
         if individual_dto.deceased == "yes" {
+            let time_of_death = match individual_dto.age_at_death.as_deref() {
+                Some(age) if age != "na" => Some(Self::get_time_element(age)?),
+                _ => None,
+            };
+            // approximated as the time from the age of onset to the age at death, if both are ISO 8601 ages
+            let survival_time_in_days = individual_dto.age_at_death.as_deref()
+                .and_then(age_util::iso_age_in_days)
+                .zip(age_util::iso_age_in_days(&individual_dto.age_of_onset))
+                .map_or(0, |(death, onset)| (death - onset).max(0.0).round() as u32);
+            let cause_of_death = individual_dto.cause_of_death
+                .map(|dx| OntologyClass { id: dx.disease_id, label: dx.disease_label });
             idvl.vital_status = Some(VitalStatus{ 
                 status: Status::Deceased.into(), 
-                time_of_death: None, 
-                cause_of_death: None, 
-                survival_time_in_days: 0 
+                time_of_death, 
+                cause_of_death, 
+                survival_time_in_days 
             });
         } 
         Ok(idvl)
@@ -142,34 +365,106 @@ impl PpktExporter {
         &self.hgnc_version
     } 
 
-    /// TODO possibly the PpktExporter has state (created, etc, also dynamically get the time string)
-    pub fn get_meta_data(&self, ppkt_row: &PpktRow) -> Result<MetaData> {
-
-        let created_by = "Earnest B. Biocurator";
-        let mut meta_data = Builder::meta_data_now(created_by);
+    /// MetaData with the resources (ontologies) that are shared by all phenopackets.
+    /// The curator is recorded as the creator (by name, or by ORCID if no name was set), and the ORCID as the submitter.
+    /// Without a curator, the creator is a placeholder as in earlier versions.
+    fn get_resource_meta_data(&self) -> MetaData {
+        let mut meta_data = match self.orcid_curie() {
+            Some(orcid) => {
+                let created_by = self.creator_name.clone().unwrap_or_else(|| orcid.clone());
+                let mut meta_data = Builder::meta_data_now(&created_by);
+                meta_data.submitted_by = orcid;
+                meta_data
+            },
+            None => Builder::meta_data_now(self.creator_name.as_deref().unwrap_or(DEFAULT_CREATED_BY)),
+        };
         let hpo = phenopacket_tools::builders::resources::Resources::hpo_version(self.hpo_version());
         let geno = phenopacket_tools::builders::resources::Resources::geno_version(self.geno_version());
         let so = phenopacket_tools::builders::resources::Resources::geno_version(self.so_version());
         let omim = phenopacket_tools::builders::resources::Resources::omim_version(self.omim_version());
-        let indvl_dto = ppkt_row.get_individual_dto();
         // TODO add HGNC
         //let hgnc =  phenopacket_tools::builders::resources::Resources::hgnc_version(self.omim_version());
+        meta_data.resources.push(hpo);
+        meta_data.resources.push(geno);
+        meta_data.resources.push(so);
+        meta_data.resources.push(omim);
+        meta_data
+    }
+
+    /// TODO possibly the PpktExporter has state (created, etc, also dynamically get the time string)
+    pub fn get_meta_data(&self, ppkt_row: &PpktRow) -> Result<MetaData> {
+        let mut meta_data = self.get_resource_meta_data();
+        let indvl_dto = ppkt_row.get_individual_dto();
         let ext_res = ExternalReference{ 
             id: indvl_dto.pmid, 
             reference: String::default(), 
             description: indvl_dto.title 
         };
-        meta_data.resources.push(hpo);
-        meta_data.resources.push(geno);
-        meta_data.resources.push(so);
-        meta_data.resources.push(omim);
         meta_data.external_references.push(ext_res);
+        if let Some(provenance) = &self.provenance {
+            meta_data.external_references.push(provenance.to_external_reference());
+        }
+        if let Some(ancestry) = Self::get_ancestry_reference(indvl_dto.ancestry.as_deref())? {
+            meta_data.external_references.push(ancestry);
+        }
         Ok(meta_data)
     }
 
+    /// MetaData for a GA4GH Cohort: the shared resources, one external reference for each distinct PMID of the
+    /// cohort, and the provenance of the template (if available)
+    pub fn get_cohort_meta_data(&self, ppkt_rows: &[PpktRow]) -> MetaData {
+        let mut meta_data = self.get_resource_meta_data();
+        for ppkt_row in ppkt_rows {
+            let indvl_dto = ppkt_row.get_individual_dto();
+            if meta_data.external_references.iter().any(|er| er.id == indvl_dto.pmid) {
+                continue;
+            }
+            meta_data.external_references.push(ExternalReference{ 
+                id: indvl_dto.pmid, 
+                reference: String::default(), 
+                description: indvl_dto.title 
+            });
+        }
+        if let Some(provenance) = &self.provenance {
+            meta_data.external_references.push(provenance.to_external_reference());
+        }
+        meta_data
+    }
+
+    /// Add the resources and external references of `member` (e.g., the MetaData of a phenopacket of a cohort)
+    /// that are not yet in `meta_data`; entries are identified by their id
+    pub fn merge_meta_data(meta_data: &mut MetaData, member: MetaData) {
+        for resource in member.resources {
+            if !meta_data.resources.iter().any(|r| r.id == resource.id) {
+                meta_data.resources.push(resource);
+            }
+        }
+        for reference in member.external_references {
+            if !meta_data.external_references.iter().any(|er| er.id == reference.id) {
+                meta_data.external_references.push(reference);
+            }
+        }
+    }
+
+    /// Phenopacket Schema v2 has no field for ancestry, so we record the reported ancestry
+    /// as a labeled external reference to the HANCESTRO term.
+    fn get_ancestry_reference(ancestry: Option<&str>) -> Result<Option<ExternalReference>> {
+        let label = match ancestry {
+            Some(label) if label != "na" => label,
+            _ => return Ok(None),
+        };
+        let term_id = ancestry_util::ancestry_term_id(label)
+            .ok_or_else(|| Error::TemplateError { msg: format!("Unrecognized ancestry '{label}'") })?;
+        Ok(Some(ExternalReference { 
+            id: term_id.to_string(), 
+            reference: format!("http://purl.obolibrary.org/obo/{}", term_id.replace(':', "_")), 
+            description: format!("ancestry: {label}") 
+        }))
+    }
+
 
     /// Generate the phenopacket identifier from the PMID and the individual identifier
-    pub fn get_phenopacket_id(&self, ppkt_row: &PpktRow) -> String {
+    pub fn get_phenopacket_id(ppkt_row: &PpktRow) -> String {
         let individual_dto = ppkt_row.get_individual_dto();
         let pmid = individual_dto.pmid.replace(":", "_");
         let individual_id = individual_dto.individual_id.replace(" ", "_");
@@ -188,33 +483,81 @@ impl PpktExporter {
         sanitized
     }
 
-    /// TODO extend for multiple diseases
+    /// Generate the phenopacket identifiers of all rows of a cohort. If several rows yield the same identifier (e.g.,
+    /// "Individual 1" and "Individual(1)" with the same PMID), the second and further rows get a numeric suffix
+    /// (_2, _3, ...), so that the identifiers are unique within the cohort.
+    pub fn get_unique_phenopacket_ids(ppkt_rows: &[PpktRow]) -> Vec<String> {
+        let base_ids: Vec<String> = ppkt_rows.iter().map(Self::get_phenopacket_id).collect();
+        let mut used: HashSet<String> = base_ids.iter().cloned().collect();
+        let mut seen: HashSet<&str> = HashSet::new();
+        let mut unique_ids: Vec<String> = Vec::with_capacity(base_ids.len());
+        for ppkt_id in &base_ids {
+            if seen.insert(ppkt_id.as_str()) {
+                unique_ids.push(ppkt_id.clone());
+                continue;
+            }
+            let mut n = 2;
+            let mut candidate = format!("{ppkt_id}_{n}");
+            while used.contains(&candidate) {
+                n += 1;
+                candidate = format!("{ppkt_id}_{n}");
+            }
+            used.insert(candidate.clone());
+            unique_ids.push(candidate);
+        }
+        unique_ids
+    }
+
+    /// Find phenopacket identifiers that are generated for more than one row, with the (zero-based) indices of these rows
+    pub fn find_duplicate_phenopacket_ids(ppkt_rows: &[PpktRow]) -> Vec<(String, Vec<usize>)> {
+        let mut rows_by_id: Vec<(String, Vec<usize>)> = Vec::new();
+        for (i, row) in ppkt_rows.iter().enumerate() {
+            let ppkt_id = Self::get_phenopacket_id(row);
+            match rows_by_id.iter_mut().find(|(id, _)| *id == ppkt_id) {
+                Some((_, rows)) => rows.push(i),
+                None => rows_by_id.push((ppkt_id, vec![i])),
+            }
+        }
+        rows_by_id.retain(|(_, rows)| rows.len() > 1);
+        rows_by_id
+    }
+
+    /// Get the (first) disease of the row, e.g., the disease of a Mendelian template
     pub fn get_disease(&self, ppkt_row: &PpktRow) -> Result<Disease> {
+        Ok(self.get_diseases(ppkt_row)?.remove(0))
+    }
+
+    /// Get all diseases of the row (two for melded templates). Each disease is annotated with the age of onset of the individual.
+    pub fn get_diseases(&self, ppkt_row: &PpktRow) -> Result<Vec<Disease>> {
         let disease_list = ppkt_row.get_disease_dto_list();
         if disease_list.is_empty() {
             return Err(Error::TemplateError { msg: format!("todo empty disease") });
         }
-        let dto = disease_list[0].clone();
-        let dx_id = Builder::ontology_class(dto.disease_id, dto.disease_label)
-            .map_err(|e| Error::DiseaseIdError{msg:format!("malformed disease id")})?;
-        let mut disease = Disease{ 
-            term: Some(dx_id), 
-            excluded: false, 
-            onset: None, 
-            resolution: None, 
-            disease_stage: vec![], 
-            clinical_tnm_finding: vec![], 
-            primary_site: None, 
-            laterality: None 
-        };
         let idl_dto = ppkt_row.get_individual_dto();
         let onset = idl_dto.age_of_onset;
-        if onset != "na" {
-            let age = time_element_from_str(&onset)
-                .map_err(|e| Error::malformed_time_element(e.to_string()))?;
-            disease.onset = Some(age);
+        let onset = if onset != "na" {
+            let age = Self::get_time_element(&onset)?;
+            Some(age)
+        } else {
+            None
         };
-        Ok(disease)
+        disease_list.into_iter()
+            .map(|dto| {
+                let disease_id = dto.disease_id.clone();
+                let dx_id = Builder::ontology_class(dto.disease_id, dto.disease_label)
+                    .map_err(|e| Error::DiseaseIdError{msg:format!("malformed disease id: '{disease_id}'")})?;
+                Ok(Disease{ 
+                    term: Some(dx_id), 
+                    excluded: false, 
+                    onset: onset.clone(), 
+                    resolution: None, 
+                    disease_stage: vec![], 
+                    clinical_tnm_finding: vec![], 
+                    primary_site: None, 
+                    laterality: None 
+                })
+            })
+            .collect()
     }
 
     fn allele_not_contained(allele: &str) -> String {
@@ -226,7 +569,8 @@ impl PpktExporter {
     fn get_sv_variant_interpretation(
         gvb: &GeneVariantBundleDto, 
         allele: &str,
-        sv: &StructuralVariant
+        sv: &StructuralVariant,
+        allelic_state: Option<OntologyClass>
     ) -> VariantInterpretation {
         let gene_ctxt = GeneDescriptor{ 
             value_id: gvb.hgnc_id.clone(), 
@@ -240,13 +584,24 @@ impl PpktExporter {
             id: sv.so_id().to_string(), 
             label: sv.so_label().to_string() 
         };
+        let (description, expressions) = match (sv.breakpoints(), sv.iscn()) {
+            (Some((first, second)), Some(iscn)) => {
+                let iscn = Expression {
+                    syntax: "iscn".to_string(),
+                    value: iscn,
+                    version: String::default(),
+                };
+                (format!("Translocation breakpoints: {first}, {second}"), vec![iscn])
+            },
+            _ => (String::default(), vec![]),
+        };
         let vdesc = VariationDescriptor {
             id: variant_util::generate_id(),
             variation: None,
             label: sv.label().to_string(),
-            description: String::default(),
+            description,
             gene_context: Some(gene_ctxt),
-            expressions: vec![],
+            expressions,
             vcf_record: None,
             xrefs: vec![],
             alternate_labels: vec![],
@@ -254,7 +609,7 @@ impl PpktExporter {
             molecule_context: MoleculeContext::Genomic.into(),
             structural_type: Some(sv_type),
             vrs_ref_allele_seq: String::default(),
-            allelic_state: None,
+            allelic_state,
         };
         let vi = VariantInterpretation{ 
             acmg_pathogenicity_classification: AcmgPathogenicityClassification::Pathogenic.into(), 
@@ -267,7 +622,8 @@ impl PpktExporter {
     fn get_hgvs_variant_interpretation(
             gvb: &GeneVariantBundleDto, 
             allele: &str,
-            hgvs: &HgvsVariant) -> VariantInterpretation {
+            hgvs: &HgvsVariant,
+            allelic_state: Option<OntologyClass>) -> VariantInterpretation {
         let gene_ctxt = GeneDescriptor{ 
             value_id: gvb.hgnc_id.clone(), 
             symbol: gvb.gene_symbol.clone(), 
@@ -302,6 +658,14 @@ impl PpktExporter {
                     };
             expression_list.push(hgvs_g);
         };
+        if let Some(hgvs_p) = hgvs.p_hgvs() {
+            let hgvs_p = Expression{
+                        syntax: "hgvs.p".to_string(),
+                        value: hgvs_p.to_string(),
+                        version: String::default(),
+                    };
+            expression_list.push(hgvs_p);
+        };
         
 
         let vdesc = VariationDescriptor{ 
@@ -318,7 +682,7 @@ impl PpktExporter {
             molecule_context: MoleculeContext::Genomic.into(), 
             structural_type: None, 
             vrs_ref_allele_seq: String::default(), 
-            allelic_state: None 
+            allelic_state 
         };
         let vi = VariantInterpretation{ 
             acmg_pathogenicity_classification: AcmgPathogenicityClassification::Pathogenic.into(), 
@@ -328,82 +692,87 @@ impl PpktExporter {
         vi
     }
 
+    /// One VariantInterpretation per distinct allele of the bundle; allele2 is included if it is set and differs
+    /// from allele1 (compound heterozygous). A homozygous variant is represented once, with the homozygous allelic state.
     fn get_variant_interpretation_list(
         gvb: &GeneVariantBundleDto, 
         hgvs_dict: &HashMap<String, HgvsVariant>,
-        structural_dict: &HashMap<String, StructuralVariant>) 
+        structural_dict: &HashMap<String, StructuralVariant>,
+        allelic_state: Option<OntologyClass>) 
     -> Vec<VariantInterpretation> {
         let mut v_interp_list: Vec<VariantInterpretation> = Vec::new();
-        if gvb.allele1 == "na" {
-            return v_interp_list;
+        let mut alleles = vec![&gvb.allele1];
+        if gvb.allele2 != gvb.allele1 {
+            alleles.push(&gvb.allele2);
         }
-        if hgvs_dict.contains_key(&gvb.allele1) {
-            let hgvs = hgvs_dict.get(&gvb.allele1).unwrap();
-            let vinterp = Self::get_hgvs_variant_interpretation(gvb, &gvb.allele1, hgvs);
-            v_interp_list.push(vinterp);
-        } else if structural_dict.contains_key(&gvb.allele1) {
-            let sv = structural_dict.get(&gvb.allele1).unwrap();
-            let vinterp = Self::get_sv_variant_interpretation(gvb, &gvb.allele1, sv);
-        } else {
-            // Assume allele2 is not set if allele1 is
-            return v_interp_list
+        for allele in alleles {
+            if allele == "na" {
+                continue;
+            }
+            if let Some(hgvs) = hgvs_dict.get(allele) {
+                let vinterp = Self::get_hgvs_variant_interpretation(gvb, allele, hgvs, allelic_state.clone());
+                v_interp_list.push(vinterp);
+            } else if let Some(sv) = structural_dict.get(allele) {
+                let vinterp = Self::get_sv_variant_interpretation(gvb, allele, sv, allelic_state.clone());
+                v_interp_list.push(vinterp);
+            }
         }
-        
-
-
         v_interp_list
     }
     
     
     
+    /// Get one Interpretation per disease of the row. Mendelian rows have one disease and one gene/variant bundle;
+    /// melded rows have two of each, and the n-th disease is attributed to the variants of the n-th gene/variant bundle.
     pub fn get_interpretation_list(
         &self, 
         ppkt_row: &PpktRow,
         hgvs_dict: &HashMap<String, HgvsVariant>,
         structural_dict: &HashMap<String, StructuralVariant>) 
     -> std::result::Result<Vec<Interpretation>, String> {
-        let mut dx_list = ppkt_row.get_disease_dto_list();
+        let dx_list = ppkt_row.get_disease_dto_list();
         let gdb_list = ppkt_row.get_gene_var_dto_list();
-        //TODO for now we just support Mendelian. Need to extend for digenic and Melded
-        if dx_list.len() != 1 || gdb_list.len() != 1 {
-            return Err("Only mendelian supported TODO".to_ascii_lowercase());
+        if dx_list.is_empty() || dx_list.len() != gdb_list.len() {
+            return Err(format!("Expected one gene/variant bundle per disease but got {} disease(s) and {} gene/variant bundle(s)",
+                dx_list.len(), gdb_list.len()));
         }
-        println!("{}{}-", file!(), line!());
-        let gdb_dto = gdb_list.first().unwrap();
-        let dx_dto = dx_list.first().unwrap();
-        let a1 = &gdb_dto.allele1;
-        let a2 = &gdb_dto.allele2;
-        if a1 != "na" && ! hgvs_dict.contains_key(a1) && !structural_dict.contains_key(a1) {
-            return Err(Self::allele_not_contained(a1));
-        }
-        if a2 != "na" && ! hgvs_dict.contains_key(a2) && !structural_dict.contains_key(a2) {
-            return Err(Self::allele_not_contained(a2));
-        }
-        let v_interpretations = Self::get_variant_interpretation_list(gdb_dto, hgvs_dict, structural_dict);
-        let disease_clz = OntologyClass{
-            id: dx_dto.disease_id.clone(),
-            label: dx_dto.disease_label.clone(),
-        };
-        let mut g_interpretations: Vec<GenomicInterpretation> = Vec::new();
-        for vi in v_interpretations {
-            let gi = GenomicInterpretation{
-                subject_or_biosample_id: ppkt_row.get_individual_dto().individual_id.clone(),
-                interpretation_status: InterpretationStatus::Causative.into(),
-                call: Some(Call::VariantInterpretation(vi))
+        let individual_dto = ppkt_row.get_individual_dto();
+        let mut interpretation_list: Vec<Interpretation> = Vec::with_capacity(dx_list.len());
+        for (dx_dto, gdb_dto) in dx_list.iter().zip(gdb_list.iter()) {
+            let a1 = &gdb_dto.allele1;
+            let a2 = &gdb_dto.allele2;
+            if a1 != "na" && ! hgvs_dict.contains_key(a1) && !structural_dict.contains_key(a1) {
+                return Err(Self::allele_not_contained(a1));
+            }
+            if a2 != "na" && ! hgvs_dict.contains_key(a2) && !structural_dict.contains_key(a2) {
+                return Err(Self::allele_not_contained(a2));
+            }
+            let allelic_state = self.get_allelic_state(gdb_dto, &individual_dto.sex);
+            let v_interpretations = Self::get_variant_interpretation_list(gdb_dto, hgvs_dict, structural_dict, allelic_state);
+            let disease_clz = OntologyClass{
+                id: dx_dto.disease_id.clone(),
+                label: dx_dto.disease_label.clone(),
+            };
+            let mut g_interpretations: Vec<GenomicInterpretation> = Vec::new();
+            for vi in v_interpretations {
+                let gi = GenomicInterpretation{
+                    subject_or_biosample_id: individual_dto.individual_id.clone(),
+                    interpretation_status: InterpretationStatus::Causative.into(),
+                    call: Some(Call::VariantInterpretation(vi))
+                };
+                g_interpretations.push(gi);
+            }
+            let diagnosis = Diagnosis{
+                disease: Some(disease_clz),
+                genomic_interpretations: g_interpretations,
             };
-            g_interpretations.push(gi);
+            interpretation_list.push(Interpretation{
+                id: generate_id(),
+                progress_status: ProgressStatus::Solved.into(),
+                diagnosis: Some(diagnosis),
+                summary: String::default(),
+            });
         }
-        let diagnosis = Diagnosis{
-            disease: Some(disease_clz),
-            genomic_interpretations: g_interpretations,
-        };
-        let i = Interpretation{
-            id: generate_id(),
-            progress_status: ProgressStatus::Solved.into(),
-            diagnosis: Some(diagnosis),
-            summary: String::default(),
-        };
-        let interpretation_list: Vec<Interpretation> = vec![i];
         Ok(interpretation_list)
     }
 
@@ -417,22 +786,32 @@ impl PpktExporter {
             if ! dto.is_ascertained() {
                 continue;
             }
-            let hpo_term = Builder::ontology_class(dto.term_id(), dto.label())
-                .map_err(|e| Error::termid_parse_error(dto.term_id()))?;
+            let hpo_term = self.get_hpo_ontology_class(dto.term_id(), dto.label())?;
+            let severity = match dto.severity() {
+                Some(label) => {
+                    let id = age_util::severity_term_id(&label)
+                        .ok_or_else(|| Error::HpoError { msg: format!("Unrecognized severity '{label}'") })?;
+                    Some(OntologyClass { id: id.to_string(), label })
+                },
+                None => None,
+            };
+            let resolution = match dto.resolution() {
+                Some(age) => Some(Self::get_time_element(&age)?),
+                None => None,
+            };
             let mut pf = PhenotypicFeature{ 
                 description: String::default(), 
                 r#type: Some(hpo_term), 
                 excluded: dto.is_excluded(), 
-                severity: None, 
+                severity, 
                 modifiers: vec![], 
                 onset: None,
-                resolution: None, 
+                resolution, 
                 evidence: vec![]
             };
             if dto.has_onset() {
                 let value = dto.onset()?;
-                let ost = time_element_from_str(&value)
-                    .map_err(|e| Error::malformed_time_element(value))?;
+                let ost = Self::get_time_element(&value)?;
                 pf.onset = Some(ost);
             }
             ppkt_feature_list.push(pf);
@@ -447,21 +826,15 @@ impl PpktExporter {
         hgvs_dict: &HashMap<String, HgvsVariant>,
         structural_dict: &HashMap<String, StructuralVariant>) 
     -> Result<Phenopacket> {
-        if ppkt_row.get_gene_var_dto_list().len() != 1 {
-            panic!("NEED TO EXTEND MODEL TO NON MEND. NEED TO EXTEND CACHE KEY FOR GENE-TRANSCRIPT-NAME");
-        }
         let interpretation_list = self.get_interpretation_list(ppkt_row, hgvs_dict, structural_dict)?;
-        let gv_dto = ppkt_row.get_gene_var_dto_list()[0].clone();
-        let allele1 = gv_dto.allele1;
-        let allele2= gv_dto.allele2;
         let ppkt = Phenopacket{ 
-            id: self.get_phenopacket_id(ppkt_row), 
+            id: Self::get_phenopacket_id(ppkt_row), 
             subject:  Some(self.extract_individual(ppkt_row)?), 
             phenotypic_features: self.get_phenopacket_features(ppkt_row)?, 
             measurements: vec![], 
             biosamples: vec![], 
             interpretations: interpretation_list, 
-            diseases: vec![self.get_disease(ppkt_row)?], 
+            diseases: self.get_diseases(ppkt_row)?, 
             medical_actions: vec![], 
             files: vec![], 
             meta_data: Some(self.get_meta_data(ppkt_row)?) 
@@ -471,4 +844,220 @@ impl PpktExporter {
     }
 
 
-}
\ No newline at end of file
+}
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rstest::{fixture, rstest};
+    use crate::template::header_duplet_row::HeaderDupletRow;
+    use crate::template::pt_template::TemplateType;
+    use crate::variant::vcf_var::VcfVar;
+    use crate::dto::template_dto::{DiseaseDto, RowDto};
+
+    /// Header of a melded template with a single HPO column (Seizure)
+    #[fixture]
+    fn melded_header() -> Arc<HeaderDupletRow> {
+        let hpo_duplets = vec![HpoTermDuplet::new("Seizure", "HP:0001250")];
+        Arc::new(HeaderDupletRow::from_hpo_duplets(hpo_duplets, TemplateType::Melded))
+    }
+
+    /// Melded row with two diseases (FOP and Marfan syndrome) and the corresponding ACVR1 and FBN1 variants
+    #[fixture]
+    fn melded_row() -> Vec<String> {
+        vec![
+            "PMID:29482508", "A case with two diagnoses", "proband", "",
+            "OMIM:135100", "Fibrodysplasia ossificans progressiva", "OMIM:154700", "Marfan syndrome",
+            "HGNC:171", "ACVR1", "NM_001111067.4", "c.617G>A", "na", "",
+            "HGNC:3603", "FBN1", "NM_000138.5", "c.8242G>T", "na", "",
+            "P9Y", "P16Y", "no", "M", "na", "observed"
+        ].into_iter().map(|s| s.to_owned()).collect()
+    }
+
+    #[rstest]
+    #[case("M", "GENO:0000134", "hemizygous")]
+    #[case("F", "GENO:0000135", "heterozygous")]
+    fn test_allelic_state_x_linked(#[case] sex: &str, #[case] geno_id: &str, #[case] geno_label: &str) {
+        let mut exporter = PpktExporter::new("2025-05-06", "0000-0000-0000-0000");
+        exporter.set_x_linked_genes(HashSet::from(["HGNC:3754".to_string()]));
+        let gvb = GeneVariantBundleDto::new("HGNC:3754", "FLNA", "NM_001110556.2", "c.994G>A", "na", "");
+        let allelic_state = exporter.get_allelic_state(&gvb, sex).unwrap();
+        assert_eq!(geno_id, allelic_state.id);
+        assert_eq!(geno_label, allelic_state.label);
+    }
+
+    #[rstest]
+    fn test_vital_status(melded_header: Arc<HeaderDupletRow>, mut melded_row: Vec<String>) {
+        melded_row[22] = "yes".to_string();
+        let exporter = PpktExporter::new("2025-05-06", "0000-0000-0000-0000");
+        let ppkt_row = PpktRow::from_row(melded_header.clone(), melded_row).unwrap();
+        let vital_status = exporter.extract_individual(&ppkt_row).unwrap().vital_status.unwrap();
+        assert_eq!(None, vital_status.time_of_death);
+        assert_eq!(0, vital_status.survival_time_in_days);
+        let mut dto = RowDto::from_ppkt_row(&ppkt_row);
+        dto.individual_dto.age_at_death = Some("P16Y".to_string());
+        dto.individual_dto.cause_of_death = Some(DiseaseDto::new("OMIM:154700", "Marfan syndrome"));
+        let ppkt_row = PpktRow::from_dto(&dto, melded_header);
+        let vital_status = exporter.extract_individual(&ppkt_row).unwrap().vital_status.unwrap();
+        assert!(vital_status.time_of_death.is_some());
+        assert_eq!("OMIM:154700", vital_status.cause_of_death.unwrap().id);
+        assert_eq!(2557, vital_status.survival_time_in_days);
+    }
+
+    #[rstest]
+    #[case("XXY", Some(KaryotypicSex::Xxy))]
+    #[case("47,XXY", Some(KaryotypicSex::Xxy))]
+    #[case("46,XX", Some(KaryotypicSex::Xx))]
+    #[case("45,X", Some(KaryotypicSex::Xo))]
+    #[case("na", Some(KaryotypicSex::UnknownKaryotype))]
+    #[case("46,XXY", None)]
+    #[case("XZ", None)]
+    #[case("", None)]
+    fn test_parse_karyotypic_sex(#[case] input: &str, #[case] expected: Option<KaryotypicSex>) {
+        let result = individual_header::parse_karyotypic_sex(input);
+        assert_eq!(expected, result.as_ref().ok().copied(), "Failed on input: {}", input);
+        if expected.is_none() {
+            assert!(matches!(result, Err(Error::SexFieldError { .. })));
+        }
+    }
+
+    #[rstest]
+    fn test_karyotypic_sex(melded_header: Arc<HeaderDupletRow>, melded_row: Vec<String>) {
+        let exporter = PpktExporter::new("2025-05-06", "0000-0000-0000-0000");
+        let ppkt_row = PpktRow::from_row(melded_header.clone(), melded_row).unwrap();
+        let individual = exporter.extract_individual(&ppkt_row).unwrap();
+        assert_eq!(KaryotypicSex::UnknownKaryotype as i32, individual.karyotypic_sex);
+        let mut dto = RowDto::from_ppkt_row(&ppkt_row);
+        dto.individual_dto.karyotypic_sex = Some("47,XXY".to_string());
+        let individual = exporter.extract_individual(&PpktRow::from_dto(&dto, melded_header.clone())).unwrap();
+        assert_eq!(KaryotypicSex::Xxy as i32, individual.karyotypic_sex);
+        assert_eq!(Sex::Male as i32, individual.sex);
+        dto.individual_dto.karyotypic_sex = Some("47,XY".to_string());
+        let ppkt_row = PpktRow::from_dto(&dto, melded_header);
+        assert!(matches!(exporter.extract_individual(&ppkt_row), Err(Error::SexFieldError { .. })));
+    }
+
+    #[rstest]
+    fn test_feature_severity_and_resolution(melded_header: Arc<HeaderDupletRow>, mut melded_row: Vec<String>) {
+        melded_row[25] = "P10Y;severity=Mild;resolution=P12Y".to_string();
+        let ppkt_row = PpktRow::from_row(melded_header, melded_row).unwrap();
+        let exporter = PpktExporter::new("2025-05-06", "0000-0000-0000-0000");
+        let features = exporter.get_phenopacket_features(&ppkt_row).unwrap();
+        assert_eq!(1, features.len());
+        assert_eq!(Some(OntologyClass { id: "HP:0012825".to_string(), label: "Mild".to_string() }), features[0].severity);
+        assert_eq!(Some(PpktExporter::get_time_element("P10Y").unwrap()), features[0].onset);
+        assert_eq!(Some(PpktExporter::get_time_element("P12Y").unwrap()), features[0].resolution);
+    }
+
+    #[rstest]
+    fn test_onset_label_time_element() {
+        let time_element = PpktExporter::get_time_element("Infantile onset").unwrap();
+        match time_element.element {
+            Some(time_element::Element::OntologyClass(clz)) => {
+                assert_eq!("HP:0003593", clz.id);
+                assert_eq!("Infantile onset", clz.label);
+            },
+            other => panic!("Expected an onset OntologyClass but got {:?}", other),
+        }
+    }
+
+    #[rstest]
+    fn test_gestational_age_disease_onset(melded_header: Arc<HeaderDupletRow>, mut melded_row: Vec<String>) {
+        melded_row[20] = "G30w2d".to_string();
+        let ppkt_row = PpktRow::from_row(melded_header, melded_row).unwrap();
+        let exporter = PpktExporter::new("2025-05-06", "0000-0000-0000-0000");
+        let disease = exporter.get_disease(&ppkt_row).unwrap();
+        let expected = TimeElement { 
+            element: Some(time_element::Element::GestationalAge(GestationalAge { weeks: 30, days: 2 })) 
+        };
+        assert_eq!(Some(expected), disease.onset);
+    }
+
+    #[rstest]
+    fn test_compound_heterozygous_interpretations() {
+        let gvb = GeneVariantBundleDto::new("HGNC:3603", "FBN1", "NM_000138.5", "c.8242G>T", "c.1A>G", "");
+        let mut hgvs_dict: HashMap<String, HgvsVariant> = HashMap::new();
+        hgvs_dict.insert("c.8242G>T".to_string(), HgvsVariant::new("hg38".to_string(), VcfVar::new("chr15", 48411364, "C", "A"), 
+            Some("FBN1".to_string()), Some("HGNC:3603".to_string()), None, None, None, None, None));
+        hgvs_dict.insert("c.1A>G".to_string(), HgvsVariant::new("hg38".to_string(), VcfVar::new("chr15", 48644710, "T", "C"), 
+            Some("FBN1".to_string()), Some("HGNC:3603".to_string()), None, None, None, None, None));
+        let exporter = PpktExporter::new("2025-05-06", "0000-0000-0000-0000");
+        let allelic_state = exporter.get_allelic_state(&gvb, "F");
+        let v_interps = PpktExporter::get_variant_interpretation_list(&gvb, &hgvs_dict, &HashMap::new(), allelic_state);
+        assert_eq!(2, v_interps.len());
+        let positions: Vec<u64> = v_interps.iter()
+            .map(|vi| vi.variation_descriptor.as_ref().unwrap().vcf_record.as_ref().unwrap().pos)
+            .collect();
+        assert_eq!(vec![48411364, 48644710], positions);
+        for vi in &v_interps {
+            let state = vi.variation_descriptor.as_ref().unwrap().allelic_state.as_ref().unwrap();
+            assert_eq!("GENO:0000135", state.id);
+        }
+    }
+
+    #[rstest]
+    fn test_get_diseases_melded(melded_header: Arc<HeaderDupletRow>, melded_row: Vec<String>) {
+        let ppkt_row = PpktRow::from_row(melded_header, melded_row).unwrap();
+        let exporter = PpktExporter::new("2025-05-06", "0000-0000-0000-0000");
+        let diseases = exporter.get_diseases(&ppkt_row).unwrap();
+        assert_eq!(2, diseases.len());
+        assert_eq!("OMIM:135100", diseases[0].term.as_ref().unwrap().id);
+        assert_eq!("OMIM:154700", diseases[1].term.as_ref().unwrap().id);
+        assert!(diseases.iter().all(|d| d.onset.is_some()));
+        assert_eq!(diseases[0], exporter.get_disease(&ppkt_row).unwrap());
+    }
+
+    /// Each disease of a melded row is exported with the variants of the corresponding gene/variant bundle
+    #[rstest]
+    fn test_extract_phenopacket_melded(melded_header: Arc<HeaderDupletRow>, melded_row: Vec<String>) {
+        let ppkt_row = PpktRow::from_row(melded_header, melded_row).unwrap();
+        let mut hgvs_dict: HashMap<String, HgvsVariant> = HashMap::new();
+        hgvs_dict.insert("c.617G>A".to_string(), HgvsVariant::new("hg38".to_string(), VcfVar::new("chr2", 157774114, "C", "T"), 
+            Some("ACVR1".to_string()), Some("HGNC:171".to_string()), None, None, None, None, None));
+        let exporter = PpktExporter::new("2025-05-06", "0000-0000-0000-0000");
+        // the FBN1 variant has not been validated
+        assert!(exporter.extract_phenopacket(&ppkt_row, &hgvs_dict, &HashMap::new()).is_err());
+        hgvs_dict.insert("c.8242G>T".to_string(), HgvsVariant::new("hg38".to_string(), VcfVar::new("chr15", 48411364, "C", "A"), 
+            Some("FBN1".to_string()), Some("HGNC:3603".to_string()), None, None, None, None, None));
+        let ppkt = exporter.extract_phenopacket(&ppkt_row, &hgvs_dict, &HashMap::new()).unwrap();
+        assert_eq!(2, ppkt.diseases.len());
+        assert_eq!(2, ppkt.interpretations.len());
+        for (interpretation, (disease_id, pos)) in ppkt.interpretations.iter().zip([("OMIM:135100", 157774114), ("OMIM:154700", 48411364)]) {
+            let diagnosis = interpretation.diagnosis.as_ref().unwrap();
+            assert_eq!(disease_id, diagnosis.disease.as_ref().unwrap().id);
+            assert_eq!(1, diagnosis.genomic_interpretations.len());
+            match diagnosis.genomic_interpretations[0].call.as_ref() {
+                Some(Call::VariantInterpretation(vi)) => {
+                    assert_eq!(pos, vi.variation_descriptor.as_ref().unwrap().vcf_record.as_ref().unwrap().pos);
+                },
+                other => panic!("Expected a variant interpretation but got {:?}", other),
+            }
+        }
+    }
+
+    #[rstest]
+    fn test_creator_meta_data() {
+        let mut exporter = PpktExporter::new("2025-05-06", "0000-0002-0736-9199");
+        let meta_data = exporter.get_resource_meta_data();
+        assert_eq!("ORCID:0000-0002-0736-9199", meta_data.created_by);
+        assert_eq!("ORCID:0000-0002-0736-9199", meta_data.submitted_by);
+        exporter.set_creator_name("Jane Curator");
+        let meta_data = exporter.get_resource_meta_data();
+        assert_eq!("Jane Curator", meta_data.created_by);
+        assert_eq!("ORCID:0000-0002-0736-9199", meta_data.submitted_by);
+        // without a curator, the placeholder creator is used
+        let exporter = PpktExporter::from_settings("2025-05-06", &ExportSettings::default());
+        assert_eq!(None, exporter.orcid_curie());
+        assert_eq!("Earnest B. Biocurator", exporter.get_resource_meta_data().created_by);
+    }
+
+    #[rstest]
+    fn test_allelic_state_autosomal() {
+        let exporter = PpktExporter::new("2025-05-06", "0000-0000-0000-0000");
+        let het = GeneVariantBundleDto::new("HGNC:171", "ACVR1", "NM_001111067.4", "c.617G>A", "na", "");
+        assert_eq!("GENO:0000135", exporter.get_allelic_state(&het, "M").unwrap().id);
+        let hom = GeneVariantBundleDto::new("HGNC:171", "ACVR1", "NM_001111067.4", "c.617G>A", "c.617G>A", "");
+        assert_eq!("GENO:0000136", exporter.get_allelic_state(&hom, "F").unwrap().id);
+    }
+}
@@ -0,0 +1,268 @@
+//! PpktImporter
+//!
+//! Convert a GA4GH phenopacket (e.g., a legacy phenopacket that was created with pyphetools) back into a row of a
+//! template. This is the inverse of [`crate::ppkt::ppkt_exporter::PpktExporter::extract_phenopacket`]: the subject is
+//! mapped to the individual columns, the diseases to the disease columns, the variant interpretations to the
+//! gene/variant columns (using the hgvs.c expressions), and the phenotypic features to the HPO columns.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use ontolius::Identified;
+use phenopackets::schema::v2::core::genomic_interpretation::Call;
+use phenopackets::schema::v2::core::vital_status::Status;
+use phenopackets::schema::v2::core::{time_element, KaryotypicSex, Sex, TimeElement, VariationDescriptor};
+use phenopackets::schema::v2::Phenopacket;
+
+use crate::dto::template_dto::{CellDto, DiseaseDto, GeneVariantBundleDto, IndividualBundleDto, RowDto};
+use crate::error::{Error, Result};
+use crate::header::hpo_term_duplet::HpoTermDuplet;
+use crate::ppkt::ppkt_row::PpktRow;
+use crate::template::header_duplet_row::HeaderDupletRow;
+use crate::variant::variant_util;
+
+
+/// The variants of one gene, collected from the variant interpretations of a phenopacket
+struct GeneAlleles {
+    hgnc_id: String,
+    symbol: String,
+    transcript: Option<String>,
+    alleles: Vec<String>,
+    homozygous: bool,
+}
+
+/// The contents of a phenopacket, arranged as the parts of a template row
+#[derive(Clone, Debug)]
+pub struct PpktImporter {
+    individual_dto: IndividualBundleDto,
+    disease_dto_list: Vec<DiseaseDto>,
+    gene_var_dto_list: Vec<GeneVariantBundleDto>,
+    /// HPO terms of the phenotypic features with the cell value (observed, excluded, or the age of onset)
+    hpo_values: Vec<(HpoTermDuplet, String)>,
+}
+
+impl PpktImporter {
+    pub fn from_phenopacket(ppkt: &Phenopacket) -> Result<Self> {
+        Ok(Self {
+            individual_dto: Self::get_individual_dto(ppkt)?,
+            disease_dto_list: Self::get_disease_dto_list(ppkt)?,
+            gene_var_dto_list: Self::get_gene_var_dto_list(ppkt)?,
+            hpo_values: Self::get_hpo_values(ppkt)?,
+        })
+    }
+
+    /// HPO terms of the phenotypic features that do not (yet) have a column in the template
+    pub fn missing_hpo_terms(&self, header: &HeaderDupletRow) -> Vec<HpoTermDuplet> {
+        self.hpo_values.iter()
+            .map(|(duplet, _)| duplet)
+            .filter(|duplet| !header.hpo_duplets().iter().any(|hd| hd.hpo_id() == duplet.hpo_id()))
+            .cloned()
+            .collect()
+    }
+
+    /// Create the template row. Returns an error that lists the HPO terms of the phenopacket that are not in
+    /// the header; these columns must be added to the template (see [`Self::missing_hpo_terms`]) before the import.
+    pub fn to_ppkt_row(&self, header: Arc<HeaderDupletRow>) -> Result<PpktRow> {
+        let missing = self.missing_hpo_terms(&header);
+        if !missing.is_empty() {
+            let terms: Vec<String> = missing.iter()
+                .map(|duplet| format!("{} ({})", duplet.hpo_label(), duplet.hpo_id()))
+                .collect();
+            return Err(Error::PpktError { msg: format!("HPO terms not in template: {}", terms.join(", ")) });
+        }
+        let values: HashMap<&str, &str> = self.hpo_values.iter()
+            .map(|(duplet, value)| (duplet.hpo_id(), value.as_str()))
+            .collect();
+        let hpo_data = header.hpo_duplets().iter()
+            .map(|duplet| CellDto::new(values.get(duplet.hpo_id()).copied().unwrap_or("na")))
+            .collect();
+        let mut individual_dto = self.individual_dto.clone();
+        if !header.has_ancestry() {
+            individual_dto.ancestry = None;
+        } else if individual_dto.ancestry.is_none() {
+            individual_dto.ancestry = Some("na".to_string());
+        }
+        if !header.has_vital_status() {
+            individual_dto.age_at_death = None;
+            individual_dto.cause_of_death = None;
+        } else if individual_dto.age_at_death.is_none() {
+            individual_dto.age_at_death = Some("na".to_string());
+        }
+        if !header.has_karyotypic_sex() {
+            individual_dto.karyotypic_sex = None;
+        } else if individual_dto.karyotypic_sex.is_none() {
+            individual_dto.karyotypic_sex = Some("na".to_string());
+        }
+        let row_dto = RowDto {
+            individual_dto,
+            disease_dto_list: self.disease_dto_list.clone(),
+            gene_var_dto_list: self.gene_var_dto_list.clone(),
+            hpo_data,
+            trailing_metadata: vec![],
+        };
+        Ok(PpktRow::from_dto(&row_dto, header))
+    }
+
+    /// The PMID and title are taken from the PMID external reference of the MetaData, and the ancestry (if any)
+    /// from the HANCESTRO external reference that is written by the exporter
+    fn get_individual_dto(ppkt: &Phenopacket) -> Result<IndividualBundleDto> {
+        let subject = ppkt.subject.as_ref()
+            .ok_or_else(|| Error::PpktError { msg: format!("Phenopacket '{}' has no subject", ppkt.id) })?;
+        let external_references = ppkt.meta_data.as_ref()
+            .map(|meta_data| meta_data.external_references.as_slice())
+            .unwrap_or_default();
+        let pmid_reference = external_references.iter()
+            .find(|er| er.id.starts_with("PMID:"))
+            .ok_or_else(|| Error::PpktError { msg: format!("Phenopacket '{}' has no PMID external reference", ppkt.id) })?;
+        let sex = match subject.sex {
+            s if s == Sex::Male as i32 => "M",
+            s if s == Sex::Female as i32 => "F",
+            s if s == Sex::OtherSex as i32 => "O",
+            _ => "U",
+        };
+        let age_of_onset = match ppkt.diseases.iter().find_map(|dx| dx.onset.as_ref()) {
+            Some(onset) => time_element_to_str(onset)?,
+            None => "na".to_string(),
+        };
+        let age_at_last_encounter = match &subject.time_at_last_encounter {
+            Some(age) => time_element_to_str(age)?,
+            None => "na".to_string(),
+        };
+        let deceased = match &subject.vital_status {
+            Some(vs) if vs.status == Status::Deceased as i32 => "yes",
+            Some(vs) if vs.status == Status::Alive as i32 => "no",
+            _ => "na",
+        };
+        let mut dto = IndividualBundleDto::new(&pmid_reference.id, &pmid_reference.description, &subject.id, "",
+            &age_of_onset, &age_at_last_encounter, deceased, sex);
+        dto.ancestry = external_references.iter()
+            .find(|er| er.id.starts_with("HANCESTRO:"))
+            .map(|er| er.description.strip_prefix("ancestry: ").unwrap_or(&er.description).to_string());
+        dto.karyotypic_sex = KaryotypicSex::try_from(subject.karyotypic_sex).ok()
+            .filter(|karyotype| *karyotype != KaryotypicSex::UnknownKaryotype)
+            .map(|karyotype| karyotype.as_str_name().to_string());
+        if let Some(vital_status) = &subject.vital_status {
+            dto.age_at_death = vital_status.time_of_death.as_ref()
+                .map(time_element_to_str)
+                .transpose()?;
+            dto.cause_of_death = vital_status.cause_of_death.as_ref()
+                .map(|clz| DiseaseDto::new(&clz.id, &clz.label));
+        }
+        Ok(dto)
+    }
+
+    fn get_disease_dto_list(ppkt: &Phenopacket) -> Result<Vec<DiseaseDto>> {
+        let disease_dto_list: Vec<DiseaseDto> = ppkt.diseases.iter()
+            .filter(|dx| !dx.excluded)
+            .filter_map(|dx| dx.term.as_ref())
+            .map(|clz| DiseaseDto::new(&clz.id, &clz.label))
+            .collect();
+        if disease_dto_list.is_empty() {
+            return Err(Error::PpktError { msg: format!("Phenopacket '{}' has no disease", ppkt.id) });
+        }
+        Ok(disease_dto_list)
+    }
+
+    /// One gene/variant bundle per gene (in order of first appearance). A single variant is entered as allele_1,
+    /// unless its allelic state is homozygous, in which case it is entered as allele_1 and allele_2.
+    fn get_gene_var_dto_list(ppkt: &Phenopacket) -> Result<Vec<GeneVariantBundleDto>> {
+        let homozygous_id = variant_util::HOMOZYGOUS.identifier().to_string();
+        let mut genes: Vec<GeneAlleles> = Vec::new();
+        let variation_descriptors = ppkt.interpretations.iter()
+            .filter_map(|interpretation| interpretation.diagnosis.as_ref())
+            .flat_map(|diagnosis| diagnosis.genomic_interpretations.iter())
+            .filter_map(|gi| match &gi.call {
+                Some(Call::VariantInterpretation(vi)) => vi.variation_descriptor.as_ref(),
+                _ => None,
+            });
+        for vdesc in variation_descriptors {
+            let gene = vdesc.gene_context.as_ref()
+                .ok_or_else(|| Error::PpktError { msg: format!("Variant '{}' has no gene context", vdesc.id) })?;
+            let (transcript, allele) = Self::get_transcript_and_allele(vdesc);
+            let is_homozygous = vdesc.allelic_state.as_ref().is_some_and(|clz| clz.id == homozygous_id);
+            let idx = match genes.iter().position(|ga| ga.hgnc_id == gene.value_id) {
+                Some(idx) => idx,
+                None => {
+                    genes.push(GeneAlleles { 
+                        hgnc_id: gene.value_id.clone(), 
+                        symbol: gene.symbol.clone(), 
+                        transcript: None, 
+                        alleles: Vec::new(), 
+                        homozygous: false 
+                    });
+                    genes.len() - 1
+                }
+            };
+            let gene_alleles = &mut genes[idx];
+            if gene_alleles.transcript.is_none() {
+                gene_alleles.transcript = transcript;
+            }
+            if !gene_alleles.alleles.contains(&allele) {
+                gene_alleles.alleles.push(allele);
+            }
+            gene_alleles.homozygous |= is_homozygous;
+        }
+        let mut gene_var_dto_list: Vec<GeneVariantBundleDto> = Vec::with_capacity(genes.len());
+        for GeneAlleles { hgnc_id, symbol, transcript, alleles, homozygous } in genes {
+            let transcript = transcript
+                .ok_or_else(|| Error::PpktError { msg: format!("No transcript (hgvs.c expression) for {symbol}") })?;
+            let (allele1, allele2) = match alleles.as_slice() {
+                [a1] if homozygous => (a1.as_str(), a1.as_str()),
+                [a1] => (a1.as_str(), "na"),
+                [a1, a2] => (a1.as_str(), a2.as_str()),
+                _ => {
+                    return Err(Error::PpktError { msg: format!("Expected one or two alleles of {symbol} but got {}", alleles.len()) });
+                }
+            };
+            gene_var_dto_list.push(GeneVariantBundleDto::new(&hgnc_id, &symbol, &transcript, allele1, allele2, ""));
+        }
+        Ok(gene_var_dto_list)
+    }
+
+    /// HGVS variants are identified by their hgvs.c expression (e.g., NM_000138.5:c.8242G>T), structural
+    /// variants, which do not have a transcript, by their label (e.g., DEL: deletion exon 5)
+    fn get_transcript_and_allele(vdesc: &VariationDescriptor) -> (Option<String>, String) {
+        match vdesc.expressions.iter()
+            .find(|expr| expr.syntax == "hgvs.c")
+            .and_then(|expr| expr.value.split_once(':')) {
+            Some((transcript, allele)) => (Some(transcript.to_string()), allele.to_string()),
+            None => (None, vdesc.label.clone()),
+        }
+    }
+
+    fn get_hpo_values(ppkt: &Phenopacket) -> Result<Vec<(HpoTermDuplet, String)>> {
+        let mut hpo_values: Vec<(HpoTermDuplet, String)> = Vec::with_capacity(ppkt.phenotypic_features.len());
+        for feature in &ppkt.phenotypic_features {
+            let term = match &feature.r#type {
+                Some(term) => term,
+                None => continue,
+            };
+            if feature.excluded {
+                hpo_values.push((HpoTermDuplet::new(&term.label, &term.id), "excluded".to_string()));
+                continue;
+            }
+            let mut value = match &feature.onset {
+                Some(onset) => time_element_to_str(onset)?,
+                None => "observed".to_string(),
+            };
+            if let Some(severity) = &feature.severity {
+                value.push_str(&format!(";severity={}", severity.label));
+            }
+            if let Some(resolution) = &feature.resolution {
+                value.push_str(&format!(";resolution={}", time_element_to_str(resolution)?));
+            }
+            hpo_values.push((HpoTermDuplet::new(&term.label, &term.id), value));
+        }
+        Ok(hpo_values)
+    }
+}
+
+/// Represent a TimeElement as in the age columns of the template, e.g., P3Y, G32w2d, or Infantile onset
+fn time_element_to_str(time_element: &TimeElement) -> Result<String> {
+    match &time_element.element {
+        Some(time_element::Element::Age(age)) => Ok(age.iso8601duration.clone()),
+        Some(time_element::Element::GestationalAge(ga)) => Ok(format!("G{}w{}d", ga.weeks, ga.days)),
+        Some(time_element::Element::OntologyClass(clz)) => Ok(clz.label.clone()),
+        _ => Err(Error::malformed_time_element(format!("Cannot represent time element in template: {:?}", time_element))),
+    }
+}
@@ -17,21 +17,19 @@ use polars::prelude::default_arrays;
 use crate::dto::case_dto::CaseDto;
 use crate::dto::hpo_term_dto::HpoTermDto;
 use crate::dto::template_dto::{CellDto, DiseaseDto, GeneVariantBundleDto, IndividualBundleDto, RowDto, TemplateDto};
-use crate::dto::validation_errors::ValidationErrors;
+use crate::dto::validation_errors::{QcIssue, ValidationErrors};
 use crate::header::individual_header::IndividualHeader;
+use crate::header::duplet_item::DupletItem;
 use crate::hpo::age_util::{self, check_hpo_table_cell};
 use crate::hpo::hpo_util;
 use crate::template::curie::Curie;
 use crate::error::{self, Error, Result};
 use crate::template::disease_bundle::{self, DiseaseBundle};
 use crate::template::gene_variant_bundle::{self, GeneVariantBundle};
-use crate::template::individual_bundle::IndividualBundle;
+use crate::template::individual_bundle::{IndividualBundle, OptionalIndividualValues};
 use crate::template::pt_template::TemplateType;
 use crate::template::simple_label::SimpleLabel;
-use crate::template::header_duplet_row::{self, HeaderDupletRow};
-
-/// The index where the Mendelian demographic part sars
-const DEMOGRAPHIC_IDX:usize = 12;
+use crate::template::header_duplet_row::{self, HeaderDupletRow, HeaderIndexer};
 
 
 #[derive(Clone, Debug)]
@@ -40,7 +38,9 @@ pub struct PpktRow {
     individual_bundle: IndividualBundle,
     disease_bundle_list: Vec<DiseaseBundle>,
     gene_var_bundle_list: Vec<GeneVariantBundle>,
-    hpo_content: Vec<String>
+    hpo_content: Vec<String>,
+    /// Values of the trailing metadata section (columns after the metadata marker), preserved verbatim
+    trailing_metadata: Vec<String>,
 }
 
 
@@ -52,7 +52,7 @@ impl PpktRow {
     ) -> std::result::Result<Self, ValidationErrors> {
         match header.template_type() {
             crate::template::pt_template::TemplateType::Mendelian => Self::from_mendelian_row(header, content),
-            crate::template::pt_template::TemplateType::Melded => todo!(),
+            crate::template::pt_template::TemplateType::Melded => Self::from_melded_row(header, content),
         }
     }
 
@@ -60,16 +60,12 @@ impl PpktRow {
         header: Arc<HeaderDupletRow>,
         content: Vec<String>
     ) -> std::result::Result<Self, ValidationErrors> {
-        let ibundle = IndividualBundle::from_row(&content, DEMOGRAPHIC_IDX)?;
-        let disease_bundle = DiseaseBundle::from_row(&content, 4)?; // todo -- put index contents in same place
-        let gene_variant_bundle = GeneVariantBundle::from_row(&content, 6)?;
+        let indexer = HeaderIndexer::new(header.template_type());
         let mut verrs = ValidationErrors::new();
-        let mut hpo_content: Vec<String> = Vec::new();
-        for item in content.iter().skip(17) {
-            let cell = if item.trim().is_empty() { "na" } else { item }; // TODO -- remove once old templates have been restructured
-            verrs.push_result(age_util::check_hpo_table_cell(&item));
-            hpo_content.push(item.clone());
-        }
+        let (hpo_content, optional_values, trailing_metadata) = Self::parse_hpo_section(&header, &content, &indexer, &mut verrs);
+        let ibundle = IndividualBundle::from_row(&content, indexer.demographic_idx(), optional_values)?;
+        let disease_bundle = DiseaseBundle::from_row(&content, indexer.disease_idx())?;
+        let gene_variant_bundle = GeneVariantBundle::from_row(&content, indexer.gene_var_idx())?;
         if verrs.has_error() {
             return Err(verrs);
         }
@@ -77,10 +73,92 @@ impl PpktRow {
             individual_bundle: ibundle, 
             disease_bundle_list: vec![disease_bundle], 
             gene_var_bundle_list: vec![gene_variant_bundle],
-            hpo_content 
+            hpo_content,
+            trailing_metadata
         })
     }
 
+    /// Create a row of a melded template, i.e., with two disease bundles and two gene/variant bundles.
+    /// In contrast to the Mendelian path, the errors of all bundles are collected before returning.
+    pub fn from_melded_row(
+        header: Arc<HeaderDupletRow>,
+        content: Vec<String>
+    ) -> std::result::Result<Self, ValidationErrors> {
+        let indexer = HeaderIndexer::new(header.template_type());
+        let mut verrs = ValidationErrors::new();
+        let (hpo_content, optional_values, trailing_metadata) = Self::parse_hpo_section(&header, &content, &indexer, &mut verrs);
+        let ibundle = IndividualBundle::from_row(&content, indexer.demographic_idx(), optional_values)
+            .map_err(|e| verrs.add_errors(e.errors()))
+            .ok();
+        let mut disease_bundle_list = Vec::new();
+        for idx in indexer.disease_bundle_indices() {
+            match DiseaseBundle::from_row(&content, idx) {
+                Ok(bundle) => disease_bundle_list.push(bundle),
+                Err(e) => verrs.add_errors(e.errors()),
+            }
+        }
+        let mut gene_var_bundle_list = Vec::new();
+        for idx in indexer.gene_var_bundle_indices() {
+            match GeneVariantBundle::from_row(&content, idx) {
+                Ok(bundle) => gene_var_bundle_list.push(bundle),
+                Err(e) => verrs.add_errors(e.errors()),
+            }
+        }
+        match ibundle {
+            Some(individual_bundle) if !verrs.has_error() => Ok(Self { 
+                header: header.clone(), 
+                individual_bundle, 
+                disease_bundle_list, 
+                gene_var_bundle_list,
+                hpo_content,
+                trailing_metadata
+            }),
+            _ => Err(verrs),
+        }
+    }
+
+    /// Parse the part of a data row that follows the demographic columns: the HPO/na separator, the HPO columns,
+    /// the optional columns (ancestry, vital status, karyotypic sex) and the optional trailing metadata section.
+    /// Returns the HPO values, the optional values, and the trailing metadata values; errors are added to `verrs`.
+    fn parse_hpo_section(
+        header: &HeaderDupletRow,
+        content: &[String],
+        indexer: &HeaderIndexer,
+        verrs: &mut ValidationErrors
+    ) -> (Vec<String>, OptionalIndividualValues, Vec<String>) {
+        // the optional columns come after the HPO columns, followed by the optional trailing metadata section
+        let mut i = indexer.hpo_idx() + header.hpo_count();
+        let mut optional_values = OptionalIndividualValues::default();
+        if header.has_ancestry() {
+            optional_values.ancestry = content.get(i).cloned();
+            i += 1;
+        }
+        if header.has_vital_status() {
+            optional_values.age_at_death = content.get(i).cloned();
+            optional_values.cause_of_death = match (content.get(i + 1), content.get(i + 2)) {
+                (Some(id), Some(label)) if id != "na" => Some(DiseaseDto::new(id, label)),
+                _ => None,
+            };
+            i += 3;
+        }
+        if header.has_karyotypic_sex() {
+            optional_values.karyotypic_sex = content.get(i).cloned();
+            i += 1;
+        }
+        let metadata_start = i + 1; // skip the marker column
+        let trailing_metadata: Vec<String> = (0..header.trailing_metadata().len())
+            .map(|offset| content.get(metadata_start + offset).cloned().unwrap_or_default())
+            .collect();
+        let separator = content.get(indexer.separator_idx()).map(String::as_str).unwrap_or_default();
+        verrs.push_result(DupletItem::hpo_separator().qc_data(separator));
+        let mut hpo_content: Vec<String> = Vec::new();
+        for item in content.iter().skip(indexer.hpo_idx()).take(header.hpo_count()) {
+            verrs.push_result(age_util::check_hpo_table_cell(item));
+            hpo_content.push(item.clone());
+        }
+        (hpo_content, optional_values, trailing_metadata)
+    }
+
     /// Create a new PpktRow. This is used when we create a row (phenopacket) with terms that
     /// may not be included in the previous phenopackets and which may not have values for all of the
     /// terms in the previous phenopackets. 
@@ -98,7 +176,7 @@ impl PpktRow {
         tid_to_value_map: HashMap<TermId, String>, 
         cohort_dto: TemplateDto) -> std::result::Result<Self, String> {
         if cohort_dto.cohort_type != TemplateType::Mendelian {
-            panic!("from_map: Melded not supported");
+            return Err(format!("from_tid_to_value_map: {:?} templates not supported", cohort_dto.cohort_type));
         }
         let mut items = Vec::with_capacity(header.hpo_count());
         for hduplet in header.hpo_duplets() {
@@ -113,7 +191,8 @@ impl PpktRow {
             individual_bundle: ibundle, 
             disease_bundle_list, 
             gene_var_bundle_list: gvb_list, 
-            hpo_content: items
+            hpo_content: items,
+            trailing_metadata: vec![]
         })
     }
 
@@ -127,14 +206,47 @@ impl PpktRow {
             individual_bundle: IndividualBundle::from_dto(dto.individual_dto.clone()), 
             disease_bundle_list: DiseaseBundle::from_dto_list(dto.disease_dto_list.clone()), 
             gene_var_bundle_list: GeneVariantBundle::from_dto_list(dto.gene_var_dto_list.clone()), 
-            hpo_content
+            hpo_content,
+            trailing_metadata: dto.trailing_metadata.clone()
         }
     }
 
     pub fn get_individual_dto(&self) -> IndividualBundleDto {
         let ibdl = &self.individual_bundle;
-        IndividualBundleDto::new(ibdl.pmid(), ibdl.title(), ibdl.individual_id(), ibdl.comment(),
-            ibdl.age_of_onset(), ibdl.age_at_last_encounter(), ibdl.deceased(), ibdl.sex())
+        let mut dto = IndividualBundleDto::new(ibdl.pmid(), ibdl.title(), ibdl.individual_id(), ibdl.comment(),
+            ibdl.age_of_onset(), ibdl.age_at_last_encounter(), ibdl.deceased(), ibdl.sex());
+        dto.ancestry = ibdl.ancestry.clone();
+        dto.age_at_death = ibdl.age_at_death.clone();
+        dto.cause_of_death = ibdl.cause_of_death.clone();
+        dto.karyotypic_sex = ibdl.karyotypic_sex.clone();
+        dto
+    }
+
+    /// Values of the optional columns in the column order of [`HeaderDupletRow::get_optional_duplets`];
+    /// "na" is written for missing values
+    pub fn get_optional_values(&self) -> Vec<String> {
+        let ibdl = &self.individual_bundle;
+        let na = || "na".to_string();
+        let mut values = Vec::new();
+        if self.header.has_ancestry() {
+            values.push(ibdl.ancestry.clone().unwrap_or_else(na));
+        }
+        if self.header.has_vital_status() {
+            values.push(ibdl.age_at_death.clone().unwrap_or_else(na));
+            match &ibdl.cause_of_death {
+                Some(dx) => values.extend([dx.disease_id.clone(), dx.disease_label.clone()]),
+                None => values.extend([na(), na()]),
+            }
+        }
+        if self.header.has_karyotypic_sex() {
+            values.push(ibdl.karyotypic_sex.clone().unwrap_or_else(na));
+        }
+        values
+    }
+
+    /// Values of the trailing metadata section (empty if the template does not have one)
+    pub fn trailing_metadata(&self) -> &[String] {
+        &self.trailing_metadata
     }
 
     pub fn get_disease_dto_list(&self) -> Vec<DiseaseDto> {
@@ -153,6 +265,66 @@ impl PpktRow {
         gbdto_list
     }
 
+    /// Values of the constant (non-HPO) columns in column order, including the separator column.
+    /// The order corresponds to [`HeaderDupletRow::get_fixed_duplets`].
+    pub fn get_fixed_values(&self) -> Vec<String> {
+        let ibdl = &self.individual_bundle;
+        let mut values = vec![ibdl.pmid.clone(), ibdl.title.clone(), ibdl.individual_id.clone(), ibdl.comment.clone()];
+        for disease in &self.disease_bundle_list {
+            values.push(disease.disease_id.clone());
+            values.push(disease.disease_label.clone());
+        }
+        for gvb in &self.gene_var_bundle_list {
+            values.push(gvb.hgnc_id.clone());
+            values.push(gvb.gene_symbol.clone());
+            values.push(gvb.transcript.clone());
+            values.push(gvb.allele1.clone());
+            values.push(gvb.allele2.clone());
+            values.push(gvb.variant_comment.clone());
+        }
+        values.push(ibdl.age_of_onset.clone());
+        values.push(ibdl.age_at_last_encounter.clone());
+        values.push(ibdl.deceased.clone());
+        values.push(ibdl.sex.clone());
+        values.push("na".to_string());
+        values
+    }
+
+    pub fn hpo_content(&self) -> &[String] {
+        &self.hpo_content
+    }
+
+    pub fn disease_count(&self) -> usize {
+        self.disease_bundle_list.len()
+    }
+
+    pub fn gene_variant_count(&self) -> usize {
+        self.gene_var_bundle_list.len()
+    }
+
+    /// Replace the allele `from` by `to` in all gene/variant bundles of this row.
+    /// Returns the number of cells that were changed.
+    pub fn replace_allele(&mut self, from: &str, to: &str) -> usize {
+        let mut n_replaced = 0;
+        for gvb in &mut self.gene_var_bundle_list {
+            for allele in [&mut gvb.allele1, &mut gvb.allele2] {
+                if allele.as_str() == from {
+                    *allele = to.to_string();
+                    n_replaced += 1;
+                }
+            }
+        }
+        n_replaced
+    }
+
+    /// Notes about alleles whose invisible whitespace (e.g., NBSP) was normalized when the row was created
+    pub fn allele_normalization_notes(&self) -> Vec<String> {
+        self.gene_var_bundle_list
+            .iter()
+            .flat_map(|gvb| gvb.normalization_notes().iter().cloned())
+            .collect()
+    }
+
     pub fn get_hpo_value_list(&self) -> Vec<CellDto> {
         let mut cell_dto_list: Vec<CellDto> = Vec::new();
         for hpo_val in &self.hpo_content {
@@ -166,37 +338,66 @@ impl PpktRow {
     }
 
 
+    /// Create a new row (e.g., a new case) of the same Mendelian cohort as this row, which provides the disease bundle.
+    /// # Arguments
+    ///
+    /// * `header_duplet_row` - Header of the cohort, which must include all HPO terms of the annotations
+    /// * `individual_dto` - DTO with demographic information about the new individual
+    /// * `gene_variant_list` - genotypes of the new individual
+    /// * `annotations` - HPO annotations of the new individual
+    /// * `existing_annotation_map` - values (e.g., observed, na, P32Y2M) of HPO terms; the annotations take precedence
+    ///
+    /// HPO columns without a value are set to na. All errors (e.g., terms not in the header, malformed cells) are collected.
     pub fn mendelian_from_dto(
         &self,
         header_duplet_row: Arc<HeaderDupletRow>,
         individual_dto: IndividualBundleDto,
+        gene_variant_list: Vec<GeneVariantBundleDto>,
         annotations: Vec<HpoTermDto>,
         existing_annotation_map:HashMap<TermId, String>) 
     -> std::result::Result<Self, ValidationErrors> 
     {
-        let verrs = ValidationErrors::new();
-        //let existing_header = self.header:
-        
-       /*  Ok(Self {
-            header_duplet_row: header_duplet_row,
-            content: values
-        })*/
-        if verrs.has_error() {
-            Err(verrs)
-        } else {
-            Ok(Self{ 
-                header: header_duplet_row, 
-                individual_bundle: todo!(), 
-                disease_bundle_list: todo!(), 
-                gene_var_bundle_list: todo!(), 
-                hpo_content: todo!() })
+        let mut verrs = ValidationErrors::new();
+        if *header_duplet_row.template_type() != TemplateType::Mendelian {
+            return Err(ValidationErrors::from_one_err(format!(
+                "mendelian_from_dto: {:?} templates not supported", header_duplet_row.template_type())));
+        }
+        let hpo_id_list = header_duplet_row.get_hpo_id_list()?;
+        let mut tid_to_value_map = existing_annotation_map;
+        for dto in annotations {
+            match dto.ontolius_term_id() {
+                Ok(tid) if hpo_id_list.contains(&tid) => {
+                    tid_to_value_map.insert(tid, dto.entry().to_string());
+                },
+                Ok(tid) => verrs.push_str(format!("HPO term {} ({}) is not in the template header", tid, dto.label())),
+                Err(e) => verrs.push_str(e),
+            }
         }
+        let hpo_content: Vec<String> = hpo_id_list
+            .iter()
+            .map(|tid| tid_to_value_map.get(tid).map_or("na", |v| v).to_string())
+            .collect();
+        let trailing_metadata = vec![String::new(); header_duplet_row.trailing_metadata().len()];
+        let ppkt_row = Self { 
+            header: header_duplet_row, 
+            individual_bundle: IndividualBundle::from_dto(individual_dto), 
+            disease_bundle_list: self.disease_bundle_list.clone(), 
+            gene_var_bundle_list: GeneVariantBundle::from_dto_list(gene_variant_list), 
+            hpo_content,
+            trailing_metadata
+        };
+        verrs.push_verr_result(ppkt_row.check_for_errors());
+        verrs.ok()?;
+        Ok(ppkt_row)
     }
 
     /// This function checks the current PpktRow for syntactical errors
     pub fn check_for_errors(&self) -> std::result::Result<(), ValidationErrors> {
         let mut verrs = ValidationErrors::new();
         verrs.push_verr_result(self.individual_bundle.do_qc());
+        verrs.push_result(age_util::check_onset_before_last_encounter(
+            self.individual_bundle.age_of_onset(), 
+            self.individual_bundle.age_at_last_encounter()));
         for db in &self.disease_bundle_list {
             verrs.push_verr_result(db.do_qc());
         }
@@ -210,6 +411,31 @@ impl PpktRow {
         verrs.ok()
     }
 
+    /// Check each cell of this row (individual, disease, gene/variant, demographic, HPO, and optional columns) and
+    /// return the problems with their locations. `matrix_row` is the index of this row in the template matrix.
+    pub fn get_cell_issues(&self, matrix_row: usize) -> Vec<QcIssue> {
+        let mut issues: Vec<QcIssue> = Vec::new();
+        let fixed_duplets = self.header.get_fixed_duplets();
+        for (col, (duplet, value)) in fixed_duplets.iter().zip(self.get_fixed_values().iter()).enumerate() {
+            if let Err(msg) = duplet.qc_data(value) {
+                issues.push(QcIssue::cell_error(matrix_row, col, msg));
+            }
+        }
+        let hpo_idx = fixed_duplets.len();
+        for (i, item) in self.hpo_content.iter().enumerate() {
+            if let Err(msg) = check_hpo_table_cell(item) {
+                issues.push(QcIssue::cell_error(matrix_row, hpo_idx + i, msg));
+            }
+        }
+        let optional_idx = hpo_idx + self.hpo_content.len();
+        for (i, (duplet, value)) in self.header.get_optional_duplets().iter().zip(self.get_optional_values()).enumerate() {
+            if let Err(msg) = duplet.qc_data(&value) {
+                issues.push(QcIssue::cell_error(matrix_row, optional_idx + i, msg));
+            }
+        }
+        issues
+    }
+
     /// Update current HPO values according to a new header.
     /// The new header may contain HPO terms that the current PpktRow does not have
     /// in this case, we must add 'na' as the value for these terms.
@@ -239,7 +465,37 @@ impl PpktRow {
             individual_bundle: self.individual_bundle.clone(), 
             disease_bundle_list: self.disease_bundle_list.clone(), 
             gene_var_bundle_list: self.gene_var_bundle_list.clone(), 
-            hpo_content: content 
+            hpo_content: content,
+            trailing_metadata: self.trailing_metadata.clone()
+        })
+    }
+
+    /// Set the values of the HPO columns listed in the map (e.g., "observed", "P3Y"). The values of other HPO columns are retained.
+    /// All TermIds of the map must be present in the header of this row.
+    pub fn update_hpo_values(
+        &self,
+        tid_to_value_map: &HashMap<TermId, String>
+    ) -> std::result::Result<Self, ValidationErrors> {
+        let mut verrs = ValidationErrors::new();
+        let hpo_id_list = self.header.get_hpo_id_list()?;
+        for tid in tid_to_value_map.keys() {
+            if !hpo_id_list.contains(tid) {
+                verrs.push_str(format!("HPO term {} is not in the template header", tid));
+            }
+        }
+        verrs.ok()?;
+        let hpo_content: Vec<String> = hpo_id_list
+            .iter()
+            .zip(self.hpo_content.iter())
+            .map(|(tid, value)| tid_to_value_map.get(tid).unwrap_or(value).clone())
+            .collect();
+        Ok(Self { 
+            header: self.header.clone(), 
+            individual_bundle: self.individual_bundle.clone(), 
+            disease_bundle_list: self.disease_bundle_list.clone(), 
+            gene_var_bundle_list: self.gene_var_bundle_list.clone(), 
+            hpo_content,
+            trailing_metadata: self.trailing_metadata.clone()
         })
     }
 
@@ -334,6 +590,7 @@ impl PpktRow {
             disease_bundle_list: self.disease_bundle_list.clone(),
             gene_var_bundle_list: self.gene_var_bundle_list.clone(),
             hpo_content: updated_hpo,
+            trailing_metadata: self.trailing_metadata.clone(),
         })
     }
 
@@ -449,6 +706,84 @@ mod test {
 
     }
 
+    /// A data row of a melded template with two disease bundles and two gene/variant bundles
+    #[fixture]
+    fn melded_row() -> Vec<String> {
+        let row: Vec<&str> = vec![
+            "PMID:29482508", "A case with two diagnoses", "proband", "",
+            "OMIM:135100", "Fibrodysplasia ossificans progressiva", "OMIM:154700", "Marfan syndrome",
+            "HGNC:171", "ACVR1", "NM_001111067.4", "c.617G>A", "na", "",
+            "HGNC:3603", "FBN1", "NM_000138.5", "c.8242G>T", "na", "",
+            "P9Y", "P16Y", "no", "M", "na", "observed", "excluded"];
+        row.into_iter().map(|s| s.to_owned()).collect()
+    }
+
+    fn melded_header() -> Arc<HeaderDupletRow> {
+        let hpo_duplets = vec![HpoTermDuplet::new("Failure to thrive", "HP:0001508"), HpoTermDuplet::new("Seizure", "HP:0001250")];
+        Arc::new(HeaderDupletRow::from_hpo_duplets(hpo_duplets, TemplateType::Melded))
+    }
+
+    #[rstest]
+    fn test_melded_row_round_trip(melded_row: Vec<String>) {
+        let ppkt_row = PpktRow::from_row(melded_header(), melded_row.clone()).unwrap();
+        assert_eq!(2, ppkt_row.disease_count());
+        assert_eq!(2, ppkt_row.gene_variant_count());
+        assert_eq!("OMIM:154700", ppkt_row.get_disease_dto_list()[1].disease_id);
+        let mut values = ppkt_row.get_fixed_values();
+        values.extend(ppkt_row.hpo_content().iter().cloned());
+        assert_eq!(melded_row, values);
+    }
+
+    #[rstest]
+    fn test_melded_row_collects_all_errors(mut melded_row: Vec<String>) {
+        melded_row[4] = "OMIM135100".to_string();
+        melded_row[6] = "OMIM154700".to_string();
+        melded_row[25] = "obsreved".to_string();
+        let verrs = PpktRow::from_row(melded_header(), melded_row).unwrap_err();
+        assert_eq!(3, verrs.errors().len());
+    }
+
+    #[rstest]
+    fn test_mendelian_from_dto(original_matrix: Vec<Vec<String>>, hpo: Arc<FullCsrOntology>) {
+        let header = Arc::new(HeaderDupletRow::mendelian(&original_matrix, hpo).unwrap());
+        let ppkt_row = PpktRow::from_row(header.clone(), original_matrix[2].clone()).unwrap();
+        let individual_dto = IndividualBundleDto::new("PMID:123", "A new case", "Individual 7", "", 
+            "Infantile onset", "P32Y", "na", "F");
+        let gene_variant_list = vec![GeneVariantBundleDto::new("HGNC:171", "ACVR1", "NM_001111067.4", "c.617G>A", "na", "")];
+        let annotations = vec![HpoTermDto::new("HP:0001822", "Hallux valgus", "observed"),
+            HpoTermDto::new("HP:0009778", "Short thumb", "excluded")];
+        let mut existing_annotation_map = HashMap::new();
+        existing_annotation_map.insert(TermId::from_str("HP:0004209").unwrap(), "P3Y".to_string());
+        existing_annotation_map.insert(TermId::from_str("HP:0009778").unwrap(), "observed".to_string());
+        let new_row = ppkt_row.mendelian_from_dto(header, individual_dto, gene_variant_list, annotations, existing_annotation_map).unwrap();
+        assert_eq!("Individual 7", new_row.get_individual_dto().individual_id);
+        assert_eq!("OMIM:135100", new_row.get_disease_dto_list()[0].disease_id);
+        assert_eq!(vec!["P3Y", "observed", "na", "na", "na", "na", "excluded"], new_row.hpo_content());
+    }
+
+    #[rstest]
+    fn test_mendelian_from_dto_unknown_term(original_matrix: Vec<Vec<String>>, hpo: Arc<FullCsrOntology>) {
+        let header = Arc::new(HeaderDupletRow::mendelian(&original_matrix, hpo).unwrap());
+        let ppkt_row = PpktRow::from_row(header.clone(), original_matrix[2].clone()).unwrap();
+        let individual_dto = ppkt_row.get_individual_dto();
+        let gene_variant_list = ppkt_row.get_gene_var_dto_list();
+        let annotations = vec![HpoTermDto::new("HP:0001250", "Seizure", "observed"),
+            HpoTermDto::new("HP:0001822", "Hallux valgus", "obsreved")];
+        let verrs = ppkt_row.mendelian_from_dto(header, individual_dto, gene_variant_list, annotations, HashMap::new()).unwrap_err();
+        let errors = verrs.errors();
+        assert_eq!(2, errors.len());
+        assert_eq!("HPO term HP:0001250 (Seizure) is not in the template header", errors[0]);
+    }
+
+    #[rstest]
+    fn test_onset_after_last_encounter(mut melded_row: Vec<String>) {
+        melded_row[20] = "P16Y".to_string();
+        melded_row[21] = "P9Y".to_string();
+        let ppkt_row = PpktRow::from_row(melded_header(), melded_row).unwrap();
+        let errors = ppkt_row.check_for_errors().unwrap_err().errors();
+        assert_eq!(vec!["Age of onset 'P16Y' is later than age at last encounter 'P9Y'".to_string()], errors);
+    }
+
 
 }
 
@@ -40,6 +40,25 @@ pub fn check_valid_curie(s: &str) -> Result<bool> {
     Ok(true)
 }
 
+/// Prefixes of the disease identifiers that are allowed in templates (OMIM, MONDO, and Orphanet)
+pub const DISEASE_ID_PREFIXES: [&str; 3] = ["OMIM", "MONDO", "ORPHA"];
+
+/// Check the prefix of a well-formed disease CURIE against [`DISEASE_ID_PREFIXES`] and the length of its suffix.
+/// OMIM identifiers have six digits and Orphanet identifiers are positive integers with at most seven digits.
+pub fn check_disease_id_prefix(value: &str) -> std::result::Result<(), String> {
+    let (prefix, suffix) = value.split_once(':').unwrap_or((value, ""));
+    if !DISEASE_ID_PREFIXES.contains(&prefix) {
+        return Err(format!("Disease id has invalid prefix: '{}'", value));
+    }
+    match prefix {
+        "OMIM" if suffix.len() != 6 => Err(format!("OMIM identifiers must have 6 digits: '{}'", value)),
+        "ORPHA" if suffix.len() > 7 || !suffix.parse::<u32>().is_ok_and(|n| n > 0) => {
+            Err(format!("Invalid Orphanet identifier: '{}'", value))
+        },
+        _ => Ok(()),
+    }
+}
+
 /// We use the CURIE struct to represent PMIDs, disease identifiers, and HGNC identifiers
 /// We use separate creator objects to ensure that the prefix is correct
 #[derive(Clone, Debug)]
@@ -56,6 +75,13 @@ impl Curie {
                 msg: format!("Invalid PubMed prefix: '{}'", value),
             });
         }
+        // PubMed identifiers are positive integers (currently eight digits)
+        let suffix = value.split_once(':').map_or("", |(_, suffix)| suffix);
+        if !suffix.parse::<u32>().is_ok_and(|n| n > 0) {
+            return Err(Error::CurieError {
+                msg: format!("Invalid PMID: '{}' (must be a positive integer)", value),
+            });
+        }
         return Ok(Curie {
             curie_value: value.to_string(),
         });
@@ -71,10 +97,8 @@ impl Curie {
             return Err(Error::DiseaseIdError {
                 msg: format!("Invalid disease identifier: {}", valid_curie.err().unwrap()),
             });
-        } else if !(value.starts_with("OMIM") || value.starts_with("MONDO")) {
-            return Err(Error::DiseaseIdError {
-                msg: format!("Disease id has invalid prefix: '{}'", value),
-            });
+        } else if let Err(msg) = check_disease_id_prefix(value) {
+            return Err(Error::DiseaseIdError { msg });
         } else {
             return Ok(Curie {
                 curie_value: value.to_string(),
@@ -103,6 +127,7 @@ impl Curie {
 #[cfg(test)]
 mod test {
     use super::*;
+    use rstest::rstest;
 
     #[test]
     fn test_pmid_ctor() {
@@ -128,6 +153,30 @@ mod test {
         }
     }
 
+    #[rstest]
+    #[case("PMID:29482508", None)]
+    #[case("PMID:0", Some("Invalid PMID: 'PMID:0' (must be a positive integer)"))]
+    #[case("PMID:000", Some("Invalid PMID: 'PMID:000' (must be a positive integer)"))]
+    #[case("PMID:123456789012", Some("Invalid PMID: 'PMID:123456789012' (must be a positive integer)"))]
+    fn test_pmid_suffix(#[case] value: &str, #[case] expected_error: Option<&str>) {
+        let result = Curie::new_pmid(value);
+        assert_eq!(expected_error.map(|s| s.to_string()), result.err().map(|e| e.to_string()));
+    }
+
+    #[rstest]
+    #[case("OMIM:617865", None)]
+    #[case("OMIM:17865", Some("OMIM identifiers must have 6 digits: 'OMIM:17865'"))]
+    #[case("OMIM:6178650", Some("OMIM identifiers must have 6 digits: 'OMIM:6178650'"))]
+    #[case("MONDO:0007739", None)]
+    #[case("ORPHA:558", None)]
+    #[case("ORPHA:0", Some("Invalid Orphanet identifier: 'ORPHA:0'"))]
+    #[case("ORPHA:12345678", Some("Invalid Orphanet identifier: 'ORPHA:12345678'"))]
+    #[case("ORPHANET:558", Some("Disease id has invalid prefix: 'ORPHANET:558'"))]
+    fn test_disease_id_suffix(#[case] value: &str, #[case] expected_error: Option<&str>) {
+        let result = Curie::new_disease_id(value);
+        assert_eq!(expected_error.map(|s| s.to_string()), result.err().map(|e| e.to_string()));
+    }
+
     #[test]
     fn test_disease_id() {
         let tests = vec![
@@ -0,0 +1,122 @@
+//! This module contains utilities to read a phenopacket-input-formatted template that was exported as CSV or TSV
+//!
+//! The rows are returned in the same shape as [`crate::template::excel::read_excel_to_dataframe`], so that templates
+//! exported from Excel or Google Sheets can be loaded by the same pipeline.
+
+use std::fs;
+
+use crate::error::{Error, Result};
+
+
+/// Read a CSV or TSV file (e.g., `delimiter` = '\t') with the two header rows and the data rows of a template.
+/// As with the Excel reader, empty data cells (including empty trailing cells of short rows) are replaced by na.
+pub fn read_delimited_to_matrix(file_path: &str, delimiter: char) -> Result<Vec<Vec<String>>> {
+    let contents = fs::read_to_string(file_path)
+        .map_err(|e| Error::custom(format!("Could not open delimited file at '{}': {}", file_path, e)))?;
+    parse_delimited(&contents, delimiter)
+}
+
+/// Arrange the records of the file into a rectangular matrix whose width is given by the first header row
+fn parse_delimited(contents: &str, delimiter: char) -> Result<Vec<Vec<String>>> {
+    let mut records: Vec<Vec<String>> = split_records(contents, delimiter)
+        .into_iter()
+        .filter(|record| record.iter().any(|cell| !cell.is_empty())) // e.g., blank final line
+        .collect();
+    if records.len() < 3 {
+        return Err(Error::empty_template(records.len()));
+    }
+    for record in records.iter_mut() {
+        while record.last().is_some_and(|cell| cell.is_empty()) {
+            record.pop();
+        }
+    }
+    let n1 = records[0].len();
+    let n2 = records[1].len();
+    if n1 != n2 {
+        return Err(Error::custom(format!("Malformed headers: expected {} fields, got {}", n2, n1)));
+    }
+    let mut list_of_rows: Vec<Vec<String>> = Vec::with_capacity(records.len());
+    for (i, mut record) in records.into_iter().enumerate() {
+        if record.len() > n1 {
+            return Err(Error::custom(format!("Malformed line:: expected {} fields, got {}", n1, record.len())));
+        }
+        record.resize(n1, String::new());
+        if i > 1 {
+            for cell in record.iter_mut().filter(|cell| cell.is_empty()) {
+                *cell = "na".to_string();
+            }
+        }
+        list_of_rows.push(record);
+    }
+    Ok(list_of_rows)
+}
+
+/// Split the text into records and fields. Fields may be quoted ("a, b"), with "" representing a quote character;
+/// quoted fields may contain the delimiter and line breaks.
+fn split_records(contents: &str, delimiter: char) -> Vec<Vec<String>> {
+    let mut records: Vec<Vec<String>> = Vec::new();
+    let mut record: Vec<String> = Vec::new();
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut chars = contents.trim_start_matches('\u{feff}').chars().peekable();
+    while let Some(c) = chars.next() {
+        if in_quotes {
+            match c {
+                '"' if chars.peek() == Some(&'"') => {
+                    field.push('"');
+                    chars.next();
+                },
+                '"' => in_quotes = false,
+                _ => field.push(c),
+            }
+        } else if c == '"' && field.is_empty() {
+            in_quotes = true;
+        } else if c == delimiter {
+            record.push(std::mem::take(&mut field));
+        } else if c == '\n' || c == '\r' {
+            if c == '\r' && chars.peek() == Some(&'\n') {
+                chars.next();
+            }
+            record.push(std::mem::take(&mut field));
+            records.push(std::mem::take(&mut record));
+        } else {
+            field.push(c);
+        }
+    }
+    if !field.is_empty() || !record.is_empty() {
+        record.push(field);
+        records.push(record);
+    }
+    records
+}
+
+// region:    --- Tests
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tsv_with_trailing_empty_cells() {
+        let tsv = "PMID\ttitle\tHPO\nCURIE\tstr\tna\nPMID:1\tA title\t\nPMID:2\t\n";
+        let matrix = parse_delimited(tsv, '\t').unwrap();
+        assert_eq!(4, matrix.len());
+        assert_eq!(vec!["PMID:1", "A title", "na"], matrix[2]);
+        assert_eq!(vec!["PMID:2", "na", "na"], matrix[3]);
+    }
+
+    #[test]
+    fn test_csv_with_quoted_fields() {
+        let csv = "PMID,title\r\nCURIE,str\r\nPMID:1,\"Genes, variants, and \"\"phenotypes\"\"\"\r\n";
+        let matrix = parse_delimited(csv, ',').unwrap();
+        assert_eq!("Genes, variants, and \"phenotypes\"", matrix[2][1]);
+    }
+
+    #[test]
+    fn test_too_few_rows() {
+        let result = parse_delimited("PMID\ttitle\nCURIE\tstr\n\n", '\t');
+        assert!(matches!(result, Err(Error::TemplateError { .. })));
+    }
+}
+
+// endregion: --- Tests
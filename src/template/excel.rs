@@ -3,12 +3,14 @@
 //! It ingests an Excel file and returns a DataFrame containing the contents of the file.
 //! It throws an error if there are syntactic errors in the input file.
 
-use calamine::{open_workbook, Reader, Xlsx, XlsxError};
+use calamine::{open_workbook, Data, Dimensions, Range, Reader, Xlsx, XlsxError};
 use std::error::Error;
 
 
 /// Reads in data from the initial formatversion of phenopacket store (up to version 0.1.24)
-/// This function replaces any empty cells in the data with na
+/// This function replaces any empty cells in the data with na.
+/// Formula cells yield the value that Excel cached when the file was saved, and each cell of a merged region
+/// gets the value of the top-left (anchor) cell, so that the matrix matches what the curator sees.
 pub fn read_excel_to_dataframe(file_path: &str) -> Result<Vec<Vec<String>>, Box<dyn Error>> {
     let mut workbook: Xlsx<_> = open_workbook(file_path).map_err(|e: XlsxError| {
         format!(
@@ -20,15 +22,22 @@ pub fn read_excel_to_dataframe(file_path: &str) -> Result<Vec<Vec<String>>, Box<
     let range = workbook
         .worksheet_range("Sheet1")
         .map_err(|e: XlsxError| format!("Error reading workbook: {}", e.to_string()))?;
-    let mut row_iter = range.rows(); // Create a single iterator over the rows
-    let first_row = row_iter
+    workbook
+        .load_merged_regions()
+        .map_err(|e: XlsxError| format!("Error reading merged cells: {}", e))?;
+    let merged_regions: Vec<Dimensions> = workbook
+        .merged_regions_by_sheet("Sheet1")
+        .into_iter()
+        .map(|(_, _, dimensions)| *dimensions)
+        .collect();
+    let cells = get_cell_matrix(&range, &merged_regions);
+    let mut row_iter = cells.into_iter(); // Create a single iterator over the rows
+    let first_row_headers = row_iter
         .next()
         .ok_or(calamine::Error::Msg("No data in the worksheet"))?;
-    let first_row_headers: Vec<String> = first_row.iter().map(|cell| cell.to_string()).collect();
-    let second_row = row_iter
+    let second_row_headers = row_iter
         .next()
         .ok_or(calamine::Error::Msg("No data in the worksheet"))?;
-    let second_row_headers: Vec<String> = second_row.iter().map(|cell| cell.to_string()).collect();
     let n1 = first_row_headers.len();
     let n2 = second_row_headers.len();
     if n1 != n2 {
@@ -43,9 +52,8 @@ pub fn read_excel_to_dataframe(file_path: &str) -> Result<Vec<Vec<String>>, Box<
     list_of_rows.push(second_row_headers);
     // Now, iterate over the remaining rows
     for row in row_iter {
-        let row_data: Vec<String> = row.iter()
-            .map(|cell| {
-                let s = cell.to_string();
+        let row_data: Vec<String> = row.into_iter()
+            .map(|s| {
                 if s.is_empty() {
                     "na".to_string()
                 } else {
@@ -68,6 +76,37 @@ pub fn read_excel_to_dataframe(file_path: &str) -> Result<Vec<Vec<String>>, Box<
     Ok(list_of_rows)
 }
 
+/// Convert the worksheet to a matrix of Strings and copy the value of the anchor cell of each merged region
+/// to the other cells of the region. The coordinates of merged regions are absolute, whereas the range
+/// starts at the first non-empty cell of the worksheet.
+fn get_cell_matrix(range: &Range<Data>, merged_regions: &[Dimensions]) -> Vec<Vec<String>> {
+    let mut cells: Vec<Vec<String>> = range.rows()
+        .map(|row| row.iter().map(|cell| cell.to_string()).collect())
+        .collect();
+    let (row_offset, col_offset) = range.start().unwrap_or((0, 0));
+    let to_index = |(row, col): (u32, u32)| -> Option<(usize, usize)> {
+        let r = row.checked_sub(row_offset)? as usize;
+        let c = col.checked_sub(col_offset)? as usize;
+        Some((r, c))
+    };
+    for region in merged_regions {
+        let anchor = match to_index(region.start).and_then(|(r, c)| cells.get(r)?.get(c).cloned()) {
+            Some(value) => value,
+            None => continue,
+        };
+        for row in region.start.0..=region.end.0 {
+            for col in region.start.1..=region.end.1 {
+                if let Some(cell) = to_index((row, col)).and_then(|(r, c)| cells.get_mut(r)?.get_mut(c)) {
+                    if cell.is_empty() {
+                        *cell = anchor.clone();
+                    }
+                }
+            }
+        }
+    }
+    cells
+}
+
 // region:    --- Tests
 
 #[cfg(test)]
@@ -87,6 +126,22 @@ mod tests {
         assert_eq!(expected, error_msg);
         Ok(())
     }
+
+    /// B1:C1 and A3:A4 are merged; C3 is a formula (=B3) with a cached value; C4 is empty
+    #[test]
+    fn test_merged_cells_and_formula() -> Result<()> {
+        let matrix = read_excel_to_dataframe("resources/excel/merged_formula.xlsx")?;
+        let expected: Vec<Vec<String>> = vec![
+            vec!["PMID", "title", "title"],
+            vec!["CURIE", "str", "str"],
+            vec!["PMID:1", "Title X", "Title X"],
+            vec!["PMID:1", "Other", "na"],
+        ].into_iter()
+            .map(|row| row.into_iter().map(str::to_string).collect())
+            .collect();
+        assert_eq!(expected, matrix);
+        Ok(())
+    }
 }
 
 // endregion: --- Tests
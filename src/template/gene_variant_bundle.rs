@@ -1,7 +1,7 @@
 use std::{rc::Rc, sync::Arc};
 use once_cell::sync::Lazy;
 
-use crate::{dto::{template_dto::GeneVariantBundleDto, validation_errors::ValidationErrors}, header::gene_variant_header::GeneVariantHeader};
+use crate::{dto::{template_dto::GeneVariantBundleDto, validation_errors::ValidationErrors}, header::{allele_util, gene_variant_header::GeneVariantHeader}};
 
 
 static SHARED_HEADER: Lazy<Arc<GeneVariantHeader>> = Lazy::new(|| {
@@ -17,6 +17,8 @@ pub struct GeneVariantBundle {
     pub(crate) allele1: String,
     pub(crate) allele2: String,
     pub(crate) variant_comment: String,
+    /// Messages describing alleles whose invisible whitespace was normalized on entry
+    pub(crate) normalization_notes: Vec<String>,
 }
 
 
@@ -29,14 +31,31 @@ impl GeneVariantBundle {
         allele2: &str,
         variant_comment: &str) 
     -> Self {
+        let mut normalization_notes = Vec::new();
+        let allele1 = Self::normalize_allele("allele_1", allele1, &mut normalization_notes);
+        let allele2 = Self::normalize_allele("allele_2", allele2, &mut normalization_notes);
         Self { 
             header: SHARED_HEADER.clone(), 
             hgnc_id: hgnc_id.to_string(), 
             gene_symbol: gene_symbol.to_string(), 
             transcript: transcript.to_string(), 
-            allele1: allele1.to_string(), 
-            allele2: allele2.to_string(), 
-            variant_comment: variant_comment.to_string()
+            allele1, 
+            allele2, 
+            variant_comment: variant_comment.to_string(),
+            normalization_notes
+        }
+    }
+
+    /// Alleles pasted from PDFs often contain non-breaking or zero-width spaces that look fine
+    /// but are rejected by the QC or by VariantValidator. We normalize them and record a note.
+    fn normalize_allele(column: &str, allele: &str, notes: &mut Vec<String>) -> String {
+        match allele_util::normalize_allele(allele) {
+            Some(normalized) => {
+                let codes = allele_util::invisible_char_codes(allele).join(", ");
+                notes.push(format!("{column}: normalized invisible whitespace ({codes}) in '{normalized}'"));
+                normalized
+            },
+            None => allele.to_string(),
         }
     }
 
@@ -61,14 +80,18 @@ impl GeneVariantBundle {
     }
 
     pub fn from_dto(dto: GeneVariantBundleDto) -> Self {
+        let mut normalization_notes = Vec::new();
+        let allele1 = Self::normalize_allele("allele_1", &dto.allele1, &mut normalization_notes);
+        let allele2 = Self::normalize_allele("allele_2", &dto.allele2, &mut normalization_notes);
         Self { 
             header: SHARED_HEADER.clone(), 
             hgnc_id: dto.hgnc_id, 
             gene_symbol: dto.gene_symbol, 
             transcript: dto.transcript, 
-            allele1: dto.allele1, 
-            allele2: dto.allele2, 
-            variant_comment: dto.variant_comment
+            allele1, 
+            allele2, 
+            variant_comment: dto.variant_comment,
+            normalization_notes
         }
     }
 
@@ -97,4 +120,8 @@ impl GeneVariantBundle {
     pub fn variant_comment(&self)  ->&str{
         &self.variant_comment
     }
+
+    pub fn normalization_notes(&self) -> &[String] {
+        &self.normalization_notes
+    }
 }
\ No newline at end of file
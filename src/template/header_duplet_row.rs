@@ -5,7 +5,7 @@
 
 
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fmt::format;
 use std::hash::DefaultHasher;
 use std::str::FromStr;
@@ -19,7 +19,7 @@ use ontolius::{Identified, TermId};
 use serde::de;
 
 use crate::dto::hpo_term_dto::HpoTermDto;
-use crate::dto::template_dto::{HeaderDupletDto};
+use crate::dto::template_dto::{CellKindDto, HeaderDupletDto};
 use crate::dto::validation_errors::ValidationErrors;
 use crate::header::disease_header::DiseaseHeader;
 use crate::header::duplet_item::DupletItem;
@@ -40,14 +40,6 @@ const OBSERVED: &str = "observed";
 
 
 
-const MENDELIAN_INDIVIDUAL_IDX: usize = 0; 
-const MENDELIAN_DISEASE_IDX: usize = 4;
-const MENDELIAN_GENE_VAR_IDX: usize = 6;
-const MENDELIAN_DEMOGRAPHIC_IDX: usize = 12;
-const MENDELIAN_SEPARATOR_IDX: usize = 16;
-const MENDELIAN_HPO_IDX: usize = 17;
-
-
 /// Number of columns in the Individual section
 const NUMBER_OF_INDIVIDUAL_FIELDS: usize = 4;
 /// Number of columns in the Disease/Gene/Variant bundle section
@@ -57,13 +49,66 @@ const NUMBER_OF_DEMOGRAPHIC_FIELDS: usize = 4;
 /// Separator field (HPO/na)
 const NUMBER_OF_SEPARATOR_FIELDS: usize = 1;
 
-const INDIVIDUAL_IDX: usize = 0;
-const DISEASE_IDX: usize = 4;
-const GENE_VAR_IDX: usize = 6;
-const DEMOGRAPHIC_IDX: usize = 12;
-const SEPARATOR_IDX: usize = 16;
-const HPO_SECTION_IDX: usize = 17;
+/// Number of columns of one disease bundle (disease_id, disease_label)
+const NUMBER_OF_DISEASE_FIELDS: usize = 2;
+/// Number of columns of one gene/variant bundle (HGNC_id ... variant.comment)
+const NUMBER_OF_GENE_VARIANT_FIELDS: usize = 6;
+
+/// Column indices of the sections of the template. The indices depend on the template type, because
+/// melded templates have two disease bundles and two gene/variant bundles instead of one each.
+/// This is the single source of truth for the column layout; the header and the rows should not hard-code any offsets.
+#[derive(Clone, Debug)]
+pub struct HeaderIndexer {
+    n_diseases: usize,
+    n_gene_variants: usize,
+}
+
+impl HeaderIndexer {
+    pub fn new(template_type: &TemplateType) -> Self {
+        match template_type {
+            TemplateType::Mendelian => Self { n_diseases: 1, n_gene_variants: 1 },
+            TemplateType::Melded => Self { n_diseases: 2, n_gene_variants: 2 },
+        }
+    }
+
+    pub fn individual_idx(&self) -> usize {
+        0
+    }
+
+    pub fn disease_idx(&self) -> usize {
+        self.individual_idx() + NUMBER_OF_INDIVIDUAL_FIELDS
+    }
+
+    pub fn gene_var_idx(&self) -> usize {
+        self.disease_idx() + NUMBER_OF_DISEASE_FIELDS * self.n_diseases
+    }
+
+    /// Start index of each disease bundle
+    pub fn disease_bundle_indices(&self) -> Vec<usize> {
+        (0..self.n_diseases)
+            .map(|i| self.disease_idx() + NUMBER_OF_DISEASE_FIELDS * i)
+            .collect()
+    }
+
+    /// Start index of each gene/variant bundle
+    pub fn gene_var_bundle_indices(&self) -> Vec<usize> {
+        (0..self.n_gene_variants)
+            .map(|i| self.gene_var_idx() + NUMBER_OF_GENE_VARIANT_FIELDS * i)
+            .collect()
+    }
+
+    pub fn demographic_idx(&self) -> usize {
+        self.gene_var_idx() + NUMBER_OF_GENE_VARIANT_FIELDS * self.n_gene_variants
+    }
 
+    pub fn separator_idx(&self) -> usize {
+        self.demographic_idx() + NUMBER_OF_DEMOGRAPHIC_FIELDS
+    }
+
+    pub fn hpo_idx(&self) -> usize {
+        self.separator_idx() + NUMBER_OF_SEPARATOR_FIELDS
+    }
+}
 
 /// Total number of constant fields (columns) in the Mendelian template
 const N_CONSTANT_FIELDS_MENDELIAN: usize = 
@@ -79,6 +124,14 @@ pub struct HeaderDupletRow {
     gene_variant_header_list: Vec<GeneVariantHeader>,
     hpo_duplets: Vec<HpoTermDuplet>,
     template_type: TemplateType,
+    /// True if the template has the optional ancestry column (after the HPO columns)
+    has_ancestry: bool,
+    /// True if the template has the optional age_at_death, cause_of_death_id, and cause_of_death_label columns
+    has_vital_status: bool,
+    /// True if the template has the optional karyotypic_sex column
+    has_karyotypic_sex: bool,
+    /// Headers of the columns after the metadata marker, which are preserved verbatim but not interpreted
+    trailing_metadata: Vec<HeaderDupletDto>,
 }
 
 
@@ -88,18 +141,59 @@ impl HeaderDupletRow {
         hpo: Arc<FullCsrOntology>,
     ) -> std::result::Result<Self, ValidationErrors> {
         Self::qc_matrix_dimensions(matrix)?;
+        Self::check_header_row_order(matrix, &Self::mendelian_from_hpo_duplets(vec![]).get_fixed_duplets())?;
+        let indexer = HeaderIndexer::new(&TemplateType::Mendelian);
         /// first Q/C the constant part of the Mendelian header
-        let iheader = IndividualHeader::from_matrix(matrix, MENDELIAN_DEMOGRAPHIC_IDX)?;
-        let dheader = DiseaseHeader::from_matrix(matrix, MENDELIAN_DISEASE_IDX)?;
-        let gheader = GeneVariantHeader::from_matrix(matrix, MENDELIAN_GENE_VAR_IDX)?;
+        let iheader = IndividualHeader::from_matrix(matrix, indexer.demographic_idx())?;
+        let dheader = DiseaseHeader::from_matrix(matrix, indexer.disease_idx())?;
+        let gheader = GeneVariantHeader::from_matrix(matrix, indexer.gene_var_idx())?;
         /// If we get here, the constant part is OK and we can check the HPO columns
         let mut hpo_duplet_list: Vec<HpoTermDuplet> = Vec::new();
-        let n = matrix[0].len(); // previously checked in qc_matrix_dimensions
-        for i in MENDELIAN_HPO_IDX..n {
+        let mut n = matrix[0].len(); // previously checked in qc_matrix_dimensions
+        // The optional trailing metadata section starts with a marker column and is not interpreted
+        let marker = DupletItem::metadata_separator();
+        let mut trailing_metadata: Vec<HeaderDupletDto> = Vec::new();
+        if let Some(marker_idx) = (indexer.hpo_idx()..n).find(|&i| matrix[0][i] == marker.row1()) {
+            marker.check_column_labels(matrix, marker_idx)
+                .map_err(ValidationErrors::from_one_err)?;
+            trailing_metadata = ((marker_idx + 1)..n)
+                .map(|i| HeaderDupletDto::new(&matrix[0][i], &matrix[1][i]))
+                .collect();
+            n = marker_idx;
+        }
+        // The optional karyotypic sex column is the last column before the metadata section
+        let karyotypic_sex_duplet = DupletItem::karyotypic_sex();
+        let has_karyotypic_sex = matrix[0][n-1] == karyotypic_sex_duplet.row1();
+        if has_karyotypic_sex {
+            karyotypic_sex_duplet.check_column_labels(matrix, n-1)
+                .map_err(ValidationErrors::from_one_err)?;
+            n -= 1;
+        }
+        // The optional vital status columns come before the karyotypic sex column
+        let vital_status_duplets = Self::vital_status_duplets();
+        let has_vital_status = n >= indexer.hpo_idx() + vital_status_duplets.len()
+            && matrix[0][n-1] == vital_status_duplets[vital_status_duplets.len()-1].row1();
+        if has_vital_status {
+            let start = n - vital_status_duplets.len();
+            for (i, duplet) in vital_status_duplets.iter().enumerate() {
+                duplet.check_column_labels(matrix, start + i)
+                    .map_err(ValidationErrors::from_one_err)?;
+            }
+            n = start;
+        }
+        // The optional ancestry column comes before the vital status columns; older templates do not have it
+        let ancestry_duplet = DupletItem::ancestry();
+        let has_ancestry = matrix[0][n-1] == ancestry_duplet.row1();
+        if has_ancestry {
+            ancestry_duplet.check_column_labels(matrix, n-1)
+                .map_err(ValidationErrors::from_one_err)?;
+            n -= 1;
+        }
+        for i in indexer.hpo_idx()..n {
             let hdup = HpoTermDuplet::new(&matrix[0][i], &matrix[1][i]);
             hpo_duplet_list.push(hdup);
         }
-        Self::check_separator(matrix)?;
+        Self::check_separator(matrix, &indexer)?;
         let hpo_util = HpoUtil::new(hpo.clone());
         hpo_util.check_hpo_duplets(&hpo_duplet_list)?;
         
@@ -108,19 +202,58 @@ impl HeaderDupletRow {
             disease_header_list: vec![DiseaseHeader::new()], 
             gene_variant_header_list: vec![GeneVariantHeader::new()], 
             hpo_duplets: hpo_duplet_list,
-            template_type: TemplateType::Mendelian
+            template_type: TemplateType::Mendelian,
+            has_ancestry,
+            has_vital_status,
+            has_karyotypic_sex,
+            trailing_metadata
         })
     }
 
 
-    fn check_separator(matrix: &Vec<Vec<String>>) -> std::result::Result<(), ValidationErrors> {
+    /// Detect the common import mistake of swapping the two header rows, i.e., type hints (e.g., CURIE)
+    /// in row 0 and column labels (e.g., PMID) in row 1, which would otherwise lead to many confusing errors.
+    /// The rows are considered to be swapped if more of the constant columns match in swapped than in correct order.
+    fn check_header_row_order(
+        matrix: &[Vec<String>], 
+        fixed_duplets: &[DupletItem]
+    ) -> std::result::Result<(), ValidationErrors> {
+        let (row0, row1) = match (matrix.first(), matrix.get(1)) {
+            (Some(row0), Some(row1)) => (row0, row1),
+            _ => { return Ok(()); } // checked in qc_matrix_dimensions
+        };
+        let mut n_in_order = 0;
+        let mut n_swapped = 0;
+        for (duplet, (h1, h2)) in fixed_duplets.iter().zip(row0.iter().zip(row1.iter())) {
+            if h1 == duplet.row1() && h2 == duplet.row2() {
+                n_in_order += 1;
+            } else if h1 == duplet.row2() && h2 == duplet.row1() {
+                n_swapped += 1;
+            }
+        }
+        if n_swapped > n_in_order {
+            Err(ValidationErrors::from_one_err(format!(
+                "Header rows appear swapped: row 0 should contain the column labels (e.g., '{}') and row 1 the type hints (e.g., '{}')",
+                fixed_duplets[0].row1(), fixed_duplets[0].row2())))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Check the HPO/na separator column, whose position depends on the template type
+    fn check_separator(matrix: &Vec<Vec<String>>, indexer: &HeaderIndexer) -> std::result::Result<(), ValidationErrors> {
         let mut verror = ValidationErrors::new();
-        let h1 = &matrix[0][16];
-        let h2 = &matrix[1][16];
+        let idx = indexer.separator_idx();
+        let (h1, h2) = match (matrix.first().and_then(|r| r.get(idx)), matrix.get(1).and_then(|r| r.get(idx))) {
+            (Some(h1), Some(h2)) => (h1, h2),
+            _ => {
+                return Err(ValidationErrors::from_one_err(format!("Missing separator column {idx}")));
+            }
+        };
         if h1 != "HPO" {
-            verror.push_str(format!("Row 0, column 16: Expected 'HPO' but got '{h1}'"));
+            verror.push_str(format!("Row 0, column {idx}: Expected 'HPO' but got '{h1}'"));
         } else if h2 != "na" {
-            verror.push_str(format!("Row 1, column 16: Expected 'na' but got '{h2}'"));
+            verror.push_str(format!("Row 1, column {idx}: Expected 'na' but got '{h2}'"));
         } 
         verror.ok()
     }
@@ -133,8 +266,9 @@ impl HeaderDupletRow {
             verr.push_str(format!("Empty matrix - must have two header rows and at least one data row but had {}", n_rows));
         }
         let n_cols = matrix[0].len();
-        if n_cols < MENDELIAN_HPO_IDX + 1 {
-            verr.push_str(format!("Incomplete matrix with {} columns, but at least {} required.", n_cols, MENDELIAN_HPO_IDX+1));
+        let min_cols = HeaderIndexer::new(&TemplateType::Mendelian).hpo_idx() + 1;
+        if n_cols < min_cols {
+            verr.push_str(format!("Incomplete matrix with {} columns, but at least {} required.", n_cols, min_cols));
         }
         for (i, row) in matrix.iter().enumerate() {
             let cols = row.len();
@@ -154,7 +288,11 @@ impl HeaderDupletRow {
             disease_header_list: self.disease_header_list.clone(), 
             gene_variant_header_list: self.gene_variant_header_list.clone(), 
             hpo_duplets: updated_hpo_duplets.clone(),
-            template_type: self.template_type.clone()
+            template_type: self.template_type.clone(),
+            has_ancestry: self.has_ancestry,
+            has_vital_status: self.has_vital_status,
+            has_karyotypic_sex: self.has_karyotypic_sex,
+            trailing_metadata: self.trailing_metadata.clone()
         })
     }
 
@@ -165,7 +303,25 @@ impl HeaderDupletRow {
             disease_header_list: vec![DiseaseHeader::new()], 
             gene_variant_header_list: vec![GeneVariantHeader::new()], 
             hpo_duplets, 
-            template_type: TemplateType::Mendelian 
+            template_type: TemplateType::Mendelian,
+            has_ancestry: false,
+            has_vital_status: false,
+            has_karyotypic_sex: false,
+            trailing_metadata: vec![]
+        }
+    }
+
+    fn melded_from_hpo_duplets(hpo_duplets: Vec<HpoTermDuplet>) -> Self {
+        Self { 
+            individual_header: IndividualHeader::new(), 
+            disease_header_list: vec![DiseaseHeader::new(), DiseaseHeader::new()], 
+            gene_variant_header_list: vec![GeneVariantHeader::new(), GeneVariantHeader::new()], 
+            hpo_duplets, 
+            template_type: TemplateType::Melded,
+            has_ancestry: false,
+            has_vital_status: false,
+            has_karyotypic_sex: false,
+            trailing_metadata: vec![]
         }
     }
 
@@ -175,7 +331,7 @@ impl HeaderDupletRow {
     -> Self {
             match template_type {
                 TemplateType::Mendelian => Self::mendelian_from_hpo_duplets(hpo_duplets),
-                TemplateType::Melded => todo!()
+                TemplateType::Melded => Self::melded_from_hpo_duplets(hpo_duplets),
             }
         }
 
@@ -188,6 +344,67 @@ impl HeaderDupletRow {
         &self.template_type
     }
 
+    pub fn has_ancestry(&self) -> bool {
+        self.has_ancestry
+    }
+
+    /// Return a copy of this header with or without the optional ancestry column
+    pub fn with_ancestry(mut self, has_ancestry: bool) -> Self {
+        self.has_ancestry = has_ancestry;
+        self
+    }
+
+    pub fn has_vital_status(&self) -> bool {
+        self.has_vital_status
+    }
+
+    /// Return a copy of this header with or without the optional age at death and cause of death columns
+    pub fn with_vital_status(mut self, has_vital_status: bool) -> Self {
+        self.has_vital_status = has_vital_status;
+        self
+    }
+
+    pub fn has_karyotypic_sex(&self) -> bool {
+        self.has_karyotypic_sex
+    }
+
+    /// Return a copy of this header with or without the optional karyotypic sex column
+    pub fn with_karyotypic_sex(mut self, has_karyotypic_sex: bool) -> Self {
+        self.has_karyotypic_sex = has_karyotypic_sex;
+        self
+    }
+
+    /// The columns for the age and cause of death of a deceased individual, in column order
+    fn vital_status_duplets() -> Vec<DupletItem> {
+        vec![DupletItem::age_at_death(), DupletItem::cause_of_death_id(), DupletItem::cause_of_death_label()]
+    }
+
+    /// The optional columns between the HPO columns and the trailing metadata section, in column order
+    pub fn get_optional_duplets(&self) -> Vec<DupletItem> {
+        let mut duplets = Vec::new();
+        if self.has_ancestry {
+            duplets.push(DupletItem::ancestry());
+        }
+        if self.has_vital_status {
+            duplets.extend(Self::vital_status_duplets());
+        }
+        if self.has_karyotypic_sex {
+            duplets.push(DupletItem::karyotypic_sex());
+        }
+        duplets
+    }
+
+    /// Headers of the trailing metadata section (not including the marker column)
+    pub fn trailing_metadata(&self) -> &[HeaderDupletDto] {
+        &self.trailing_metadata
+    }
+
+    /// Return a copy of this header with the given trailing metadata section
+    pub fn with_trailing_metadata(mut self, trailing_metadata: Vec<HeaderDupletDto>) -> Self {
+        self.trailing_metadata = trailing_metadata;
+        self
+    }
+
     pub fn get_hpo_term_dto_list(&self, values: &Vec<String>) 
     -> std::result::Result<Vec<HpoTermDto>, String> {
         let mut hpo_dto_list = Vec::new();
@@ -214,33 +431,88 @@ impl HeaderDupletRow {
             disease_header_list: vec![DiseaseHeader::new()], 
             gene_variant_header_list: vec![GeneVariantHeader::new()], 
             hpo_duplets: hpo_termduplet_list, 
-            template_type: TemplateType::Mendelian
+            template_type: TemplateType::Mendelian,
+            has_ancestry: false,
+            has_vital_status: false,
+            has_karyotypic_sex: false,
+            trailing_metadata: vec![]
         }
     }
 
 
 
-    /// Total number of columns in the template, including separator column
+    /// Total number of columns in the template, including separator column, the optional columns (ancestry, vital status, karyotypic sex),
+    /// and the optional trailing metadata section (marker and metadata columns)
     pub fn n_columns(&self) -> usize {
-        4 + 2*self.disease_header_list.len() + 6*self.gene_variant_header_list.len() + 4 + self.hpo_duplets.len() +1
+        let n_optional = self.get_optional_duplets().len();
+        let n_metadata = if self.trailing_metadata.is_empty() { 0 } else { 1 + self.trailing_metadata.len() };
+        4 + 2*self.disease_header_list.len() + 6*self.gene_variant_header_list.len() + 4 + self.hpo_duplets.len() + 1 + n_optional + n_metadata
     }
 
 
-    /// We use this function when we add new HPO terms to the cohort; since the previous HeaderRowDuplet does not
-    /// have these terms, we take the existing constant fields and append the new HPO term duplets (Note: client
-    /// code should have arranged the HPO term list previously). We will then use this to update the existing PpktRow objects
-    pub fn update_old(&self, term_list: &Vec<SimpleTerm>) -> Self {
-        let updated_hpo_duplets: Vec<HpoTermDuplet> = term_list
-            .iter()
-            .map(|term| HpoTermDuplet::new(term.name(), &term.identifier().to_string()))
-            .collect();
-        Self {
-            individual_header: self.individual_header.clone(),
-            disease_header_list: self.disease_header_list.clone(),
-            gene_variant_header_list: self.gene_variant_header_list.clone(),
-            hpo_duplets: updated_hpo_duplets,
-            template_type: self.template_type,
-        }       
+    /// Return the duplets of the constant (non-HPO) part of the header in column order, including the separator column
+    pub fn get_fixed_duplets(&self) -> Vec<DupletItem> {
+        let ihdr = &self.individual_header;
+        let mut fixed = vec![ihdr.pmid.clone(), ihdr.title.clone(), ihdr.individual_id.clone(), ihdr.comment.clone()];
+        for dheader in &self.disease_header_list {
+            fixed.push(dheader.disease_id.clone());
+            fixed.push(dheader.disease_label.clone());
+        }
+        for gvheader in &self.gene_variant_header_list {
+            fixed.push(gvheader.hgnc_id.clone());
+            fixed.push(gvheader.gene_symbol.clone());
+            fixed.push(gvheader.transcript.clone());
+            fixed.push(gvheader.allele1.clone());
+            fixed.push(gvheader.allele2.clone());
+            fixed.push(gvheader.variant_comment.clone());
+        }
+        fixed.push(ihdr.age_of_onset.clone());
+        fixed.push(ihdr.age_at_last_encounter.clone());
+        fixed.push(ihdr.deceased.clone());
+        fixed.push(ihdr.sex.clone());
+        fixed.push(DupletItem::hpo_separator());
+        fixed
+    }
+
+    /// Construct a fresh header from the constant (fixed) duplets and the current set of HPO terms.
+    ///
+    /// We use this function whenever the HPO columns of the cohort change (merging a new row, adding a term, ...)
+    /// so that the header is always rebuilt from the actual data columns rather than being patched incrementally.
+    /// Client code should have arranged the HPO term list previously (e.g., with HpoTermArranger).
+    ///
+    /// # Arguments
+    ///
+    /// * `fixed` - the duplets of the constant part of the template, in column order (see [`Self::get_fixed_duplets`])
+    /// * `hpo_terms` - the TermId and label of each HPO column, in column order
+    /// * `template_type` - Mendelian or Melded
+    pub fn rebuild_from_terms(
+        fixed: &[DupletItem],
+        hpo_terms: &[(TermId, String)],
+        template_type: TemplateType
+    ) -> std::result::Result<Self, ValidationErrors> {
+        let mut verrs = ValidationErrors::new();
+        // 17 constant fields for Mendelian and 25 for melded templates
+        let expected_fixed = Self::from_hpo_duplets(vec![], template_type).get_fixed_duplets();
+        if fixed.len() != expected_fixed.len() {
+            verrs.push_str(format!("Expected {} constant header fields but got {}", expected_fixed.len(), fixed.len()));
+            return Err(verrs);
+        }
+        for (i, (actual, expected)) in fixed.iter().zip(expected_fixed.iter()).enumerate() {
+            if ! actual.same_labels(expected) {
+                verrs.push_str(format!("Column {}: Expected '{}'/'{}' but got '{}'/'{}'",
+                    i, expected.row1(), expected.row2(), actual.row1(), actual.row2()));
+            }
+        }
+        let mut seen: HashSet<&TermId> = HashSet::new();
+        let mut hpo_duplets: Vec<HpoTermDuplet> = Vec::with_capacity(hpo_terms.len());
+        for (tid, label) in hpo_terms {
+            if ! seen.insert(tid) {
+                verrs.push_str(format!("Duplicate HPO column for {} ({})", label, tid));
+            }
+            hpo_duplets.push(HpoTermDuplet::new(label, tid.to_string()));
+        }
+        verrs.ok()?;
+        Ok(Self::from_hpo_duplets(hpo_duplets, template_type))
     }
     
     pub fn get_hpo_id_list(&self) -> std::result::Result<Vec<TermId>, ValidationErrors> {
@@ -267,6 +539,38 @@ impl HeaderDupletRow {
         self.hpo_duplets.as_ref()
     }
 
+    /// Get the two header rows of all columns of the template (fixed, HPO, optional, and trailing metadata columns),
+    /// in the same order as in the string matrix, e.g., to render column tooltips and HPO links in a GUI
+    pub fn get_header_dtos(&self) -> Vec<HeaderDupletDto> {
+        let mut dtos: Vec<HeaderDupletDto> = self.get_fixed_duplets()
+            .iter()
+            .map(HeaderDupletDto::from_duplet_item)
+            .collect();
+        dtos.extend(self.get_hpo_header_dtos());
+        dtos.extend(self.get_optional_duplets().iter().map(HeaderDupletDto::from_duplet_item));
+        if !self.trailing_metadata.is_empty() {
+            dtos.push(HeaderDupletDto::from_duplet_item(&DupletItem::metadata_separator()));
+            dtos.extend(self.trailing_metadata.iter().cloned());
+        }
+        dtos
+    }
+
+    /// Get the kind of value (free text, fixed vocabulary, age, CURIE, HPO status) of all columns of the template,
+    /// in the same order as [`Self::get_header_dtos`]. The trailing metadata columns are free text.
+    pub fn get_cell_kinds(&self) -> Vec<CellKindDto> {
+        let mut kinds: Vec<CellKindDto> = self.get_fixed_duplets()
+            .iter()
+            .map(DupletItem::cell_kind)
+            .collect();
+        kinds.extend(self.hpo_duplets.iter().map(HpoTermDuplet::cell_kind));
+        kinds.extend(self.get_optional_duplets().iter().map(DupletItem::cell_kind));
+        if !self.trailing_metadata.is_empty() {
+            kinds.push(DupletItem::metadata_separator().cell_kind());
+            kinds.extend(self.trailing_metadata.iter().map(|_| CellKindDto::FreeText));
+        }
+        kinds
+    }
+
     pub fn get_hpo_header_dtos(&self) -> Vec<HeaderDupletDto> {
         self.hpo_duplets.iter()
             .map(|hpo_duplet| hpo_duplet.to_header_dto())
@@ -339,12 +643,121 @@ mod test {
         vec![row1, row2, row3]
     }
 
+    /// Two header rows of a melded template (two disease and two gene/variant bundles)
+    #[fixture]
+    pub fn melded_header_matrix() -> Vec<Vec<String>> {
+        let row1: Vec<String> = vec![ 
+            "PMID", "title", "individual_id", "comment", 
+            "disease_id", "disease_label", "disease_id", "disease_label", 
+            "HGNC_id", "gene_symbol", "transcript", "allele_1", "allele_2", "variant.comment", 
+            "HGNC_id", "gene_symbol", "transcript", "allele_1", "allele_2", "variant.comment", 
+            "age_of_onset", "age_at_last_encounter", "deceased", "sex", "HPO", "Failure to thrive", "Seizure"
+        ].into_iter().map(|s| s.to_owned()).collect();
+        let row2: Vec<String> = vec![
+            "CURIE", "str", "str", "optional", 
+            "CURIE", "str", "CURIE", "str", 
+            "CURIE", "str", "str", "str", "str", "optional", 
+            "CURIE", "str", "str", "str", "str", "optional", 
+            "age", "age", "yes/no/na", "M:F:O:U", "na", "HP:0001508",  "HP:0001250" 
+        ].into_iter().map(|s| s.to_owned()).collect();
+        vec![row1, row2]
+    }
+
+    #[rstest]
+    fn test_header_indexer() {
+        let mendelian = HeaderIndexer::new(&TemplateType::Mendelian);
+        assert_eq!(0, mendelian.individual_idx());
+        assert_eq!(4, mendelian.disease_idx());
+        assert_eq!(6, mendelian.gene_var_idx());
+        assert_eq!(12, mendelian.demographic_idx());
+        assert_eq!(16, mendelian.separator_idx());
+        assert_eq!(17, mendelian.hpo_idx());
+        assert_eq!(N_CONSTANT_FIELDS_MENDELIAN, mendelian.hpo_idx());
+        let melded = HeaderIndexer::new(&TemplateType::Melded);
+        assert_eq!(8, melded.gene_var_idx());
+        assert_eq!(20, melded.demographic_idx());
+        assert_eq!(24, melded.separator_idx());
+        assert_eq!(25, melded.hpo_idx());
+    }
+
+    #[rstest]
+    fn test_melded_separator(
+        one_case_matrix: Vec<Vec<String>>,
+        mut melded_header_matrix: Vec<Vec<String>>) {
+        let melded = HeaderIndexer::new(&TemplateType::Melded);
+        assert!(HeaderDupletRow::check_separator(&melded_header_matrix, &melded).is_ok());
+        // A Mendelian header does not have the separator at the melded position
+        assert!(HeaderDupletRow::check_separator(&one_case_matrix, &melded).is_err());
+        melded_header_matrix[1][24] = "HPO".to_string();
+        let result = HeaderDupletRow::check_separator(&melded_header_matrix, &melded);
+        assert_eq!(vec!["Row 1, column 24: Expected 'na' but got 'HPO'".to_string()], result.unwrap_err().errors());
+    }
+
+    #[rstest]
+    fn test_swapped_header_rows(mut one_case_matrix: Vec<Vec<String>>) {
+        let fixed = HeaderDupletRow::from_hpo_duplets(vec![], TemplateType::Mendelian).get_fixed_duplets();
+        assert!(HeaderDupletRow::check_header_row_order(&one_case_matrix, &fixed).is_ok());
+        one_case_matrix.swap(0, 1);
+        let result = HeaderDupletRow::check_header_row_order(&one_case_matrix, &fixed);
+        let errors = result.unwrap_err().errors();
+        assert_eq!(1, errors.len());
+        assert!(errors[0].starts_with("Header rows appear swapped"));
+    }
+
     #[rstest]
     fn test_n_fields() {
         /// We expect a total of 17 fields before the HPO Term fields start
         assert_eq!(17, HeaderDupletRow::n_mendelian_contant_fields())
     }
 
+    #[rstest]
+    fn test_rebuild_from_terms() {
+        let fixed = HeaderDupletRow::from_hpo_duplets(vec![], TemplateType::Mendelian).get_fixed_duplets();
+        assert_eq!(HeaderDupletRow::n_mendelian_contant_fields(), fixed.len());
+        let hpo_terms = vec![
+            (TermId::from_str("HP:0001508").unwrap(), "Failure to thrive".to_string()),
+            (TermId::from_str("HP:0001250").unwrap(), "Seizure".to_string()),
+        ];
+        let header = HeaderDupletRow::rebuild_from_terms(&fixed, &hpo_terms, TemplateType::Mendelian).unwrap();
+        assert_eq!(2, header.hpo_count());
+        assert_eq!(19, header.n_columns());
+        assert_eq!("HP:0001250", header.hpo_duplets()[1].hpo_id());
+    }
+
+    #[rstest]
+    fn test_rebuild_from_terms_rejects_duplicates_and_bad_fixed_fields() {
+        let mut fixed = HeaderDupletRow::from_hpo_duplets(vec![], TemplateType::Mendelian).get_fixed_duplets();
+        let seizure = (TermId::from_str("HP:0001250").unwrap(), "Seizure".to_string());
+        let result = HeaderDupletRow::rebuild_from_terms(&fixed, &[seizure.clone(), seizure.clone()], TemplateType::Mendelian);
+        assert!(result.is_err());
+        fixed.pop(); // remove separator
+        let result = HeaderDupletRow::rebuild_from_terms(&fixed, &[seizure], TemplateType::Mendelian);
+        assert!(result.is_err());
+    }
+
+    #[rstest]
+    fn test_rebuild_from_terms_melded(melded_header_matrix: Vec<Vec<String>>) {
+        let fixed = HeaderDupletRow::from_hpo_duplets(vec![], TemplateType::Melded).get_fixed_duplets();
+        assert_eq!(25, fixed.len());
+        let hpo_terms = vec![
+            (TermId::from_str("HP:0001508").unwrap(), "Failure to thrive".to_string()),
+            (TermId::from_str("HP:0001250").unwrap(), "Seizure".to_string()),
+        ];
+        let header = HeaderDupletRow::rebuild_from_terms(&fixed, &hpo_terms, TemplateType::Melded).unwrap();
+        assert_eq!(&TemplateType::Melded, header.template_type());
+        assert_eq!(27, header.n_columns());
+        // the rebuilt header has the same columns as the melded template
+        let (row1, row2): (Vec<String>, Vec<String>) = header.get_header_dtos()
+            .into_iter()
+            .map(|dto| (dto.h1, dto.h2))
+            .unzip();
+        assert_eq!(melded_header_matrix[0], row1);
+        assert_eq!(melded_header_matrix[1], row2);
+        // the constant fields of a Mendelian template do not match a melded template
+        let mendelian_fixed = HeaderDupletRow::from_hpo_duplets(vec![], TemplateType::Mendelian).get_fixed_duplets();
+        assert!(HeaderDupletRow::rebuild_from_terms(&mendelian_fixed, &hpo_terms, TemplateType::Melded).is_err());
+    }
+
 
    
 
@@ -1,13 +1,22 @@
 use std::sync::Arc;
 use once_cell::sync::Lazy;
 
-use crate::{dto::{template_dto::IndividualBundleDto, validation_errors::ValidationErrors}, header::individual_header::IndividualHeader};
+use crate::{dto::{template_dto::{DiseaseDto, IndividualBundleDto}, validation_errors::ValidationErrors}, header::individual_header::IndividualHeader};
 
 
 static SHARED_HEADER: Lazy<Arc<IndividualHeader>> = Lazy::new(|| {
     Arc::new(IndividualHeader::new())
 });
 
+/// Values of the optional individual columns that follow the HPO columns (None if the template does not have the column)
+#[derive(Clone, Debug, Default)]
+pub struct OptionalIndividualValues {
+    pub ancestry: Option<String>,
+    pub age_at_death: Option<String>,
+    pub cause_of_death: Option<DiseaseDto>,
+    pub karyotypic_sex: Option<String>,
+}
+
 #[derive(Clone, Debug)]
 pub struct IndividualBundle {
     header: Arc<IndividualHeader>,
@@ -18,7 +27,15 @@ pub struct IndividualBundle {
     pub(crate) age_of_onset: String,
     pub(crate) age_at_last_encounter: String,
     pub(crate) deceased: String,
-    pub(crate) sex: String
+    pub(crate) sex: String,
+    /// Value of the optional ancestry column (None if the template does not have this column)
+    pub(crate) ancestry: Option<String>,
+    /// Age at death of a deceased individual, if recorded
+    pub(crate) age_at_death: Option<String>,
+    /// Cause of death of a deceased individual, if recorded
+    pub(crate) cause_of_death: Option<DiseaseDto>,
+    /// Karyotypic sex (e.g., 47,XXY), if recorded
+    pub(crate) karyotypic_sex: Option<String>,
 }
 
 impl IndividualBundle {
@@ -41,19 +58,28 @@ impl IndividualBundle {
             age_of_onset: age_of_onset.to_string(),
             age_at_last_encounter: age_at_last_encounter.to_string(),
             deceased: deceased.to_string(),
-            sex: sex.to_string()
+            sex: sex.to_string(),
+            ancestry: None,
+            age_at_death: None,
+            cause_of_death: None,
+            karyotypic_sex: None,
         }
     }
 
     /// Start idx is the index of the first demographic entry.
     /// We should consider changing the format to put the demographics right after individual.
+    /// `optional_values` are the values of the optional columns (e.g., ancestry) that the template has.
     pub fn from_row(
         row: &Vec<String>,
-        start_idx: usize
+        start_idx: usize,
+        optional_values: OptionalIndividualValues
     ) -> std::result::Result<Self, ValidationErrors> {
         let  i = start_idx;
-        let bundle = Self::new(&row[0], &row[1], &row[2], &row[3], &row[i], &row[i+1], &row[i+2], &row[i+3]);
-        println!("from row - {:?}", bundle);
+        let mut bundle = Self::new(&row[0], &row[1], &row[2], &row[3], &row[i], &row[i+1], &row[i+2], &row[i+3]);
+        bundle.ancestry = optional_values.ancestry;
+        bundle.age_at_death = optional_values.age_at_death;
+        bundle.cause_of_death = optional_values.cause_of_death;
+        bundle.karyotypic_sex = optional_values.karyotypic_sex;
         bundle.do_qc()?;
         Ok(bundle)
     }
@@ -94,6 +120,22 @@ impl IndividualBundle {
         &self.sex
     }
 
+    pub fn ancestry(&self) -> Option<&str> {
+        self.ancestry.as_deref()
+    }
+
+    pub fn age_at_death(&self) -> Option<&str> {
+        self.age_at_death.as_deref()
+    }
+
+    pub fn cause_of_death(&self) -> Option<&DiseaseDto> {
+        self.cause_of_death.as_ref()
+    }
+
+    pub fn karyotypic_sex(&self) -> Option<&str> {
+        self.karyotypic_sex.as_deref()
+    }
+
     pub fn from_dto(dto: IndividualBundleDto) -> Self {
         Self { 
             header: SHARED_HEADER.clone(), 
@@ -104,7 +146,11 @@ impl IndividualBundle {
             age_of_onset: dto.age_of_onset, 
             age_at_last_encounter: dto.age_at_last_encounter, 
             deceased: dto.deceased, 
-            sex: dto.sex 
+            sex: dto.sex,
+            ancestry: dto.ancestry,
+            age_at_death: dto.age_at_death,
+            cause_of_death: dto.cause_of_death,
+            karyotypic_sex: dto.karyotypic_sex,
         }
     }
 
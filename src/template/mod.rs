@@ -1,4 +1,5 @@
 pub mod curie;
+pub mod delimited;
 pub mod disease_bundle;
 pub mod excel;
 pub mod gene_variant_bundle;
@@ -8,6 +9,7 @@ pub mod operations;
 pub mod phetools;
 pub use phetools::PheTools;
 pub mod pt_template;
+pub mod pubmed;
 pub mod simple_label;
 
 
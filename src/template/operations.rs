@@ -36,6 +36,19 @@ impl Operation {
         }
     }
 
+    /// New value of a cell to which the operation is applied. Returns None for [`Operation::Edit`], which needs
+    /// a value from the user. "trim" only removes leading and trailing whitespace, whereas "remove whitespace"
+    /// also removes internal whitespace, e.g., c.2737C >T to c.2737C>T.
+    pub fn apply(&self, value: &str) -> Option<String> {
+        match self {
+            Operation::Edit => None,
+            Operation::Clear => Some(String::new()),
+            Operation::Trim => Some(value.trim().to_string()),
+            Operation::RemoveWhitespace => Some(value.chars().filter(|c| !c.is_whitespace()).collect()),
+            other => Some(other.as_str().to_string()),
+        }
+    }
+
     /// Try to create an Operation from a keyword
     pub fn from_keyword(s: &str) -> Option<Self> {
         match s {
@@ -58,3 +71,27 @@ impl Operation {
 }
 
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case("trim", "HGNC:29316 ", "HGNC:29316")]
+    #[case("trim", "c.2737C >T", "c.2737C >T")]
+    #[case("remove whitespace", "HGNC:29316 ", "HGNC:29316")]
+    #[case("remove whitespace", "c.2737C >T", "c.2737C>T")]
+    #[case("remove whitespace", " PMID: 29482508\t", "PMID:29482508")]
+    #[case("clear", "c.2737C>T", "")]
+    #[case("female", "M", "F")]
+    #[case("excluded", "observed", "excluded")]
+    fn test_apply(#[case] keyword: &str, #[case] value: &str, #[case] expected: &str) {
+        let operation = Operation::from_keyword(keyword).unwrap();
+        assert_eq!(Some(expected.to_string()), operation.apply(value));
+    }
+
+    #[rstest]
+    fn test_edit_needs_value() {
+        assert_eq!(None, Operation::Edit.apply("c.2737C>T"));
+    }
+}
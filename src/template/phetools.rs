@@ -3,23 +3,29 @@
 
 
 
-use crate::dto::template_dto::{DiseaseGeneDto, GeneVariantBundleDto, IndividualBundleDto, RowDto, TemplateDto};
-use crate::dto::validation_errors::ValidationErrors;
-use crate::dto::variant_dto::{VariantDto, VariantListDto};
+use crate::dto::template_dto::{CellKindDto, DiseaseGeneDto, GeneTranscriptDto, GeneVariantBundleDto, HeaderDupletDto, IndividualBundleDto, RowDto, TemplateDto};
+use crate::dto::phetools_error::PheToolsError;
+use crate::dto::validation_errors::{QcIssue, ValidationErrors};
+use crate::dto::variant_dto::{DuplicateAlleleGroupDto, VariantDto, VariantListDto, VariantValidationReport};
 use crate::error::Error;
+use crate::header::hgnc_util::HgncSymbolMap;
 use crate::hpo::hpo_util::HpoUtil;
 use crate::persistence::dir_manager::DirManager;
+use crate::ppkt::ppkt_exporter::{ExportSettings, TemplateProvenance};
 use crate::hpo::hpo_term_arranger::HpoTermArranger;
 use crate::dto::{case_dto::CaseDto, hpo_term_dto::HpoTermDto};
+use crate::variant::hgvs_variant::HgvsVariant;
+use crate::variant::structural_variant::StructuralVariant;
 use crate::variant::variant_validator::VariantValidator;
 
 use ontolius::ontology::{MetadataAware, OntologyTerms};
 use ontolius::term::MinimalTerm;
 use ontolius::{ontology::csr::FullCsrOntology, TermId};
-use phenopackets::schema::v2::Phenopacket;
+use phenopackets::schema::v2::{Cohort, Phenopacket};
 use serde_json::to_string;
-use crate::template::pt_template::PheToolsTemplate;
-use crate::template::excel;
+use crate::template::pt_template::{CohortSummary, CoverageReport, LongRecord, PheToolsTemplate, QcReport, Relation, TermFrequency};
+use crate::template::{delimited, excel};
+use crate::template::pubmed::{self, PmidStatus, PubMedClient};
 use core::option::Option::Some;
 use std::collections::{HashMap, HashSet};
 use std::fmt::{self};
@@ -38,6 +44,24 @@ pub struct PheTools {
     /// Manager to validate and cache variants
     manager: Option<DirManager>, 
     variant_validator: VariantValidator,
+    /// True if the template was changed since it was loaded, saved, or exported
+    dirty: bool,
+    /// Optional allowlist of (hgnc_id, gene_symbol, transcript) combinations for new templates
+    known_genes: Option<Vec<GeneTranscriptDto>>,
+    /// Source file and load time of the template, if it was loaded from a file
+    provenance: Option<TemplateProvenance>,
+    /// If true, the validation report warns about rows with unknown sex and an X-chromosomal variant
+    check_x_linked_unknown_sex: bool,
+    /// If true, the validation report warns about contradictory annotations of the same individual in different PMIDs
+    check_cross_pmid_conflicts: bool,
+    /// HGNC ids or symbols of X-linked genes; single variants of males in these genes are exported as hemizygous
+    x_linked_genes: HashSet<String>,
+    /// If true, exports fail if two rows yield the same phenopacket identifier (otherwise, a numeric suffix is added)
+    strict_phenopacket_ids: bool,
+    /// ORCID of the curator, written to the MetaData of exported phenopackets (a placeholder creator is used if not set)
+    curator_orcid: Option<String>,
+    /// Human-readable name of the curator (MetaData.created_by); if not set, the ORCID is used
+    curator_name: Option<String>,
 }
 
 impl PheTools {
@@ -67,9 +91,43 @@ impl PheTools {
             template: None,
             manager: None,
             variant_validator: VariantValidator::hg38(),
+            dirty: false,
+            known_genes: None,
+            provenance: None,
+            check_x_linked_unknown_sex: false,
+            check_cross_pmid_conflicts: false,
+            x_linked_genes: HashSet::new(),
+            strict_phenopacket_ids: false,
+            curator_orcid: None,
+            curator_name: None,
         }
     }
 
+    /// Set the curator of the phenopackets, e.g., 0000-0002-0736-9199 and Jane Curator.
+    /// The curator is optional; without it, the MetaData of exported phenopackets records a placeholder creator.
+    pub fn set_curator(&mut self, orcid: &str, name: Option<&str>) {
+        self.curator_orcid = Some(orcid.to_string());
+        self.curator_name = name.map(str::to_string);
+    }
+
+    /// Settings shared by all exports (curator, provenance, X-linked genes)
+    fn export_settings(&self) -> ExportSettings {
+        let settings = match &self.curator_orcid {
+            Some(orcid) => ExportSettings::new(orcid.as_str()),
+            None => ExportSettings::default(),
+        };
+        settings
+            .with_creator_name(self.curator_name.clone())
+            .with_provenance(self.provenance.clone())
+            .with_x_linked_genes(self.x_linked_genes.clone())
+    }
+
+    /// Restrict the genes that can be used to create new templates to a curated gene panel.
+    /// Template creation with any other (hgnc_id, gene_symbol, transcript) combination will fail.
+    pub fn set_known_genes(&mut self, known_genes: Vec<GeneTranscriptDto>) {
+        self.known_genes = Some(known_genes);
+    }
+
 
     /// Creates a new template to be used for curating phenopackets, initializing the disease/gene/transcript columns with the data provided.
     ///
@@ -97,22 +155,48 @@ impl PheTools {
         dto: DiseaseGeneDto,
         hpo_term_ids: Vec<TermId>,
     ) -> std::result::Result<TemplateDto, String> {
+        self.create_pyphetools_template_from_seeds_typed(dto, hpo_term_ids)
+            .map_err(|e| e.to_string())
+    }
+
+    /// Same as [`Self::create_pyphetools_template_from_seeds`], but returns a [`PheToolsError`] that can be matched
+    /// on, e.g., to distinguish an unknown HPO id (HpIdNotFound) from a gene that is not allowed (TemplateError).
+    pub fn create_pyphetools_template_from_seeds_typed(
+        &mut self,
+        dto: DiseaseGeneDto,
+        hpo_term_ids: Vec<TermId>,
+    ) -> std::result::Result<TemplateDto, PheToolsError> {
         if dto.template_type.as_str() != "mendelian" {
-            return Err("TemplateDto generation for non-Mendelian not implemented yet".to_string());
+            return Err(PheToolsError::TemplateError { msg: "TemplateDto generation for non-Mendelian not implemented yet".to_string() });
         }
-        let disease_dto = &dto.disease_dto_list[0];
-        let gene_transcript_dto = &dto.gene_transcript_dto_list[0];
         let hpo_arc = self.hpo.clone();
         let template = PheToolsTemplate::create_pyphetools_template(
             dto, 
             hpo_term_ids, 
-            hpo_arc
-        ).map_err(|e| e.to_string())?;
-        let dto = template.get_template_dto().map_err(|e| e.to_string())?;
+            hpo_arc,
+            self.known_genes.as_deref(),
+        )?;
+        let dto = template.get_template_dto()?;
         self.template = Some(template);
+        self.dirty = true;
         Ok(dto)
     }
 
+    /// Set the value (e.g., observed, excluded, na, P3Y) of the HPO column `hpo_id` in one row (zero-based index of the individual).
+    /// The error can be matched on, e.g., HpIdNotFound, ObsoleteTermId (with the replacement id), CurieError, or TemplateError.
+    pub fn set_value_typed(&mut self, row: usize, hpo_id: &str, value: &str) -> std::result::Result<(), PheToolsError> {
+        let tid = TermId::from_str(hpo_id)
+            .map_err(|_| Error::termid_parse_error(hpo_id))?;
+        match self.template.as_mut() {
+            Some(template) => {
+                template.set_hpo_value(row, &tid, value)?;
+                self.dirty = true;
+                Ok(())
+            },
+            None => Err(PheToolsError::TemplateError { msg: "Phenopacket Template not initialized".to_string() }),
+        }
+    }
+
     /// Arranges the given HPO terms into a specific order for curation.
     ///
     /// # Arguments
@@ -141,14 +225,30 @@ impl PheTools {
         term_arrager.arrange_term_ids(hpo_terms_for_curation)
     }
 
+    /// Same as [`Self::arrange_terms`], but also returns a message for each input term that was skipped because it
+    /// is not in the phenotypic abnormality subhierarchy (e.g., an onset or mode-of-inheritance term)
+    pub fn arrange_terms_with_errors(&self, hpo_terms_for_curation: &Vec<TermId>) -> (Vec<TermId>, Vec<String>) {
+        let mut term_arrager = HpoTermArranger::new(Arc::clone(&self.hpo));
+        let arranged = term_arrager.arrange_term_ids(hpo_terms_for_curation);
+        (arranged, term_arrager.errors().to_vec())
+    }
+
+    /// Return the two header rows of every column of the template (e.g., PMID/CURIE, ..., Seizure/HP:0001250)
+    pub fn get_header_dtos(&self) -> Result<Vec<HeaderDupletDto>, String> {
+        match &self.template {
+            Some(template) => Ok(template.get_header_dtos()),
+            None => Err("Phenopacket Template not initialized".to_string()),
+        }
+    }
+
     pub fn initialize_project_dir(&mut self, project_dir: PathBuf) -> Result<(), String> {
         self.manager = Some(DirManager::new(project_dir)?);
         Ok(())
     }
 
-    /// Return a Data Transfer Object to display the entire phenopacket cohort (template)
-    /// This function is called when the user opens a new template. It
-    /// opens the file, creates a DTO, and sets up the directory/variant managers
+    /// Return a Data Transfer Object to display the entire phenopacket cohort (template), with the cohort type,
+    /// the HPO header duplets, and one [`RowDto`] per phenopacket, so that the front end does not need to parse the string matrix.
+    /// This function is called when the user opens a new template.
     pub fn get_template_dto(&self) -> Result<TemplateDto, String> {
         match &self.template {
             Some(template) => {
@@ -177,6 +277,8 @@ impl PheTools {
                 match ppt.get_template_dto() {
                     Ok(dto) => {
                         self.template = Some(ppt);
+                        self.provenance = None;
+                        self.dirty = false;
                         Ok(dto)
                     } 
                     Err(e) => Err(vec![e.to_string()]),
@@ -186,6 +288,40 @@ impl PheTools {
         }
     }
 
+    /// Replace the current template with a previously saved matrix (e.g., to implement "revert to last save").
+    /// The matrix is validated first; if it is invalid, the current template is left unchanged.
+    /// In contrast to merging, this is a full replacement. The provenance of the current template is retained
+    /// and the template is marked as saved.
+    pub fn restore_from_matrix(&mut self, matrix: Vec<Vec<String>>) -> Result<(), String> {
+        let template = PheToolsTemplate::from_mendelian_template(matrix, self.hpo.clone(), false)
+            .map_err(|verrs| format!("Could not restore template: {}", verrs.errors().join("; ")))?;
+        self.template = Some(template);
+        self.mark_saved();
+        Ok(())
+    }
+
+    /// Same as [`Self::load_matrix`], but errors are returned as [`QcIssue`]s with the row and column of the
+    /// affected cell (if it can be determined), so that a UI can highlight the cells that need to be fixed.
+    pub fn load_matrix_with_issues(
+        &mut self, 
+        matrix: Vec<Vec<String>>,
+        fix_errors: bool
+    ) -> Result<TemplateDto, Vec<QcIssue>> 
+    {
+        let mut issues = PheToolsTemplate::locate_cell_issues(&matrix);
+        match self.load_matrix(matrix, fix_errors) {
+            Ok(dto) => Ok(dto),
+            Err(errors) => {
+                for e in errors {
+                    if !issues.iter().any(|issue| issue.message == e) {
+                        issues.push(QcIssue::unlocated_error(e));
+                    }
+                }
+                Err(issues)
+            }
+        }
+    }
+
     /// Transform an excel file (representing a PheTools template) into a matrix of Strings
     fn excel_template_to_matrix(
         phetools_template_path: &str,
@@ -205,7 +341,105 @@ impl PheTools {
         fix_errors: bool
     ) -> Result<TemplateDto, Vec<String>> {
         let matrix = Self::excel_template_to_matrix( phetools_template_path)?;
-        self.load_matrix(matrix, fix_errors)
+        let dto = self.load_matrix(matrix, fix_errors)?;
+        self.provenance = Some(TemplateProvenance::now(phetools_template_path));
+        Ok(dto)
+    }
+
+    /// Same as [`Self::load_excel_template`], but errors are returned as [`QcIssue`]s with cell locations
+    /// (see [`Self::load_matrix_with_issues`]).
+    pub fn load_excel_template_with_issues(
+        &mut self,
+        phetools_template_path: &str,
+        fix_errors: bool
+    ) -> Result<TemplateDto, Vec<QcIssue>> {
+        let matrix = Self::excel_template_to_matrix(phetools_template_path)
+            .map_err(|errors| errors.into_iter().map(QcIssue::unlocated_error).collect::<Vec<_>>())?;
+        let dto = self.load_matrix_with_issues(matrix, fix_errors)?;
+        self.provenance = Some(TemplateProvenance::now(phetools_template_path));
+        Ok(dto)
+    }
+
+    /// Load a CSV or TSV file (e.g., exported from Google Sheets) representing the entire PheTools template
+    /// Arguments
+    /// - `template_path` - path to the delimited file with Phetools cohort template
+    /// - `delimiter` - field delimiter, e.g., '\t' for TSV or ',' for CSV
+    /// - `fix_errors` - if true, atempt to fix easily fixable errors
+    pub fn load_delimited_template(
+        &mut self,
+        phetools_template_path: &str,
+        delimiter: char,
+        fix_errors: bool
+    ) -> Result<TemplateDto, Vec<String>> {
+        let matrix = delimited::read_delimited_to_matrix(phetools_template_path, delimiter)
+            .map_err(|e| vec![e.to_string()])?;
+        let dto = self.load_matrix(matrix, fix_errors)?;
+        self.provenance = Some(TemplateProvenance::now(phetools_template_path));
+        Ok(dto)
+    }
+
+    /// Structured Q/C issues of one row (zero-based index of the individual), e.g., for a detail panel of the selected case.
+    /// The issues carry the row and column of the affected cell in the template matrix.
+    pub fn row_errors(&self, row: usize) -> Result<Vec<QcIssue>, String> {
+        match &self.template {
+            Some(template) => template.get_row_issues(row),
+            None => Err("Phenopacket Template not initialized".to_string()),
+        }
+    }
+
+    /// Check every row of the current template and return the errors together with the number of errors per row and
+    /// per column, so that a GUI can highlight the cells and columns that need to be fixed.
+    pub fn template_qc(&self) -> Result<QcReport, String> {
+        match &self.template {
+            Some(template) => Ok(template.get_qc_report()),
+            None => Err("Phenopacket Template not initialized".to_string()),
+        }
+    }
+
+    /// Number of individuals for which each HPO term is observed, excluded, or na, in the column order of the template.
+    /// Terms that are na for all individuals were probably added by mistake (see [`TermFrequency::is_unannotated`]).
+    pub fn get_term_frequencies(&self) -> Result<Vec<TermFrequency>, String> {
+        match &self.template {
+            Some(template) => template.get_term_frequencies(),
+            None => Err("Phenopacket Template not initialized".to_string()),
+        }
+    }
+
+    /// HPO terms (TermId and label) whose column is na or empty for every individual of the cohort
+    pub fn get_unannotated_hpo_terms(&self) -> Result<Vec<(TermId, String)>, String> {
+        match &self.template {
+            Some(template) => template.get_unannotated_hpo_terms(),
+            None => Err("Phenopacket Template not initialized".to_string()),
+        }
+    }
+
+    /// Delete the HPO columns that are na or empty for every individual and return the deleted terms
+    pub fn prune_empty_hpo_columns(&mut self) -> Result<Vec<(TermId, String)>, Vec<String>> {
+        match self.template.as_mut() {
+            Some(template) => {
+                let pruned = template.prune_empty_hpo_columns()
+                    .map_err(|verrs| verrs.errors())?;
+                if !pruned.is_empty() {
+                    self.dirty = true;
+                }
+                Ok(pruned)
+            },
+            None => Err(vec!["Phenopacket Template not initialized".to_string()]),
+        }
+    }
+
+    /// Number of phenopackets and HPO columns, the diseases and genes, and the counts of observed, excluded,
+    /// and na cells of the current cohort
+    pub fn cohort_summary(&self) -> Result<CohortSummary, String> {
+        match &self.template {
+            Some(template) => Ok(template.cohort_summary()),
+            None => Err("Phenopacket Template not initialized".to_string()),
+        }
+    }
+
+    /// Source file and load time of the current template (only set if the template was loaded from a file)
+    pub fn provenance(&self) -> Option<&TemplateProvenance> {
+        self.provenance.as_ref()
     }
 
 
@@ -236,11 +470,83 @@ impl PheTools {
             .map_err(|verrs| verrs.errors().clone())?;
         let template_dto = updated_template.get_template_dto().map_err(|e| vec![e.to_string()])?;
         self.template = Some(updated_template);
+        self.dirty = true;
         
         Ok(template_dto)
     }
 
 
+    /// Add a new HPO term column to the loaded template (e.g., a phenotype that was found to be relevant during curation).
+    /// The label is taken from the ontology, the value of the new column is "na" for all existing rows, and the HPO
+    /// columns are rearranged in DFS order; the existing values of each row are moved to the new positions of their columns.
+    pub fn add_hpo_term(&mut self, term_id: TermId) -> std::result::Result<(), Vec<String>> {
+        let label = match self.hpo.term_by_id(&term_id) {
+            Some(term) => term.name().to_string(),
+            None => { return Err(vec![Error::HpIdNotFound { id: term_id.to_string() }.to_string()]); },
+        };
+        match self.template.as_mut() {
+            Some(template) => {
+                template.add_hpo_term_to_cohort(&term_id.to_string(), &label)
+                    .map_err(|verrs| verrs.errors())?;
+                self.dirty = true;
+                Ok(())
+            },
+            None => Err(vec!["Phenopacket Template not initialized".to_string()]),
+        }
+    }
+
+    /// Kind of value of the cell at `row`/`col` of the template matrix (free text, a fixed set of allowed values,
+    /// an age, a CURIE, or an HPO status), e.g., to offer a dropdown with the allowed values in a GUI
+    pub fn get_cell_kind(&self, row: usize, col: usize) -> std::result::Result<CellKindDto, String> {
+        match &self.template {
+            Some(template) => template.get_cell_kind(row, col).map_err(|e| e.to_string()),
+            None => Err("Phenopacket Template not initialized".to_string()),
+        }
+    }
+
+    /// Apply a right-click operation such as "trim", "remove whitespace", or "na" to the cell at `row`/`col` of the
+    /// template matrix (the first two rows are the header rows)
+    pub fn execute_operation(&mut self, row: usize, col: usize, operation: &str) -> std::result::Result<(), String> {
+        match self.template.as_mut() {
+            Some(template) => {
+                template.execute_operation(row, col, operation)
+                    .map_err(|e| e.to_string())?;
+                self.dirty = true;
+                Ok(())
+            },
+            None => Err("Phenopacket Template not initialized".to_string()),
+        }
+    }
+
+    /// Delete the HPO column with index `col` in the template matrix (e.g., a term that was added by mistake).
+    /// Deleting any of the fixed columns (individual, disease, gene/variant, demographics) is an error.
+    pub fn delete_hpo_column(&mut self, col: usize) -> std::result::Result<(), Vec<String>> {
+        match self.template.as_mut() {
+            Some(template) => {
+                template.delete_hpo_column(col)
+                    .map_err(|verrs| verrs.errors())?;
+                self.dirty = true;
+                Ok(())
+            },
+            None => Err(vec!["Phenopacket Template not initialized".to_string()]),
+        }
+    }
+
+
+    /// Add a GA4GH phenopacket (e.g., a legacy phenopacket created with pyphetools) as a new row of the current
+    /// template. HPO terms of the phenopacket that are not yet in the template are added as new columns.
+    pub fn import_phenopacket(&mut self, ppkt: &Phenopacket) -> std::result::Result<(), Vec<String>> {
+        match self.template.as_mut() {
+            Some(template) => {
+                template.import_phenopacket(ppkt)
+                    .map_err(|verrs| verrs.errors())?;
+                self.dirty = true;
+                Ok(())
+            },
+            None => Err(vec!["Phenopacket Template not initialized".to_string()]),
+        }
+    }
+
     /// This function is called if the user enters information about a new phenopacket to
     /// be added to an existing cohort. The function will need to merge this with the
     /// existing cohort - this means mainly that we need to add na to terms used in this
@@ -265,9 +571,34 @@ impl PheTools {
             .map_err(|verr| verr.errors().clone())?;
         let template_dto = updated_template.get_template_dto().map_err(|e| vec![e.to_string()])?;
         self.template = Some(updated_template);
+        self.dirty = true;
         Ok(template_dto)
     }
 
+    /// Set the HPO cells of an existing row from a full phenotype profile.
+    /// New HPO terms are added to the template (and set to "na" for the other rows).
+    ///
+    /// # Arguments
+    ///
+    /// * `row` - index of the phenopacket row (not counting the header rows)
+    /// * `dtos` - HPO terms with values such as observed, excluded, na, or an age of onset
+    pub fn apply_hpo_profile(
+        &mut self, 
+        row: usize, 
+        dtos: Vec<HpoTermDto>
+    ) -> Result<(), String> {
+        let template = match self.template.as_mut() {
+            Some(template) => template,
+            None => {
+                return Err("Phenopacket Template not initialized".to_string());
+            }
+        };
+        template.apply_hpo_profile(row, dtos)
+            .map_err(|verrs| verrs.errors().join("; "))?;
+        self.dirty = true;
+        Ok(())
+    }
+
     /// Return information about the version and number of terms of the HPO 
     pub fn get_hpo_data(&self) -> HashMap<String, String> {
         let hpo_clone = Arc::clone(&self.hpo);
@@ -336,6 +667,64 @@ impl PheTools {
         }
     }
 
+    /// Get the distinct alleles (by allele and transcript) of the current template as [`VariantDto`] objects, sorted as in
+    /// [`VariantDto::sort_variant_dtos`]; "na" alleles are skipped. The result can be passed directly to
+    /// [`Self::validate_variant_dto_list`]. Alleles that were already validated carry the predicted
+    /// protein consequence (e.g., p.(Arg206His)) so that it can be shown next to the c. notation.
+    pub fn collect_variant_dtos(&self) -> Result<Vec<VariantDto>, String> {
+        match &self.template {
+            Some(template) => {
+                let dto_list = template.get_variant_dto_list();
+                match &self.manager {
+                    Some(manager) => {
+                        let hgvs_dict = manager.get_hgvs_dict();
+                        Ok(dto_list.into_iter()
+                            .map(|dto| {
+                                let protein_hgvs = hgvs_dict.get(dto.variant_string())
+                                    .and_then(|hgvs| hgvs.protein_hgvs())
+                                    .map(str::to_string);
+                                dto.with_protein_hgvs(protein_hgvs)
+                            })
+                            .collect())
+                    },
+                    None => Ok(dto_list),
+                }
+            },
+            None => Err("Phenopacket Template not initialized".to_string()),
+        }
+    }
+
+    /// Find alleles of the current template that are spelled differently but resolve to the same genomic
+    /// coordinates after validation (e.g., c.76_78del and c.76_78delACG). The groups should be confirmed
+    /// by the curator before calling [`Self::merge_duplicate_alleles`].
+    pub fn find_duplicate_alleles(&self) -> Result<Vec<DuplicateAlleleGroupDto>, String> {
+        let template = match &self.template {
+            Some(template) => template,
+            None => {
+                return Err("Phenopacket Template not initialized".to_string());
+            }
+        };
+        match &self.manager {
+            Some(manager) => Ok(template.find_duplicate_alleles(manager.get_hgvs_dict())),
+            None => Err("Variant manager not initialized".to_string()),
+        }
+    }
+
+    /// Use the canonical spelling of each (confirmed) group of duplicate alleles in all rows of the template.
+    /// Returns the number of cells that were changed.
+    pub fn merge_duplicate_alleles(&mut self, groups: &[DuplicateAlleleGroupDto]) -> Result<usize, String> {
+        match self.template.as_mut() {
+            Some(template) => {
+                let n_replaced = template.merge_duplicate_alleles(groups);
+                if n_replaced > 0 {
+                    self.dirty = true;
+                }
+                Ok(n_replaced)
+            },
+            None => Err("Phenopacket Template not initialized".to_string()),
+        }
+    }
+
     pub fn validate_all_variants(&mut self) -> Result<VariantListDto, ValidationErrors> {
             let verrs = ValidationErrors::new();
             todo!();
@@ -356,6 +745,19 @@ impl PheTools {
 
 
 
+    /// Validate a list of variants and return a report (total, validated, failures with reasons, timing)
+    /// that can be serialized to JSON, e.g., to keep a history of validation runs.
+    pub fn validate_variants_with_report(&mut self, variant_dto_list: Vec<VariantDto>) -> Result<VariantValidationReport, String> {
+        match self.manager.as_mut() {
+            Some(manager) => {
+                Ok(manager.validate_variant_dto_list_with_report(variant_dto_list))
+            },
+            None => {
+                Err("Variant manager not initialized".to_string())
+            },
+        }
+    }
+
     /// Check correctness of a TemplateDto that was sent from the front end.
     /// This operation is performed to see if the edits made in the front end are valid.
     /// If everything is OK, we can go ahead and save the template using another command.
@@ -370,6 +772,162 @@ impl PheTools {
     }
 
 
+    /// Check that the gene symbols of the current template match their HGNC identifiers, e.g., using
+    /// [`HgncSymbolMap::embedded`] or a map loaded from the HGNC complete set with [`HgncSymbolMap::from_tsv`].
+    pub fn check_hgnc_symbols(&self, symbol_map: &HgncSymbolMap) -> Result<(), Vec<String>> {
+        match &self.template {
+            Some(template) => template.check_hgnc_symbols(symbol_map).map_err(|verrs| verrs.errors()),
+            None => Err(vec!["Phenopacket Template not initialized".to_string()]),
+        }
+    }
+
+    /// Export preflight: list the (zero-based index of the individual, allele) pairs whose alleles have not been
+    /// validated yet, i.e., are in neither dictionary. These should be validated before calling [`Self::export_ppkt`].
+    pub fn check_export_ready(
+        &self,
+        hgvs_dict: &HashMap<String, HgvsVariant>,
+        structural_dict: &HashMap<String, StructuralVariant>
+    ) -> Result<Vec<(usize, String)>, String> {
+        match &self.template {
+            Some(template) => Ok(template.find_unvalidated_alleles(hgvs_dict, structural_dict)),
+            None => Err("Phenopacket Template not initialized".to_string()),
+        }
+    }
+
+    /// Check that all rows of the current template have the number of disease and gene bundles
+    /// expected for the template type (e.g., one disease and one gene for Mendelian templates).
+    pub fn validate_template_consistency(&self) -> Result<(), Vec<String>> {
+        match &self.template {
+            Some(template) => template.check_row_structure().map_err(|verrs| verrs.errors()),
+            None => Err(vec!["Phenopacket Template not initialized".to_string()]),
+        }
+    }
+
+    /// Get the number of individuals per HPO onset category (e.g., Childhood onset).
+    /// Numerical ages of onset such as P3Y are assigned to the corresponding category.
+    pub fn onset_distribution(&self) -> Result<HashMap<String, usize>, String> {
+        match &self.template {
+            Some(template) => Ok(template.get_onset_distribution()),
+            None => Err("Phenopacket Template not initialized".to_string()),
+        }
+    }
+
+    /// Iterate over the rows (phenopackets) of the current template. Each row is converted to a [`RowDto`]
+    /// only when it is requested, so analysis code can process one case at a time without building the full matrix.
+    pub fn rows(&self) -> Result<impl Iterator<Item = RowDto> + '_, String> {
+        match &self.template {
+            Some(template) => Ok(template.row_dtos()),
+            None => Err("Phenopacket Template not initialized".to_string()),
+        }
+    }
+
+    /// Get a human-readable summary of the problems in the current template, grouped by severity and column.
+    /// This is intended for logs or emails; use [`Self::validate_template_consistency`] and related functions
+    /// for machine-readable results.
+    /// If enabled with [`Self::set_check_x_linked_unknown_sex`], the report also warns about rows with unknown sex
+    /// and a (validated) variant on the X chromosome. If enabled with [`Self::set_check_cross_pmid_conflicts`],
+    /// it also warns about HPO terms that are observed and excluded for the same individual in different PMIDs.
+    pub fn validation_report(&self) -> String {
+        match &self.template {
+            Some(template) => {
+                let mut warnings = match (&self.manager, self.check_x_linked_unknown_sex) {
+                    (Some(manager), true) => template.get_unknown_sex_x_linked_warnings(manager.get_hgvs_dict()),
+                    _ => Vec::new(),
+                };
+                if self.check_cross_pmid_conflicts {
+                    warnings.extend(template.get_cross_pmid_conflict_warnings());
+                }
+                template.get_validation_report_with_warnings(warnings)
+            },
+            None => "Phenopacket Template not initialized".to_string(),
+        }
+    }
+
+    /// Opt in to a warning for rows with sex "U" that carry an X-chromosomal variant. Downstream tools
+    /// often require a definite sex to infer the allelic state for X-linked genes. Off by default.
+    pub fn set_check_x_linked_unknown_sex(&mut self, check: bool) {
+        self.check_x_linked_unknown_sex = check;
+    }
+
+    /// If true, exports fail with a list of the offending rows if two rows yield the same phenopacket identifier.
+    /// By default, colliding identifiers are made unique with a numeric suffix (e.g., PMID_1_Individual_1_2).
+    pub fn set_strict_phenopacket_ids(&mut self, strict: bool) {
+        self.strict_phenopacket_ids = strict;
+    }
+
+    /// In strict mode, check that the phenopacket identifiers of the template are unique (see [`Self::set_strict_phenopacket_ids`])
+    fn check_phenopacket_ids(&self, template: &PheToolsTemplate) -> Result<(), String> {
+        if self.strict_phenopacket_ids {
+            template.check_unique_phenopacket_ids()
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Set the X-linked genes (HGNC ids, e.g., HGNC:3754, or symbols, e.g., FLNA) of the cohort. Variants of male
+    /// individuals in these genes are exported with the allelic state hemizygous (GENO:0000134) rather than heterozygous.
+    pub fn set_x_linked_genes(&mut self, x_linked_genes: HashSet<String>) {
+        self.x_linked_genes = x_linked_genes;
+    }
+
+    /// Check that the distinct PMIDs of the current template resolve to articles in PubMed and that the
+    /// titles match the title column. This requires network access unless a test client is supplied, e.g.,
+    /// `phetools.verify_pmids(&EutilsPubMedClient::default())`.
+    pub fn verify_pmids(&self, client: &dyn PubMedClient) -> Result<Vec<(String, PmidStatus)>, String> {
+        match &self.template {
+            Some(template) => pubmed::verify_pmids(client, &template.get_pmid_titles()),
+            None => Err("Phenopacket Template not initialized".to_string()),
+        }
+    }
+
+    /// Opt in to a warning for HPO terms annotated as observed in one row and excluded in another row with
+    /// the same individual_id but a different PMID (the same individual reported in two publications). Off by default.
+    pub fn set_check_cross_pmid_conflicts(&mut self, check: bool) {
+        self.check_cross_pmid_conflicts = check;
+    }
+
+    /// Get the contradictory observed/excluded annotations of individuals reported in more than one PMID
+    /// as (column, message) pairs for curator review.
+    pub fn cross_pmid_conflicts(&self) -> Result<Vec<(String, String)>, String> {
+        match &self.template {
+            Some(template) => Ok(template.get_cross_pmid_conflict_warnings()),
+            None => Err("Phenopacket Template not initialized".to_string()),
+        }
+    }
+
+    /// Find HPO terms in the current template that are the same as, more general than (Ancestor), or
+    /// more specific than (Descendant) a candidate term. This can be used to warn curators before adding a term
+    /// that is related to an existing column. Returns an empty list if no template has been loaded.
+    pub fn related_existing_terms(&self, candidate: &TermId) -> Vec<(TermId, Relation)> {
+        match &self.template {
+            Some(template) => template.get_related_terms(candidate),
+            None => vec![],
+        }
+    }
+
+    /// Compare the HPO terms observed in the current cohort with the known annotations of the disease
+    /// (e.g., from phenotype.hpoa) to see which known features are absent from the cohort and which observed
+    /// terms are not among the known features. If no template has been loaded, all known terms are reported as missing.
+    pub fn coverage_against_annotations(&self, known_terms: &[TermId]) -> CoverageReport {
+        match &self.template {
+            Some(template) => template.get_coverage(known_terms),
+            None => CoverageReport {
+                missing: known_terms.to_vec(),
+                ..Default::default()
+            },
+        }
+    }
+
+    /// Export the cohort in long format, with one record per individual and HPO term (individual_id, term_id,
+    /// label, value, onset). This is easier to analyze in R or Python than the wide matrix of [`Self::get_template_dto`].
+    /// If `skip_na` is true, terms that were not ascertained for an individual are omitted.
+    pub fn to_long_format(&self, skip_na: bool) -> Result<Vec<LongRecord>, String> {
+        match &self.template {
+            Some(template) => Ok(template.get_long_records(skip_na)),
+            None => Err("Phenopacket Template not initialized".to_string()),
+        }
+    }
+
     pub fn get_default_cohort_dir(&self) -> Option<PathBuf> {
         self.manager.as_ref().map(|dirman| dirman.get_cohort_dir())
     }
@@ -384,12 +942,14 @@ impl PheTools {
     -> Result<(), Vec<String>> {
         let template = self.validate_template(cohort_dto)?;
         self.template = Some(template);
+        self.mark_saved();
         Ok(())
     }
 
     pub fn export_ppkt(
         &mut self,
         cohort_dto: &TemplateDto) -> Result<Vec<Phenopacket>, String> {
+            let settings = self.export_settings();
             let template = self.validate_template(cohort_dto)
                 .map_err(|_| "Could not validate template. Try again".to_string())?;
             self.template = Some(template);
@@ -399,6 +959,7 @@ impl PheTools {
                     return Err("Phenopacket Template not initialized".to_string());
                 },
             };
+            self.check_phenopacket_ids(template)?;
             let dir_manager = match self.manager.as_mut() {
                 Some(manager) => manager,
                 None => {
@@ -407,10 +968,119 @@ impl PheTools {
             };
             let hgvs_dict = dir_manager.get_hgvs_dict();
             let structural_dict = dir_manager.get_structural_dict();
-            template.extract_phenopackets(hgvs_dict, structural_dict)
+            template.check_validated_transcripts(hgvs_dict)?;
+            let ppkt_list = template.extract_phenopackets(hgvs_dict, structural_dict, &settings)?;
+            self.mark_saved();
+            Ok(ppkt_list)
+    }
+
+    /// Same as [`Self::export_ppkt`], but builds the phenopackets concurrently. This is intended for large cohorts.
+    /// All errors are returned, one per row that could not be exported.
+    #[cfg(feature = "rayon")]
+    pub fn export_phenopackets_parallel(
+        &mut self,
+        cohort_dto: &TemplateDto) -> Result<Vec<Phenopacket>, Vec<String>> {
+            let settings = self.export_settings();
+            let template = self.validate_template(cohort_dto)?;
+            let dir_manager = match self.manager.as_ref() {
+                Some(manager) => manager,
+                None => {
+                    return Err(vec!["Variant Manager Template not initialized".to_string()]);
+                }
+            };
+            let hgvs_dict = dir_manager.get_hgvs_dict();
+            let structural_dict = dir_manager.get_structural_dict();
+            template.check_validated_transcripts(hgvs_dict).map_err(|e| vec![e])?;
+            self.check_phenopacket_ids(&template).map_err(|e| vec![e])?;
+            let ppkt_list = template.extract_phenopackets_parallel(hgvs_dict, structural_dict, &settings)?;
+            self.template = Some(template);
+            self.mark_saved();
+            Ok(ppkt_list)
+    }
+
+    /// Returns true if the template has been changed since it was last loaded, saved, or exported.
+    /// Applications can use this to prompt the user before closing with unsaved edits.
+    pub fn is_dirty(&self) -> bool {
+        self.dirty
+    }
+
+    /// Record that the current state of the template has been saved
+    pub fn mark_saved(&mut self) {
+        self.dirty = false;
+    }
+
+    /// Build the phenopacket of one row (zero-based index of the individual) of the current template
+    /// and return it as pretty-printed JSON, e.g., to preview a case without writing to disk.
+    pub fn phenopacket_json(&self, row: usize) -> Result<String, String> {
+        let template = match &self.template {
+            Some(template) => template,
+            None => {
+                return Err("Phenopacket Template not initialized".to_string());
+            }
+        };
+        let dir_manager = match &self.manager {
+            Some(manager) => manager,
+            None => {
+                return Err("Variant Manager Template not initialized".to_string());
+            }
+        };
+        let ppkt = template.extract_phenopacket(
+            row, 
+            dir_manager.get_hgvs_dict(), 
+            dir_manager.get_structural_dict(), 
+            &self.export_settings())?;
+        serde_json::to_string_pretty(&ppkt).map_err(|e| e.to_string())
+    }
+
+    /// Export the current template as a GA4GH Cohort message with all phenopackets and a single shared MetaData.
+    /// The variants must have been validated previously (see [`Self::validate_variant_dto_list`]).
+    pub fn export_cohort(&self, cohort_id: &str, description: &str) -> Result<Cohort, String> {
+        let template = match &self.template {
+            Some(template) => template,
+            None => {
+                return Err("Phenopacket Template not initialized".to_string());
+            }
+        };
+        let dir_manager = match &self.manager {
+            Some(manager) => manager,
+            None => {
+                return Err("Variant Manager Template not initialized".to_string());
+            }
+        };
+        template.check_validated_transcripts(dir_manager.get_hgvs_dict())?;
+        self.check_phenopacket_ids(template)?;
+        template.extract_cohort(
+            cohort_id, 
+            description, 
+            dir_manager.get_hgvs_dict(), 
+            dir_manager.get_structural_dict(), 
+            &self.export_settings())
+    }
+
+    /// Write all phenopackets of the current template as newline-delimited JSON to `writer`.
+    /// Rows that cannot be exported are skipped and reported in the returned list of errors.
+    pub fn export_ndjson<W: std::io::Write>(&self, writer: &mut W) -> Result<Vec<String>, String> {
+        let template = match &self.template {
+            Some(template) => template,
+            None => {
+                return Err("Phenopacket Template not initialized".to_string());
+            }
+        };
+        let dir_manager = match &self.manager {
+            Some(manager) => manager,
+            None => {
+                return Err("Variant Manager Template not initialized".to_string());
+            }
+        };
+        template.check_validated_transcripts(dir_manager.get_hgvs_dict())?;
+        self.check_phenopacket_ids(template)?;
+        template.write_phenopackets_ndjson(
+            writer, 
+            dir_manager.get_hgvs_dict(), 
+            dir_manager.get_structural_dict(), 
+            &self.export_settings())
     }
 
-    
     fn write_ppkt(ppkt: &Phenopacket, file_path: PathBuf) -> Result<(), String> {
         let file = OpenOptions::new()
             .write(true)
@@ -441,11 +1111,12 @@ impl core::fmt::Display for PheTools {
     fn fmt(&self, fmt: &mut core::fmt::Formatter) -> fmt::Result {
         match &self.template {
             Some(tplt) => {
-                let gene_sym = "todo".to_string();
-                let hgnc = "todo".to_string();
-                let dis = "todo".to_string();
-                let ds_id = "todo".to_string();
-                let ppkt_n = tplt.phenopacket_count();
+                let summary = tplt.cohort_summary();
+                let gene_sym = summary.genes.iter().map(|g| g.gene_symbol.as_str()).collect::<Vec<_>>().join(", ");
+                let hgnc = summary.genes.iter().map(|g| g.hgnc_id.as_str()).collect::<Vec<_>>().join(", ");
+                let dis = summary.diseases.iter().map(|d| d.disease_label.as_str()).collect::<Vec<_>>().join(", ");
+                let ds_id = summary.diseases.iter().map(|d| d.disease_id.as_str()).collect::<Vec<_>>().join(", ");
+                let ppkt_n = summary.phenopacket_count;
                 let hpo_v = "HPO: to-do update ontolius".to_string(); // TODO
                 write!(
                     fmt,
@@ -474,8 +1145,70 @@ mod tests {
     use ontolius::io::OntologyLoaderBuilder;
 
     use super::*;
+    use std::{fs::File, io::BufReader};
+    use flate2::bufread::GzDecoder;
+
+    fn hpo() -> Arc<FullCsrOntology> {
+        let reader = GzDecoder::new(BufReader::new(File::open("resources/hp.v2025-03-03.json.gz").unwrap()));
+        let loader = OntologyLoaderBuilder::new().obographs_parser().build();
+        Arc::new(loader.load_from_read(reader).unwrap())
+    }
 
- 
+    fn matrix() -> Vec<Vec<String>> {
+        let header1 = ["PMID", "title", "individual_id", "comment", "disease_id", "disease_label", "HGNC_id", "gene_symbol",
+            "transcript", "allele_1", "allele_2", "variant.comment", "age_of_onset", "age_at_last_encounter", "deceased", "sex", 
+            "HPO", "Seizure"];
+        let header2 = ["CURIE", "str", "str", "optional", "CURIE", "str", "CURIE", "str", "str", "str", "str", "optional", 
+            "age", "age", "yes/no/na", "M:F:O:U", "na", "HP:0001250"];
+        let mut matrix: Vec<Vec<String>> = vec![
+            header1.iter().map(|s| s.to_string()).collect(),
+            header2.iter().map(|s| s.to_string()).collect()
+        ];
+        for (individual, allele1, allele2) in [("A", "c.617G>A", "na"), ("B", "c.617G>A", "c.619C>G"), ("C", "c.619C>G", "c.617G>A")] {
+            let row = ["PMID:29482508", "FOP case report", individual, "", "OMIM:135100", "Fibrodysplasia ossificans progressiva",
+                "HGNC:171", "ACVR1", "NM_001111067.4", allele1, allele2, "", "P9Y", "P16Y", "no", "M", "na", "observed"];
+            matrix.push(row.iter().map(|s| s.to_string()).collect());
+        }
+        matrix
+    }
+
+    /// Alleles that occur in several rows are returned once; "na" alleles are skipped
+    #[test]
+    fn test_collect_variant_dtos() -> Result<()> {
+        let mut phetools = PheTools::new(hpo());
+        assert!(phetools.collect_variant_dtos().is_err());
+        phetools.load_matrix(matrix(), false).map_err(|e| e.join("; "))?;
+        let alleles = phetools.collect_variant_dtos()?;
+        let variants: Vec<&str> = alleles.iter().map(|dto| dto.variant_string()).collect();
+        assert_eq!(vec!["c.617G>A", "c.619C>G"], variants);
+        assert!(alleles.iter().all(|dto| dto.transcript() == "NM_001111067.4"));
+        Ok(())
+    }
+
+    /// Edits mark the template as dirty until it is saved; failed edits do not change the state
+    #[test]
+    fn test_dirty_flag() -> Result<()> {
+        let mut phetools = PheTools::new(hpo());
+        phetools.load_matrix(matrix(), false).map_err(|e| e.join("; "))?;
+        assert!(!phetools.is_dirty());
+        assert!(phetools.set_value_typed(0, "HP:0001250", "sometimes").is_err());
+        assert!(!phetools.is_dirty());
+        phetools.set_value_typed(0, "HP:0001250", "excluded").unwrap();
+        assert!(phetools.is_dirty());
+        phetools.mark_saved();
+        assert!(!phetools.is_dirty());
+        Ok(())
+    }
+
+    /// The preview needs the validated variants of the directory manager
+    #[test]
+    fn test_phenopacket_json_without_manager() -> Result<()> {
+        let mut phetools = PheTools::new(hpo());
+        assert_eq!(Err("Phenopacket Template not initialized".to_string()), phetools.phenopacket_json(0));
+        phetools.load_matrix(matrix(), false).map_err(|e| e.join("; "))?;
+        assert_eq!(Err("Variant Manager Template not initialized".to_string()), phetools.phenopacket_json(0));
+        Ok(())
+    }
 }
 
 // endregion: --- Tests
@@ -7,15 +7,15 @@
 //! - A list of PpktRow (one per phenopacket)
 use std::{collections::{HashMap, HashSet}, fmt::format, str::FromStr, sync::Arc, vec};
 use ontolius::{
-    ontology::{csr::FullCsrOntology, MetadataAware, OntologyTerms},
+    ontology::{csr::FullCsrOntology, HierarchyQueries, MetadataAware, OntologyTerms},
     term::{simple::{SimpleMinimalTerm, SimpleTerm}, MinimalTerm},
     Identified, TermId,
 };
-use phenopackets::schema::v2::Phenopacket;
+use phenopackets::schema::v2::{Cohort, Phenopacket};
 use prost::Name;
 use serde::{Deserialize, Serialize};
 
-use crate::{dto::{case_dto::CaseDto, hpo_term_dto::HpoTermDto, template_dto::{DiseaseGeneDto, GeneVariantBundleDto, IndividualBundleDto, RowDto, TemplateDto}, validation_errors::ValidationErrors}, error::{self, Error, Result}, header::hpo_term_duplet::HpoTermDuplet, hpo::hpo_util::HpoUtil, ppkt::{ppkt_exporter::{self, PpktExporter}, ppkt_row::PpktRow}, template::header_duplet_row::HeaderDupletRow, variant::{hgvs_variant::HgvsVariant, structural_variant::StructuralVariant, variant_manager::VariantManager}};
+use crate::{dto::{case_dto::CaseDto, hpo_term_dto::HpoTermDto, template_dto::{CellKindDto, DiseaseDto, DiseaseGeneDto, GeneTranscriptDto, GeneVariantBundleDto, HeaderDupletDto, IndividualBundleDto, RowDto, TemplateDto}, validation_errors::{QcIssue, ValidationErrors}, variant_dto::{DuplicateAlleleGroupDto, VariantDto}}, error::{self, Error, Result}, header::{duplet_item::DupletItem, hgnc_util::HgncSymbolMap, hpo_term_duplet::HpoTermDuplet}, hpo::{age_util, hpo_util::HpoUtil}, ppkt::{ppkt_exporter::{self, ExportSettings, PpktExporter, TemplateProvenance}, ppkt_importer::PpktImporter, ppkt_row::PpktRow}, template::header_duplet_row::HeaderDupletRow, variant::{hgvs_variant::HgvsVariant, structural_variant::StructuralVariant, variant_manager::VariantManager}};
 use crate::{
     hpo::hpo_term_arranger::HpoTermArranger
 };
@@ -42,6 +42,92 @@ impl FromStr for TemplateType {
     }
 }
 
+/// Relation of an HPO term that is already in the template to a candidate term
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Relation {
+    /// The existing term is the candidate term
+    Same,
+    /// The existing term is more general than the candidate term
+    Ancestor,
+    /// The existing term is more specific than the candidate term
+    Descendant,
+}
+
+/// Comparison of the HPO terms observed in a cohort with the known annotations of the disease (e.g., from phenotype.hpoa)
+#[derive(Clone, Debug, Default, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CoverageReport {
+    /// Known disease features observed in at least one individual (directly or with a more specific term)
+    pub covered: Vec<TermId>,
+    /// Known disease features that were not observed in any individual of the cohort
+    pub missing: Vec<TermId>,
+    /// Observed terms that are neither known disease features nor more specific than one
+    pub extra: Vec<TermId>,
+}
+
+/// Summary of the Q/C issues of all rows of a template, e.g., to highlight problematic rows and columns in a GUI
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QcReport {
+    /// Number of errors in each row (zero-based index of the individual)
+    pub row_error_counts: Vec<usize>,
+    /// Number of errors in each column of the template matrix (errors that concern an entire row are not counted)
+    pub column_error_counts: Vec<usize>,
+    /// The errors, located in the template matrix (including the two header rows)
+    pub issues: Vec<QcIssue>,
+}
+
+/// Number of individuals for which an HPO term was observed, excluded, or not ascertained (na)
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TermFrequency {
+    pub term_id: TermId,
+    pub label: String,
+    /// Observed, with or without an age of onset
+    pub observed: usize,
+    pub excluded: usize,
+    pub na: usize,
+}
+
+impl TermFrequency {
+    /// True if the term was not annotated for any individual (all na); such columns can usually be deleted
+    pub fn is_unannotated(&self) -> bool {
+        self.observed == 0 && self.excluded == 0
+    }
+}
+
+/// Overview of a cohort, e.g., for a dashboard
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CohortSummary {
+    pub phenopacket_count: usize,
+    pub hpo_column_count: usize,
+    /// Distinct diseases of the cohort, in order of first occurrence (two per row for melded templates)
+    pub diseases: Vec<DiseaseDto>,
+    /// Distinct genes (with transcript) of the cohort, in order of first occurrence
+    pub genes: Vec<GeneTranscriptDto>,
+    /// Number of HPO cells with observed or an age of onset
+    pub observed_count: usize,
+    pub excluded_count: usize,
+    /// Number of HPO cells with na (or empty)
+    pub na_count: usize,
+}
+
+/// One (individual, HPO term) pair of the cohort in long ("tidy") format, e.g., for statistical analysis in R or Python
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LongRecord {
+    pub pmid: String,
+    pub individual_id: String,
+    pub term_id: String,
+    pub label: String,
+    /// observed, excluded, or na
+    pub value: String,
+    /// Age of onset if the cell contains an age (e.g., P16Y or Congenital onset); such terms are observed
+    pub onset: Option<String>,
+}
+
 /// All data needed to edit a cohort of phenopackets or export as GA4GH Phenopackets
 pub struct PheToolsTemplate {
     header: Arc<HeaderDupletRow>,
@@ -57,6 +143,8 @@ const TITLE_COL: usize = 1;
 const INDIVIDUAL_ID_COL: usize = 2;
 const INDIVIDUAL_COMMENT: usize = 3;
 const EMPTY_STRING: &str = "";
+/// Maximum number of example rows shown per column in the validation report
+const REPORT_SAMPLE_SIZE: usize = 3;
 
 impl PheToolsTemplate {
     /// Create the initial pyphetools template using HPO seed terms
@@ -69,6 +157,9 @@ impl PheToolsTemplate {
         let mut hp_header_duplet_list: Vec<HpoTermDuplet> = Vec::new();
         for hpo_id in hpo_term_ids {
             match hpo.term_by_id(&hpo_id) {
+                Some(term) if term.identifier() != &hpo_id => {
+                    return Err(Error::ObsoleteTermId { id: hpo_id.to_string(), replacement: term.identifier().to_string() });
+                }
                 Some(term) => {
                     let hpo_duplet = HpoTermDuplet::new(term.name(), term.identifier().to_string());
                     hp_header_duplet_list.push(hpo_duplet);
@@ -91,6 +182,8 @@ impl PheToolsTemplate {
         })
     }
 
+    /// Restore a template (Mendelian or melded) from a DTO created with [`Self::to_dto`], e.g., to resume editing
+    /// a template that was saved as JSON. The rows are not checked for errors.
     pub fn from_dto(
         hpo: Arc<FullCsrOntology>,
         cohort_dto: &TemplateDto) 
@@ -101,7 +194,11 @@ impl PheToolsTemplate {
             .iter()
             .map(|dto| dto.clone().to_hpo_duplet())
             .collect();
-        let updated_header: HeaderDupletRow = HeaderDupletRow::from_hpo_duplets(hpo_duplets, tt);
+        let updated_header: HeaderDupletRow = HeaderDupletRow::from_hpo_duplets(hpo_duplets, tt)
+            .with_ancestry(cohort_dto.has_ancestry())
+            .with_vital_status(cohort_dto.has_vital_status())
+            .with_karyotypic_sex(cohort_dto.has_karyotypic_sex())
+            .with_trailing_metadata(cohort_dto.trailing_metadata_headers.clone());
         let arc_header = Arc::new(updated_header);
         let updated_ppkt_rows = cohort_dto.rows.iter()
             .map(|dto| PpktRow::from_dto(dto, arc_header.clone())).collect();
@@ -116,12 +213,29 @@ impl PheToolsTemplate {
     
 
     pub fn get_template_dto(&self) -> Result<TemplateDto> {
+        Ok(self.to_dto())
+    }
+
+    /// Lossless representation of the template (HPO columns in column order, all cell values, the optional columns,
+    /// and the trailing metadata section) that can be saved as JSON and restored with [`Self::from_dto`]
+    pub fn to_dto(&self) -> TemplateDto {
         let header_dto = self.header.get_hpo_header_dtos();
         let row_dto_list: Vec<RowDto> = self.ppkt_rows
             .iter()
             .map(RowDto::from_ppkt_row)
             .collect();
-        Ok(TemplateDto::mendelian(header_dto, row_dto_list))
+        let mut template_dto = TemplateDto::mendelian(header_dto, row_dto_list);
+        template_dto.cohort_type = self.template_type;
+        template_dto.trailing_metadata_headers = self.header.trailing_metadata().to_vec();
+        template_dto.ancestry_column = self.header.has_ancestry();
+        template_dto.vital_status_columns = self.header.has_vital_status();
+        template_dto.karyotypic_sex_column = self.header.has_karyotypic_sex();
+        template_dto
+    }
+
+    /// Two header rows of all columns (see [`HeaderDupletRow::get_header_dtos`])
+    pub fn get_header_dtos(&self) -> Vec<HeaderDupletDto> {
+        self.header.get_header_dtos()
     }
 
     pub fn from_template_dto(
@@ -129,7 +243,11 @@ impl PheToolsTemplate {
         hpo: Arc<FullCsrOntology>) 
     -> std::result::Result<Self, ValidationErrors> {
         let header_duplet_row = match template_dto.cohort_type {
-            TemplateType::Mendelian => HeaderDupletRow::new_mendelian_ppkt_from_dto(&template_dto.hpo_headers),
+            TemplateType::Mendelian => HeaderDupletRow::new_mendelian_ppkt_from_dto(&template_dto.hpo_headers)
+                .with_ancestry(template_dto.has_ancestry())
+                .with_vital_status(template_dto.has_vital_status())
+                .with_karyotypic_sex(template_dto.has_karyotypic_sex())
+                .with_trailing_metadata(template_dto.trailing_metadata_headers.clone()),
             other => {
                 return Err(ValidationErrors::from_string(format!("Only Mendelian implemented. We cannot yet handle '{:?}'", other)));
             }
@@ -151,19 +269,173 @@ impl PheToolsTemplate {
         Ok(template)
     }
 
+    /// Find the HPO columns of the template that are the same as, ancestors of, or descendants of the candidate term.
+    /// Columns that are not related to the candidate are not returned, nor are columns with malformed identifiers.
+    pub fn get_related_terms(&self, candidate: &TermId) -> Vec<(TermId, Relation)> {
+        let mut related: Vec<(TermId, Relation)> = Vec::new();
+        for duplet in self.header.hpo_duplets() {
+            let existing = match duplet.to_term_id() {
+                Ok(tid) => tid,
+                Err(_) => continue,
+            };
+            if &existing == candidate {
+                related.push((existing, Relation::Same));
+            } else if self.hpo.is_ancestor_of(&existing, candidate) {
+                related.push((existing, Relation::Ancestor));
+            } else if self.hpo.is_descendant_of(&existing, candidate) {
+                related.push((existing, Relation::Descendant));
+            }
+        }
+        related
+    }
+
+    /// Compare the HPO terms observed in at least one individual with the known annotations of the disease.
+    /// A known term counts as covered if it or one of its descendants was observed. Excluded terms do not count,
+    /// and empty cells are treated as na. Columns with malformed identifiers are skipped.
+    pub fn get_coverage(&self, known_terms: &[TermId]) -> CoverageReport {
+        let mut observed: Vec<TermId> = Vec::new();
+        for (i, duplet) in self.header.hpo_duplets().iter().enumerate() {
+            let is_observed = self.ppkt_rows.iter()
+                .filter_map(|row| row.hpo_content().get(i))
+                .any(|value| !matches!(age_util::hpo_cell_value(value), "" | "na" | "excluded"));
+            if let (true, Ok(tid)) = (is_observed, duplet.to_term_id()) {
+                observed.push(tid);
+            }
+        }
+        let mut report = CoverageReport::default();
+        for known in known_terms {
+            if observed.iter().any(|tid| tid == known || self.hpo.is_descendant_of(tid, known)) {
+                report.covered.push(known.clone());
+            } else {
+                report.missing.push(known.clone());
+            }
+        }
+        report.extra = observed.into_iter()
+            .filter(|tid| !known_terms.iter().any(|known| tid == known || self.hpo.is_descendant_of(tid, known)))
+            .collect();
+        report
+    }
+
+    /// Get one record per individual and HPO column (long format). Cells that contain an age are
+    /// reported as observed with the age as onset. Empty cells are treated as na; na records are skipped if `skip_na` is true.
+    pub fn get_long_records(&self, skip_na: bool) -> Vec<LongRecord> {
+        let mut records: Vec<LongRecord> = Vec::new();
+        let hpo_duplets = self.header.hpo_duplets();
+        for ppkt_row in &self.ppkt_rows {
+            let individual = ppkt_row.get_individual_dto();
+            for (duplet, cell) in hpo_duplets.iter().zip(ppkt_row.hpo_content()) {
+                let (value, onset) = match age_util::hpo_cell_value(cell) {
+                    "" | "na" => ("na", None),
+                    "observed" => ("observed", None),
+                    "excluded" => ("excluded", None),
+                    age => ("observed", Some(age.to_string())),
+                };
+                if skip_na && value == "na" {
+                    continue;
+                }
+                records.push(LongRecord { 
+                    pmid: individual.pmid.clone(), 
+                    individual_id: individual.individual_id.clone(), 
+                    term_id: duplet.hpo_id().to_string(), 
+                    label: duplet.hpo_label().to_string(), 
+                    value: value.to_string(), 
+                    onset 
+                });
+            }
+        }
+        records
+    }
+
+    /// Serialize the template as a matrix of Strings (two header rows followed by one row per phenopacket).
+    /// This is the inverse of [`Self::from_mendelian_template`]; all columns, including the optional
+    /// comment and variant.comment columns, are written as is. The optional columns (e.g., ancestry) are written
+    /// after the HPO columns if the template has them, followed by the trailing metadata section (if any).
+    pub fn get_string_matrix(&self) -> Vec<Vec<String>> {
+        let header_dtos = self.header.get_header_dtos();
+        let row1: Vec<String> = header_dtos.iter().map(|h| h.h1.clone()).collect();
+        let row2: Vec<String> = header_dtos.iter().map(|h| h.h2.clone()).collect();
+        let mut matrix = Vec::with_capacity(self.n_rows());
+        matrix.push(row1);
+        matrix.push(row2);
+        for ppkt_row in &self.ppkt_rows {
+            matrix.push(self.get_string_row(ppkt_row));
+        }
+        matrix
+    }
+
+    /// Serialize one row of the template in the column order of [`Self::get_string_matrix`]
+    fn get_string_row(&self, ppkt_row: &PpktRow) -> Vec<String> {
+        let trailing_headers = self.header.trailing_metadata();
+        let mut row = ppkt_row.get_fixed_values();
+        row.extend(ppkt_row.hpo_content().iter().cloned());
+        row.extend(ppkt_row.get_optional_values());
+        if !trailing_headers.is_empty() {
+            row.push("na".to_string());
+            let values = ppkt_row.trailing_metadata();
+            row.extend((0..trailing_headers.len())
+                .map(|i| values.get(i).cloned().unwrap_or_default()));
+        }
+        row
+    }
+
+    /// Apply an operation (e.g., "trim", "remove whitespace", "na", "observed") to the cell at `row`/`col` of the
+    /// template matrix, whose first two rows are the header rows. "remove whitespace" is only available for
+    /// identifier and HGVS columns (see [`DupletItem::is_whitespace_free`]) and "clear" only for the optional
+    /// columns (see [`DupletItem::is_optional`]). The row is unchanged if the operation would result in an invalid row.
+    pub fn execute_operation(&mut self, row: usize, col: usize, operation: &str) -> Result<()> {
+        let op = Operation::from_keyword(operation)
+            .ok_or_else(|| Error::unrecognized_operation(operation))?;
+        if row < 2 || row >= self.ppkt_rows.len() + 2 {
+            return Err(Error::TemplateError { msg: format!("Row {row} is not a data row (the template has {} rows)", self.ppkt_rows.len() + 2) });
+        }
+        let mut values = self.get_string_row(&self.ppkt_rows[row - 2]);
+        if col >= values.len() {
+            return Err(Error::TemplateError { msg: format!("Column {col} out of bounds (the template has {} columns)", values.len()) });
+        }
+        let fixed_duplets = self.header.get_fixed_duplets();
+        let duplet = fixed_duplets.get(col);
+        if matches!(op, Operation::RemoveWhitespace) && !duplet.is_some_and(DupletItem::is_whitespace_free) {
+            return Err(Error::OperationError { msg: format!("Cannot remove whitespace in column {col} (use trim)") });
+        }
+        if matches!(op, Operation::Clear) && !duplet.is_some_and(DupletItem::is_optional) {
+            let msg = match duplet {
+                Some(duplet) => format!("Cannot clear column {col} ({} must not be empty)", duplet.row1()),
+                None => format!("Cannot clear column {col}"),
+            };
+            return Err(Error::OperationError { msg });
+        }
+        let value = op.apply(&values[col])
+            .ok_or_else(|| Error::OperationError { msg: format!("Operation '{}' requires a value", op.as_str()) })?;
+        values[col] = value;
+        let updated_row = PpktRow::from_row(self.header.clone(), values)
+            .map_err(Error::ValidationErrors)?;
+        self.ppkt_rows[row - 2] = updated_row;
+        Ok(())
+    }
+
     /// Get a list of all HPO identifiers currently in the template
     pub fn get_hpo_term_ids(&self) -> std::result::Result<Vec<TermId>, Vec<String>> {
         self.header.get_hpo_id_list().map_err(|verr|verr.errors().clone())
     }
 
+    /// Create a new template from seed HPO terms.
+    /// If `known_genes` is provided, each gene of the DiseaseGeneDto must match one of the allowed
+    /// (hgnc_id, gene_symbol, transcript) combinations.
     pub fn create_pyphetools_template(
         dg_dto: DiseaseGeneDto,
         hpo_term_ids: Vec<TermId>,
         hpo: Arc<FullCsrOntology>,
+        known_genes: Option<&[GeneTranscriptDto]>,
     ) -> Result<PheToolsTemplate> {
+        if let Some(allowed) = known_genes {
+            Self::check_known_genes(&dg_dto, allowed)?;
+        }
         let mut smt_list: Vec<SimpleMinimalTerm> = Vec::new();
         for hpo_id in &hpo_term_ids {
             match hpo.term_by_id(hpo_id) {
+                Some(term) if term.identifier() != hpo_id => {
+                    return Err(Error::ObsoleteTermId { id: hpo_id.to_string(), replacement: term.identifier().to_string() });
+                }
                 Some(term) => {
                     let smt =
                         SimpleMinimalTerm::new(term.identifier().clone(), term.name(), vec![], false);
@@ -182,12 +454,35 @@ impl PheToolsTemplate {
 
 
 
+    fn check_known_genes(
+        dg_dto: &DiseaseGeneDto,
+        known_genes: &[GeneTranscriptDto],
+    ) -> Result<()> {
+        for gt_dto in &dg_dto.gene_transcript_dto_list {
+            let is_known = known_genes.iter().any(|known| 
+                known.hgnc_id == gt_dto.hgnc_id 
+                    && known.gene_symbol == gt_dto.gene_symbol 
+                    && known.transcript == gt_dto.transcript);
+            if !is_known {
+                return Err(Error::TemplateError { 
+                    msg: format!("{} ({}, {}) is not in the list of known genes", 
+                        gt_dto.gene_symbol, gt_dto.hgnc_id, gt_dto.transcript) 
+                });
+            }
+        }
+        Ok(())
+    }
+
     pub fn from_mendelian_template(
-        matrix: Vec<Vec<String>>,
+        mut matrix: Vec<Vec<String>>,
         hpo: Arc<FullCsrOntology>,
         fix_errors: bool
     ) -> std::result::Result<Self, ValidationErrors> {
         let verrs = ValidationErrors::new();
+        if fix_errors {
+            // obsolete HPO ids would otherwise be reported as errors by the header Q/C
+            HpoUtil::new(hpo.clone()).remap_obsolete_ids(&mut matrix);
+        }
         let header = HeaderDupletRow::mendelian(&matrix, hpo.clone())?;
 
         const HEADER_ROWS: usize = 2; // first two rows of template are header
@@ -212,6 +507,109 @@ impl PheToolsTemplate {
 
     }
 
+    /// Check each data cell of a (Mendelian) template matrix and return the errors with their location.
+    /// This is used to map load failures to cells; the checks are the same as those applied by
+    /// [`Self::from_mendelian_template`] to the constant columns, the HPO columns, and the optional columns (e.g., ancestry).
+    pub fn locate_cell_issues(matrix: &[Vec<String>]) -> Vec<QcIssue> {
+        let mut issues: Vec<QcIssue> = Vec::new();
+        let fixed_duplets = HeaderDupletRow::from_hpo_duplets(vec![], TemplateType::Mendelian).get_fixed_duplets();
+        let (row0, row1) = match (matrix.first(), matrix.get(1)) {
+            (Some(row0), Some(row1)) => (row0, row1),
+            _ => { return issues; }
+        };
+        let optional_duplets = HeaderDupletRow::from_hpo_duplets(vec![], TemplateType::Mendelian)
+            .with_ancestry(true)
+            .with_vital_status(true)
+            .with_karyotypic_sex(true)
+            .get_optional_duplets();
+        for (r, row) in matrix.iter().enumerate().skip(2) {
+            for (c, cell) in row.iter().enumerate() {
+                let optional = row0.get(c)
+                    .and_then(|h| optional_duplets.iter().find(|duplet| duplet.row1() == h));
+                let result = if let Some(duplet) = fixed_duplets.get(c) {
+                    duplet.qc_data(cell)
+                } else if let Some(duplet) = optional {
+                    duplet.qc_data(cell)
+                } else if row1.get(c).is_some_and(|h| h.starts_with("HP:")) {
+                    age_util::check_hpo_table_cell(cell)
+                } else {
+                    Ok(())
+                };
+                if let Err(msg) = result {
+                    issues.push(QcIssue::cell_error(r, c, msg));
+                }
+            }
+        }
+        issues
+    }
+
+    /// Set the value (e.g., observed, excluded, na, P3Y) of an HPO column in one row (zero-based index of the individual)
+    pub fn set_hpo_value(&mut self, row: usize, tid: &TermId, value: &str) -> Result<()> {
+        let term = self.hpo.term_by_id(tid)
+            .ok_or_else(|| Error::HpIdNotFound { id: tid.to_string() })?;
+        if term.identifier() != tid {
+            return Err(Error::ObsoleteTermId { id: tid.to_string(), replacement: term.identifier().to_string() });
+        }
+        age_util::check_hpo_table_cell(value)
+            .map_err(|msg| Error::TemplateError { msg })?;
+        let n_rows = self.ppkt_rows.len();
+        let ppkt_row = self.ppkt_rows.get(row)
+            .ok_or_else(|| Error::row_index_error(row, n_rows))?;
+        let updated_row = ppkt_row.update_hpo_values(&HashMap::from([(tid.clone(), value.to_string())]))
+            .map_err(Error::ValidationErrors)?;
+        self.ppkt_rows[row] = updated_row;
+        Ok(())
+    }
+
+    /// Q/C issues of the cells of one row (zero-based index of the individual), located in the template matrix
+    pub fn get_row_issues(&self, row: usize) -> std::result::Result<Vec<QcIssue>, String> {
+        let ppkt_row = self.ppkt_rows.get(row)
+            .ok_or_else(|| format!("Row index {row} out of bounds (template has {} rows)", self.ppkt_rows.len()))?;
+        Ok(ppkt_row.get_cell_issues(row + 2)) // skip the two header rows
+    }
+
+    /// Check all rows of the template and summarize the errors per row and per column. Cell errors are located as in
+    /// [`Self::get_row_issues`]; other errors of [`PpktRow::check_for_errors`] (e.g., onset after last encounter) are
+    /// attributed to the row only.
+    pub fn get_qc_report(&self) -> QcReport {
+        let n_columns = self.header.get_header_dtos().len();
+        let mut report = QcReport {
+            row_error_counts: Vec::with_capacity(self.ppkt_rows.len()),
+            column_error_counts: vec![0; n_columns],
+            issues: Vec::new(),
+        };
+        for (i, ppkt_row) in self.iter_ppkt_rows().enumerate() {
+            let matrix_row = i + 2; // skip the two header rows
+            let mut issues = ppkt_row.get_cell_issues(matrix_row);
+            if let Err(verrs) = ppkt_row.check_for_errors() {
+                for e in verrs.errors() {
+                    if !issues.iter().any(|issue| issue.message == e) {
+                        issues.push(QcIssue::row_error(matrix_row, e));
+                    }
+                }
+            }
+            for col in issues.iter().filter_map(|issue| issue.col) {
+                if let Some(count) = report.column_error_counts.get_mut(col) {
+                    *count += 1;
+                }
+            }
+            report.row_error_counts.push(issues.len());
+            report.issues.extend(issues);
+        }
+        report
+    }
+
+    /// Assemble a template from pre-built parts without any quality control.
+    /// This is intended for tests that need templates in known (possibly invalid) states.
+    pub(crate) fn from_parts(
+        header: Arc<HeaderDupletRow>,
+        ppkt_rows: Vec<PpktRow>,
+        template_type: TemplateType,
+        hpo: Arc<FullCsrOntology>,
+    ) -> Self {
+        Self { header, template_type, hpo, ppkt_rows }
+    }
+
     fn check_duplet(&self, duplet: &HpoTermDuplet) -> std::result::Result<(), String> {
         let term_id = match TermId::from_str(duplet.hpo_id()) {
             Ok(tid) => tid,
@@ -233,7 +631,8 @@ impl PheToolsTemplate {
 
     /// This function can be used after we have converted a DTO to a PhetoolsTemplate
     /// to check for syntactic errors in all of the fields (corresponding to all of the columns of the template)
-    /// It does not check for ontology errors, e.g., a term is excluded and a child of that term is observed
+    /// It also checks for ontology conflicts, i.e., a term is observed while one of its ancestors is excluded
+    /// (see [`Self::check_ancestor_conflicts`])
     pub fn check_for_errors(&self) -> std::result::Result<(), ValidationErrors> {
         let mut verrs = ValidationErrors::new();
         for duplet in &self.header.get_hpo_duplets() {
@@ -242,10 +641,253 @@ impl PheToolsTemplate {
         for ppkt_row in &self.ppkt_rows {
             verrs.push_verr_result(ppkt_row.check_for_errors());
         }
+        verrs.push_verr_result(self.check_ancestor_conflicts());
+        verrs.ok()
+    }
+
+    /// Semantic Q/C: a term cannot be observed in a row in which one of its ancestors is excluded
+    /// (e.g., Short 1st metacarpal observed but Short thumb excluded), since this would produce a contradictory phenopacket.
+    pub fn check_ancestor_conflicts(&self) -> std::result::Result<(), ValidationErrors> {
+        let mut verrs = ValidationErrors::new();
+        for (i, ppkt_row) in self.ppkt_rows.iter().enumerate() {
+            let dto_list = match ppkt_row.get_hpo_term_dto_list() {
+                Ok(dto_list) => dto_list,
+                Err(e) => {
+                    verrs.push_str(e);
+                    continue;
+                }
+            };
+            let mut observed: Vec<(TermId, String)> = Vec::new();
+            let mut excluded: Vec<(TermId, String)> = Vec::new();
+            for dto in dto_list.iter().filter(|dto| dto.is_ascertained()) {
+                let Ok(tid) = TermId::from_str(dto.term_id()) else {
+                    continue; // reported by the header Q/C
+                };
+                if dto.is_excluded() {
+                    excluded.push((tid, dto.label()));
+                } else {
+                    observed.push((tid, dto.label()));
+                }
+            }
+            for (obs_tid, obs_label) in &observed {
+                for (excl_tid, excl_label) in &excluded {
+                    if self.hpo.is_ancestor_of(excl_tid, obs_tid) {
+                        verrs.push_str(format!("Row {} ({}): {} ({}) is observed but its ancestor {} ({}) is excluded",
+                            i, ppkt_row.get_individual_dto().individual_id, obs_label, obs_tid, excl_label, excl_tid));
+                    }
+                }
+            }
+        }
+        verrs.ok()
+    }
+
 
+    /// Check that each row has the number of disease and gene/variant bundles expected for the template type,
+    /// i.e., one of each for Mendelian and two of each for Melded templates.
+    pub fn check_row_structure(&self) -> std::result::Result<(), ValidationErrors> {
+        let mut verrs = ValidationErrors::new();
+        let expected = match self.template_type {
+            TemplateType::Mendelian => 1,
+            TemplateType::Melded => 2,
+        };
+        for (i, ppkt_row) in self.ppkt_rows.iter().enumerate() {
+            let individual_id = ppkt_row.get_individual_dto().individual_id;
+            if ppkt_row.disease_count() != expected {
+                verrs.push_str(format!("Row {} ({}): expected {} disease(s) for {:?} template but found {}", 
+                    i, individual_id, expected, self.template_type, ppkt_row.disease_count()));
+            }
+            if ppkt_row.gene_variant_count() != expected {
+                verrs.push_str(format!("Row {} ({}): expected {} gene/variant bundle(s) for {:?} template but found {}", 
+                    i, individual_id, expected, self.template_type, ppkt_row.gene_variant_count()));
+            }
+        }
         verrs.ok()
     }
 
+    /// Count the individuals of the cohort by HPO onset category (e.g., Childhood onset).
+    /// Rows whose age of onset is "na" or cannot be assigned to a category are not counted.
+    pub fn get_onset_distribution(&self) -> HashMap<String, usize> {
+        let mut onset_counts: HashMap<String, usize> = HashMap::new();
+        for ppkt_row in &self.ppkt_rows {
+            let onset = ppkt_row.get_individual_dto().age_of_onset;
+            if let Some(category) = age_util::onset_category(&onset) {
+                *onset_counts.entry(category).or_insert(0) += 1;
+            }
+        }
+        onset_counts
+    }
+
+    /// Create a human-readable report of the problems in the template, e.g., to send to a collaborator.
+    /// Errors (invalid cell contents, rows that do not match the template type) and warnings (rows without
+    /// any observed or excluded HPO term, HPO columns without any data, alleles with normalized invisible
+    /// whitespace, implausibly high ages) are grouped by column, with
+    /// a count and up to [`REPORT_SAMPLE_SIZE`] example rows per column.
+    pub fn get_validation_report(&self) -> String {
+        self.get_validation_report_with_warnings(Vec::new())
+    }
+
+    /// Same as [`Self::get_validation_report`], with additional (column, message) warnings from checks that
+    /// need more information than the template itself, e.g., [`Self::get_unknown_sex_x_linked_warnings`].
+    pub fn get_validation_report_with_warnings(&self, additional_warnings: Vec<(String, String)>) -> String {
+        let mut errors: Vec<(String, String)> = Vec::new();
+        let mut warnings: Vec<(String, String)> = Vec::new();
+        let fixed_duplets = self.header.get_fixed_duplets();
+        let hpo_duplets = self.header.hpo_duplets();
+        let mut hpo_column_has_data = vec![false; hpo_duplets.len()];
+        if let Err(verrs) = self.check_row_structure() {
+            for e in verrs.errors() {
+                errors.push(("row structure".to_string(), e));
+            }
+        }
+        for (i, ppkt_row) in self.ppkt_rows.iter().enumerate() {
+            let row_label = format!("row {} ({})", i + 1, ppkt_row.get_individual_dto().individual_id);
+            for (duplet, value) in fixed_duplets.iter().zip(ppkt_row.get_fixed_values()) {
+                if let Err(e) = duplet.qc_data(&value) {
+                    errors.push((duplet.row1().to_string(), format!("{row_label}: {e}")));
+                }
+            }
+            let mut has_hpo_data = false;
+            for (j, (duplet, value)) in hpo_duplets.iter().zip(ppkt_row.hpo_content()).enumerate() {
+                if let Err(e) = age_util::check_hpo_table_cell(value) {
+                    errors.push((duplet.hpo_label().to_string(), format!("{row_label}: {e}")));
+                }
+                if value != "na" && !value.is_empty() {
+                    has_hpo_data = true;
+                    hpo_column_has_data[j] = true;
+                }
+            }
+            if !has_hpo_data {
+                warnings.push(("HPO".to_string(), format!("{row_label}: no observed or excluded HPO terms")));
+            }
+            for (duplet, value) in self.header.get_optional_duplets().iter().zip(ppkt_row.get_optional_values()) {
+                if let Err(e) = duplet.qc_data(&value) {
+                    errors.push((duplet.row1().to_string(), format!("{row_label}: {e}")));
+                }
+            }
+            let individual = ppkt_row.get_individual_dto();
+            if individual.deceased == "yes" && individual.age_at_last_encounter == "na" {
+                warnings.push(("age_at_last_encounter".to_string(), format!("{row_label}: deceased individual without age at last encounter")));
+            }
+            for (column, age) in [("age_of_onset", &individual.age_of_onset), ("age_at_last_encounter", &individual.age_at_last_encounter)] {
+                if let Err(e) = age_util::check_plausible_age(age) {
+                    warnings.push((column.to_string(), format!("{row_label}: {e}")));
+                }
+            }
+            for note in ppkt_row.allele_normalization_notes() {
+                warnings.push(("allele".to_string(), format!("{row_label}: {note}")));
+            }
+        }
+        for (duplet, has_data) in hpo_duplets.iter().zip(hpo_column_has_data) {
+            if !has_data {
+                warnings.push((duplet.hpo_label().to_string(), format!("{} ({}): no data in any row", duplet.hpo_label(), duplet.hpo_id())));
+            }
+        }
+        warnings.extend(additional_warnings);
+        let mut report = format!("Validation report: {} individuals, {} HPO columns\n", self.ppkt_rows.len(), hpo_duplets.len());
+        if errors.is_empty() && warnings.is_empty() {
+            report.push_str("No issues found.\n");
+            return report;
+        }
+        Self::append_report_section(&mut report, "Errors", &errors);
+        Self::append_report_section(&mut report, "Warnings", &warnings);
+        report
+    }
+
+    /// Find rows with unknown sex ("U") that carry a variant on the X chromosome. For X-linked genes,
+    /// the allelic state (hemizygous or heterozygous) cannot be inferred in this case. Only validated HGVS
+    /// variants are considered, since structural variants do not have chromosomal coordinates.
+    /// Returns (column, message) warnings as used in [`Self::get_validation_report_with_warnings`].
+    pub fn get_unknown_sex_x_linked_warnings(&self, hgvs_dict: &HashMap<String, HgvsVariant>) -> Vec<(String, String)> {
+        let mut warnings: Vec<(String, String)> = Vec::new();
+        for (i, ppkt_row) in self.ppkt_rows.iter().enumerate() {
+            let individual = ppkt_row.get_individual_dto();
+            if individual.sex != "U" {
+                continue;
+            }
+            for gvb in ppkt_row.get_gene_var_dto_list() {
+                let x_linked = [&gvb.allele1, &gvb.allele2]
+                    .iter()
+                    .filter_map(|allele| hgvs_dict.get(allele.as_str()))
+                    .any(|hgvs| hgvs.is_x_chromosomal());
+                if x_linked {
+                    warnings.push(("sex".to_string(), format!(
+                        "row {} ({}): unknown sex (U) with X-chromosomal variant in {}; allelic state cannot be inferred",
+                        i + 1, individual.individual_id, gvb.gene_symbol)));
+                }
+            }
+        }
+        warnings
+    }
+
+    /// Get the distinct PMIDs of the template with the title of the first row that cites them
+    pub fn get_pmid_titles(&self) -> Vec<(String, String)> {
+        let mut pmid_titles: Vec<(String, String)> = Vec::new();
+        for ppkt_row in &self.ppkt_rows {
+            let individual = ppkt_row.get_individual_dto();
+            if !pmid_titles.iter().any(|(pmid, _)| pmid == &individual.pmid) {
+                pmid_titles.push((individual.pmid, individual.title));
+            }
+        }
+        pmid_titles
+    }
+
+    /// Find HPO terms that are annotated as observed in one row and as excluded in another row with the
+    /// same individual_id but a different PMID, i.e., the same individual reported in two publications
+    /// with contradictory annotations. Onset ages count as observed; "na" and empty cells are ignored.
+    /// Returns (column, message) warnings for curator review as used in [`Self::get_validation_report_with_warnings`].
+    pub fn get_cross_pmid_conflict_warnings(&self) -> Vec<(String, String)> {
+        let mut warnings: Vec<(String, String)> = Vec::new();
+        let hpo_duplets = self.header.hpo_duplets();
+        let individuals: Vec<IndividualBundleDto> = self.ppkt_rows.iter()
+            .map(|row| row.get_individual_dto())
+            .collect();
+        for i in 0..self.ppkt_rows.len() {
+            for j in (i + 1)..self.ppkt_rows.len() {
+                let (ind_i, ind_j) = (&individuals[i], &individuals[j]);
+                if ind_i.individual_id != ind_j.individual_id || ind_i.pmid == ind_j.pmid {
+                    continue;
+                }
+                let values = self.ppkt_rows[i].hpo_content().iter()
+                    .zip(self.ppkt_rows[j].hpo_content().iter());
+                for (duplet, (value_i, value_j)) in hpo_duplets.iter().zip(values) {
+                    let (cell_i, cell_j) = (age_util::hpo_cell_value(value_i), age_util::hpo_cell_value(value_j));
+                    let conflict = !matches!(cell_i, "" | "na") && !matches!(cell_j, "" | "na")
+                        && (cell_i == "excluded") != (cell_j == "excluded");
+                    if conflict {
+                        warnings.push((duplet.hpo_label().to_string(), format!(
+                            "individual '{}': {} ({}) is '{}' in {} but '{}' in {}",
+                            ind_i.individual_id, duplet.hpo_label(), duplet.hpo_id(),
+                            value_i, ind_i.pmid, value_j, ind_j.pmid)));
+                    }
+                }
+            }
+        }
+        warnings
+    }
+
+    /// Append the issues of one severity level to the report, grouped by column in order of first appearance
+    fn append_report_section(report: &mut String, title: &str, issues: &[(String, String)]) {
+        report.push_str(&format!("{title} ({})\n", issues.len()));
+        let mut columns: Vec<&str> = Vec::new();
+        for (column, _) in issues {
+            if !columns.contains(&column.as_str()) {
+                columns.push(column);
+            }
+        }
+        for column in columns {
+            let messages: Vec<&str> = issues.iter()
+                .filter(|(c, _)| c == column)
+                .map(|(_, msg)| msg.as_str())
+                .collect();
+            report.push_str(&format!("  {column}: {} issue(s)\n", messages.len()));
+            for msg in messages.iter().take(REPORT_SAMPLE_SIZE) {
+                report.push_str(&format!("    - {msg}\n"));
+            }
+            if messages.len() > REPORT_SAMPLE_SIZE {
+                report.push_str(&format!("    ... and {} more\n", messages.len() - REPORT_SAMPLE_SIZE));
+            }
+        }
+    }
 
     /// Validate the current template
     ///
@@ -266,21 +908,320 @@ impl PheToolsTemplate {
         self.ppkt_rows.len()
     }
 
+    /// Iterate over the rows of the template as [`RowDto`] objects, which are created lazily
+    pub fn row_dtos(&self) -> impl Iterator<Item = RowDto> + '_ {
+        self.ppkt_rows.iter().map(RowDto::from_ppkt_row)
+    }
+
+    /// Count how many individuals are observed, excluded, and na for each HPO column, in column order
+    pub fn get_term_frequencies(&self) -> std::result::Result<Vec<TermFrequency>, String> {
+        let mut frequencies: Vec<TermFrequency> = Vec::with_capacity(self.header.hpo_count());
+        for duplet in self.header.hpo_duplets() {
+            frequencies.push(TermFrequency { 
+                term_id: duplet.to_term_id()?, 
+                label: duplet.hpo_label().to_string(), 
+                observed: 0, 
+                excluded: 0, 
+                na: 0 
+            });
+        }
+        for ppkt_row in &self.ppkt_rows {
+            let hpo_term_dto_list = ppkt_row.get_hpo_term_dto_list()?;
+            for (frequency, dto) in frequencies.iter_mut().zip(hpo_term_dto_list.iter()) {
+                if dto.is_excluded() {
+                    frequency.excluded += 1;
+                } else if dto.is_not_ascertained() || dto.entry().is_empty() {
+                    frequency.na += 1;
+                } else {
+                    frequency.observed += 1;
+                }
+            }
+        }
+        Ok(frequencies)
+    }
+
+    /// HPO columns (TermId and label) that are na or empty in every row. These columns add no information and can
+    /// be removed with [`Self::prune_empty_hpo_columns`]. A template without rows has no such columns.
+    pub fn get_unannotated_hpo_terms(&self) -> std::result::Result<Vec<(TermId, String)>, String> {
+        if self.ppkt_rows.is_empty() {
+            return Ok(vec![]);
+        }
+        Ok(self.get_term_frequencies()?
+            .into_iter()
+            .filter(TermFrequency::is_unannotated)
+            .map(|frequency| (frequency.term_id, frequency.label))
+            .collect())
+    }
+
+    /// Delete the HPO columns that are na or empty in every row and return their TermIds and labels.
+    /// The template is unchanged if any column cannot be deleted.
+    pub fn prune_empty_hpo_columns(&mut self) -> std::result::Result<Vec<(TermId, String)>, ValidationErrors> {
+        let unannotated = self.get_unannotated_hpo_terms()
+            .map_err(ValidationErrors::from_one_err)?;
+        let first_hpo_col = self.header.get_fixed_duplets().len();
+        let empty_columns: Vec<usize> = self.header.hpo_duplets()
+            .iter()
+            .enumerate()
+            .filter(|(_, duplet)| unannotated.iter().any(|(tid, _)| tid.to_string() == duplet.hpo_id()))
+            .map(|(i, _)| first_hpo_col + i)
+            .collect();
+        let header = self.header.clone();
+        let ppkt_rows = self.ppkt_rows.clone();
+        // delete from the right so that the indices of the remaining empty columns do not change
+        for col in empty_columns.into_iter().rev() {
+            if let Err(verrs) = self.delete_hpo_column(col) {
+                self.header = header;
+                self.ppkt_rows = ppkt_rows;
+                return Err(verrs);
+            }
+        }
+        Ok(unannotated)
+    }
+
+    /// Counts of phenopackets and HPO cells and the diseases and genes of the cohort
+    pub fn cohort_summary(&self) -> CohortSummary {
+        let mut summary = CohortSummary {
+            phenopacket_count: self.ppkt_rows.len(),
+            hpo_column_count: self.header.hpo_count(),
+            diseases: Vec::new(),
+            genes: Vec::new(),
+            observed_count: 0,
+            excluded_count: 0,
+            na_count: 0,
+        };
+        for ppkt_row in &self.ppkt_rows {
+            for disease in ppkt_row.get_disease_dto_list() {
+                if !summary.diseases.contains(&disease) {
+                    summary.diseases.push(disease);
+                }
+            }
+            for gvb in ppkt_row.get_gene_var_dto_list() {
+                let seen = summary.genes.iter()
+                    .any(|g| g.hgnc_id == gvb.hgnc_id && g.transcript == gvb.transcript);
+                if !seen {
+                    summary.genes.push(GeneTranscriptDto {
+                        hgnc_id: gvb.hgnc_id,
+                        gene_symbol: gvb.gene_symbol,
+                        transcript: gvb.transcript,
+                    });
+                }
+            }
+            for cell in ppkt_row.hpo_content() {
+                match age_util::hpo_cell_value(cell) {
+                    "excluded" => summary.excluded_count += 1,
+                    "na" | "" => summary.na_count += 1,
+                    _ => summary.observed_count += 1,
+                }
+            }
+        }
+        summary
+    }
+
+    /// Kind of value (and allowed values, if any) of the cell at `row`/`col` of the template matrix, whose first two
+    /// rows are the header rows. Header cells cannot be edited and result in an error.
+    pub fn get_cell_kind(&self, row: usize, col: usize) -> Result<CellKindDto> {
+        if row < 2 || row >= self.ppkt_rows.len() + 2 {
+            return Err(Error::TemplateError { msg: format!("Row {row} is not a data row (the template has {} rows)", self.ppkt_rows.len() + 2) });
+        }
+        self.header.get_cell_kinds()
+            .into_iter()
+            .nth(col)
+            .ok_or_else(|| Error::TemplateError { msg: format!("Column {col} out of bounds (the template has {} columns)", self.n_columns()) })
+    }
+
+    /// Iterate over the rows of the template without copying them, e.g., to run Q/C or export on a large cohort
+    pub fn iter_ppkt_rows(&self) -> impl ExactSizeIterator<Item = &PpktRow> + '_ {
+        self.ppkt_rows.iter()
+    }
+
 
 
     /// Delete a row. We expect this to come from a GUI where the rows include
     /// the headers (two rows) and adjust here. TODO - Consider
     /// adjusting the count in the GUI
     pub fn delete_row(&mut self, row: usize) -> Result<()> {
-        if row > self.ppkt_rows.len() {
+        if row >= self.ppkt_rows.len() {
             return Err(Error::TemplateError { msg: format!("Attempt to delete row {row} but there are only {} rows", self.ppkt_rows.len()) });
         }
         self.ppkt_rows.remove(row);
         Ok(())
     }
 
-    pub fn get_variant_dto_list(&self) {
-        
+    /// Delete an HPO column, e.g., a term that was added by mistake. `col` is the index of the column in the
+    /// template matrix; only HPO columns (not the fixed individual, disease, gene/variant, and demographic columns)
+    /// may be deleted. The values of the other HPO columns are retained in all rows.
+    pub fn delete_hpo_column(&mut self, col: usize) -> std::result::Result<(), ValidationErrors> {
+        let first_hpo_col = self.header.get_fixed_duplets().len();
+        let hpo_duplets = self.header.hpo_duplets();
+        if col < first_hpo_col || col >= first_hpo_col + hpo_duplets.len() {
+            return Err(ValidationErrors::from_one_err(format!(
+                "Column {col} is not an HPO column (the {} HPO columns start at column {first_hpo_col}); fixed columns cannot be deleted",
+                hpo_duplets.len())));
+        }
+        let hpo_idx = col - first_hpo_col;
+        let mut hpo_terms: Vec<(TermId, String)> = Vec::with_capacity(hpo_duplets.len());
+        for (i, duplet) in hpo_duplets.iter().enumerate() {
+            if i == hpo_idx {
+                continue;
+            }
+            let tid = TermId::from_str(duplet.hpo_id())
+                .map_err(|_| ValidationErrors::from_one_err(format!("Could not parse {}", duplet.hpo_id())))?;
+            hpo_terms.push((tid, duplet.hpo_label().to_string()));
+        }
+        let updated_hdr = HeaderDupletRow::rebuild_from_terms(&self.header.get_fixed_duplets(), &hpo_terms, self.template_type)?
+            .with_ancestry(self.header.has_ancestry())
+            .with_vital_status(self.header.has_vital_status())
+            .with_karyotypic_sex(self.header.has_karyotypic_sex())
+            .with_trailing_metadata(self.header.trailing_metadata().to_vec());
+        let updated_hdr_arc = Arc::new(updated_hdr);
+        let mut verrs = ValidationErrors::new();
+        let mut updated_ppkt_rows: Vec<PpktRow> = Vec::with_capacity(self.ppkt_rows.len());
+        for ppkt in &self.ppkt_rows {
+            match ppkt.update_header(updated_hdr_arc.clone()) {
+                Ok(new_ppkt) => updated_ppkt_rows.push(new_ppkt),
+                Err(e) => verrs.add_errors(e.errors()),
+            }
+        }
+        if verrs.has_error() {
+            return Err(verrs);
+        }
+        self.header = updated_hdr_arc;
+        self.ppkt_rows = updated_ppkt_rows;
+        Ok(())
+    }
+
+    /// Get a list of the distinct alleles referenced in the template as [`VariantDto`] objects.
+    /// Alleles that start with "c." or "n." are treated as HGVS, all others as candidate structural variants.
+    /// Empty ("na") alleles are skipped. The dtos are not validated.
+    pub fn get_variant_dto_list(&self) -> Vec<VariantDto> {
+        let mut seen: HashSet<(String, String)> = HashSet::new();
+        let mut dto_list: Vec<VariantDto> = Vec::new();
+        for ppkt_row in &self.ppkt_rows {
+            for gvb in ppkt_row.get_gene_var_dto_list() {
+                for allele in [&gvb.allele1, &gvb.allele2] {
+                    if allele == "na" || allele.is_empty() {
+                        continue;
+                    }
+                    if !seen.insert((allele.clone(), gvb.transcript.clone())) {
+                        continue;
+                    }
+                    let dto = if allele.starts_with("c.") || allele.starts_with("n.") {
+                        VariantDto::new_hgvs(allele, &gvb.transcript, &gvb.hgnc_id, &gvb.gene_symbol)
+                    } else {
+                        VariantDto::new_sv(allele, &gvb.transcript, &gvb.hgnc_id, &gvb.gene_symbol)
+                    };
+                    dto_list.push(dto);
+                }
+            }
+        }
+        VariantDto::sort_variant_dtos(&mut dto_list);
+        dto_list
+    }
+
+    /// Find alleles that are spelled differently (e.g., c.76_78del and c.76_78delACG) but that resolve to the same
+    /// genomic coordinates after validation. Alleles that are not in `hgvs_dict` (not yet validated, or structural)
+    /// are ignored. The shortest spelling of each group is proposed as the canonical one. The groups should be
+    /// shown to the curator, who can then merge them with [`Self::merge_duplicate_alleles`].
+    pub fn find_duplicate_alleles(&self, hgvs_dict: &HashMap<String, HgvsVariant>) -> Vec<DuplicateAlleleGroupDto> {
+        let mut coordinates_to_alleles: HashMap<String, Vec<String>> = HashMap::new();
+        for dto in self.get_variant_dto_list() {
+            if let Some(hgvs) = hgvs_dict.get(dto.variant_string()) {
+                let vcf = format!("{} {}:{}{}>{}", hgvs.assembly(), hgvs.chr(), hgvs.position(), hgvs.ref_allele(), hgvs.alt_allele());
+                let alleles = coordinates_to_alleles.entry(vcf).or_default();
+                if !alleles.iter().any(|a| a == dto.variant_string()) {
+                    alleles.push(dto.variant_string().to_string());
+                }
+            }
+        }
+        let mut groups: Vec<DuplicateAlleleGroupDto> = Vec::new();
+        for (vcf, mut alleles) in coordinates_to_alleles {
+            if alleles.len() < 2 {
+                continue;
+            }
+            alleles.sort_by(|a, b| a.len().cmp(&b.len()).then_with(|| a.cmp(b)));
+            let canonical = alleles.remove(0);
+            groups.push(DuplicateAlleleGroupDto { vcf, canonical, duplicates: alleles });
+        }
+        groups.sort_by(|a, b| a.canonical.cmp(&b.canonical));
+        groups
+    }
+
+    /// Find validated alleles whose cached [`HgvsVariant`] was validated against a different transcript than the one
+    /// currently used in the template (e.g., after the transcript was changed). The genomic coordinates of these
+    /// alleles may be wrong, so they must be validated again before export. Cached variants without a transcript are ignored.
+    pub fn find_stale_transcript_alleles(&self, hgvs_dict: &HashMap<String, HgvsVariant>) -> Vec<String> {
+        let mut stale: Vec<String> = Vec::new();
+        for dto in self.get_variant_dto_list() {
+            let cached_transcript = hgvs_dict.get(dto.variant_string())
+                .and_then(|hgvs| hgvs.transcript())
+                .map(|tx| tx.split(':').next().unwrap_or(tx)); // e.g., NM_000138.5:c.8242G>T
+            if let Some(cached_transcript) = cached_transcript {
+                if cached_transcript != dto.transcript() {
+                    stale.push(format!("{} (validated against {}, template uses {})", 
+                        dto.variant_string(), cached_transcript, dto.transcript()));
+                }
+            }
+        }
+        stale
+    }
+
+    /// Export preflight: Err if any validated allele is stale (see [`Self::find_stale_transcript_alleles`])
+    pub fn check_validated_transcripts(&self, hgvs_dict: &HashMap<String, HgvsVariant>) -> std::result::Result<(), String> {
+        let stale = self.find_stale_transcript_alleles(hgvs_dict);
+        if stale.is_empty() {
+            Ok(())
+        } else {
+            Err(format!("Re-validate against new transcript before export: {}", stale.join("; ")))
+        }
+    }
+
+    /// Export preflight: find the alleles that are neither in `hgvs_dict` nor in `structural_dict` and would therefore
+    /// make the export fail. Returns (zero-based index of the individual, allele) pairs in row order; "na" alleles are skipped.
+    pub fn find_unvalidated_alleles(
+        &self,
+        hgvs_dict: &HashMap<String, HgvsVariant>,
+        structural_dict: &HashMap<String, StructuralVariant>
+    ) -> Vec<(usize, String)> {
+        let mut unvalidated: Vec<(usize, String)> = Vec::new();
+        for (i, ppkt_row) in self.ppkt_rows.iter().enumerate() {
+            for gvb in ppkt_row.get_gene_var_dto_list() {
+                for allele in [gvb.allele1, gvb.allele2] {
+                    if allele != "na" && !allele.is_empty() 
+                        && !hgvs_dict.contains_key(&allele) && !structural_dict.contains_key(&allele) {
+                        unvalidated.push((i, allele));
+                    }
+                }
+            }
+        }
+        unvalidated
+    }
+
+    /// Check that the gene symbol of each gene/variant bundle is the approved symbol of its HGNC identifier
+    /// (only genes in the `symbol_map` can be checked)
+    pub fn check_hgnc_symbols(&self, symbol_map: &HgncSymbolMap) -> std::result::Result<(), ValidationErrors> {
+        let mut verrs = ValidationErrors::new();
+        for (i, ppkt_row) in self.ppkt_rows.iter().enumerate() {
+            for gvb in ppkt_row.get_gene_var_dto_list() {
+                if let Err(e) = symbol_map.check_symbol(&gvb.hgnc_id, &gvb.gene_symbol) {
+                    verrs.push_str(format!("Row {}: {}", i + 1, e));
+                }
+            }
+        }
+        verrs.ok()
+    }
+
+    /// Replace the duplicate spellings of each group by the canonical spelling in all rows.
+    /// Returns the number of cells that were changed.
+    pub fn merge_duplicate_alleles(&mut self, groups: &[DuplicateAlleleGroupDto]) -> usize {
+        let mut n_replaced = 0;
+        for group in groups {
+            for duplicate in &group.duplicates {
+                for ppkt_row in &mut self.ppkt_rows {
+                    n_replaced += ppkt_row.replace_allele(duplicate, &group.canonical);
+                }
+            }
+        }
+        n_replaced
     }
 
     /// Arranges the given HPO terms into a specific order for curation.
@@ -320,8 +1261,8 @@ impl PheToolsTemplate {
         let mut term_arrager = HpoTermArranger::new(self.hpo.clone());
         let arranged_terms = term_arrager.arrange_terms(&all_tids)?;
          // === Step 3: Rearrange the existing PpktRow objects to have the new HPO terms set to "na"
-        // 3a. Update the HeaderDupletRow object.
-        let update_hdr = self.header.update_old(&arranged_terms);
+        // 3a. Rebuild the HeaderDupletRow object from the arranged terms.
+        let update_hdr = self.rebuild_header(&arranged_terms)?;
         let updated_hdr_arc = Arc::new(update_hdr);
         // 3b. Update the existing PpktRow objects
         let mut updated_ppkt_rows: Vec<PpktRow> = Vec::new();
@@ -361,32 +1302,247 @@ impl PheToolsTemplate {
         verrs.ok()
     }
 
-     /// get the total number of rows (which is 2 for the header plus the number of phenopacket rows)
-    pub fn n_rows(&self) -> usize {
-        2 + self.ppkt_rows.len()
-    }
-
-    pub fn n_columns(&self) -> usize {
+    /// Set the HPO cells of an existing row from a list of HpoTermDto objects (e.g., the full phenotype profile of one case).
+    /// HPO terms that are not yet in the template are added to the header, the HPO columns are rearranged,
+    /// and the other rows are given the value "na" for the new terms. Cells of the row that are
+    /// not mentioned in the DTO list keep their current value.
+    ///
+    /// # Arguments
+    ///
+    /// * `row` - index of the phenopacket row (not counting the two header rows)
+    /// * `hpo_dto_items` - HPO terms with their values (observed, excluded, na, or an age of onset)
+    pub fn apply_hpo_profile(
+        &mut self,
+        row: usize,
+        hpo_dto_items: Vec<HpoTermDto>
+    ) -> std::result::Result<(), ValidationErrors> {
+        if row >= self.ppkt_rows.len() {
+            return Err(ValidationErrors::from_one_err(
+                format!("Attempt to set HPO data for row {row} but there are only {} rows", self.ppkt_rows.len())));
+        }
+        let mut verrs = ValidationErrors::new();
+        for dto in &hpo_dto_items {
+            verrs.push_result(age_util::check_hpo_table_cell(dto.entry())
+                .map_err(|e| format!("{} ({}): {}", dto.label(), dto.term_id(), e)));
+        }
+        let hpo_util = HpoUtil::new(self.hpo.clone());
+        let hpo_duplets = hpo_util.hpo_duplets_from_dto(&hpo_dto_items)?;
+        if verrs.has_error() {
+            return Err(verrs);
+        }
+        // === STEP 1: Merge the TermIds of the DTOs with the existing terms and arrange them ===
+        let mut tid_to_value_map: HashMap<TermId, String> = HashMap::new();
+        for (duplet, dto) in hpo_duplets.iter().zip(hpo_dto_items.iter()) {
+            let tid = duplet.to_term_id().map_err(ValidationErrors::from_one_err)?;
+            tid_to_value_map.insert(tid, dto.entry().to_string());
+        }
+        let mut term_id_set: HashSet<TermId> = tid_to_value_map.keys().cloned().collect();
+        term_id_set.extend(self.header.get_hpo_id_list()?);
+        let all_tids: Vec<TermId> = term_id_set.into_iter().collect();
+        let mut term_arrager = HpoTermArranger::new(self.hpo.clone());
+        let arranged_terms = term_arrager.arrange_terms(&all_tids)?;
+        // === STEP 2: Rebuild the header and remap the existing rows to the new columns ===
+        let updated_hdr_arc = Arc::new(self.rebuild_header(&arranged_terms)?);
+        let mut updated_ppkt_rows: Vec<PpktRow> = Vec::with_capacity(self.ppkt_rows.len());
+        for ppkt in &self.ppkt_rows {
+            match ppkt.update_header(updated_hdr_arc.clone()) {
+                Ok(updated_ppkt) => updated_ppkt_rows.push(updated_ppkt),
+                Err(e) => verrs.add_errors(e.errors()),
+            }
+        }
+        if verrs.has_error() {
+            return Err(verrs);
+        }
+        // === STEP 3: Set the values of the selected row ===
+        updated_ppkt_rows[row] = updated_ppkt_rows[row].update_hpo_values(&tid_to_value_map)?;
+        self.header = updated_hdr_arc;
+        self.ppkt_rows = updated_ppkt_rows;
+        Ok(())
+    }
+
+    /// Create a new HeaderDupletRow with the constant fields of the current header and the (arranged) HPO terms
+    fn rebuild_header(&self, arranged_terms: &[SimpleTerm]) -> std::result::Result<HeaderDupletRow, ValidationErrors> {
+        let hpo_terms: Vec<(TermId, String)> = arranged_terms
+            .iter()
+            .map(|term| (term.identifier().clone(), term.name().to_string()))
+            .collect();
+        HeaderDupletRow::rebuild_from_terms(&self.header.get_fixed_duplets(), &hpo_terms, self.template_type)
+            .map(|header| header
+                .with_ancestry(self.header.has_ancestry())
+                .with_vital_status(self.header.has_vital_status())
+                .with_karyotypic_sex(self.header.has_karyotypic_sex())
+                .with_trailing_metadata(self.header.trailing_metadata().to_vec()))
+    }
+
+     /// get the total number of rows (which is 2 for the header plus the number of phenopacket rows)
+    pub fn n_rows(&self) -> usize {
+        2 + self.ppkt_rows.len()
+    }
+
+    pub fn n_columns(&self) -> usize {
         self.header.n_columns()
     }
 
 
+    /// Create the exporter used by all export functions, with the OntologyClass messages of the HPO columns cached
+    fn build_exporter(&self, settings: &ExportSettings) -> std::result::Result<PpktExporter, String> {
+        let mut ppkt_exporter = PpktExporter::from_settings(self.hpo.version(), settings);
+        ppkt_exporter.cache_hpo_terms(self.header.hpo_duplets())
+            .map_err(|e| format!("Could not extract phenopacket: {}", e))?;
+        Ok(ppkt_exporter)
+    }
+
     pub fn extract_phenopackets(
         &self,
         hgvs_dict: &HashMap<String, HgvsVariant>,
-        structural_dict: &HashMap<String, StructuralVariant>) 
+        structural_dict: &HashMap<String, StructuralVariant>,
+        settings: &ExportSettings) 
     -> std::result::Result<Vec<Phenopacket>, String> {
-        let mut ppkt_list: Vec<Phenopacket> = Vec::new();
-        let hpo_version = self.hpo.version();
-        let creator_orcid = "TEMP_ORCID";
-        let ppkt_exporter = PpktExporter::new(hpo_version, creator_orcid);
-        for row in &self.ppkt_rows {
-            match ppkt_exporter.extract_phenopacket(row,  hgvs_dict,
-                structural_dict) {
-                    Ok(ppkt) =>  { ppkt_list.push(ppkt); },
-                    Err(e) => { return Err(format!("Could not extract phenopacket: {}", e));},
-                }
+        self.iter_phenopackets(hgvs_dict, structural_dict, settings)?
+            .collect()
+    }
+
+    /// Lazily export the rows of the template, yielding one phenopacket (or the reason why the row could not be
+    /// exported) per row in template order. The phenopackets are the same as those returned by
+    /// [`Self::extract_phenopackets`], but only one is held in memory at a time.
+    pub fn iter_phenopackets<'a>(
+        &'a self,
+        hgvs_dict: &'a HashMap<String, HgvsVariant>,
+        structural_dict: &'a HashMap<String, StructuralVariant>,
+        settings: &ExportSettings) 
+    -> std::result::Result<impl Iterator<Item = std::result::Result<Phenopacket, String>> + 'a, String> {
+        let ppkt_exporter = self.build_exporter(settings)?;
+        let ppkt_ids = PpktExporter::get_unique_phenopacket_ids(&self.ppkt_rows);
+        Ok(self.iter_ppkt_rows().zip(ppkt_ids).map(move |(row, ppkt_id)| {
+            let mut ppkt = ppkt_exporter.extract_phenopacket(row, hgvs_dict, structural_dict)
+                .map_err(|e| format!("Could not extract phenopacket: {}", e))?;
+            ppkt.id = ppkt_id;
+            Ok(ppkt)
+        }))
+    }
+
+    /// Write the phenopackets of all rows as newline-delimited JSON (one phenopacket per line) to `writer`, e.g., to
+    /// pipe a cohort into downstream tools. Rows that cannot be exported are skipped; the returned list contains one
+    /// error message per skipped row. Err is returned only if the export could not be set up or writing failed.
+    pub fn write_phenopackets_ndjson<W: std::io::Write>(
+        &self,
+        writer: &mut W,
+        hgvs_dict: &HashMap<String, HgvsVariant>,
+        structural_dict: &HashMap<String, StructuralVariant>,
+        settings: &ExportSettings) 
+    -> std::result::Result<Vec<String>, String> {
+        let mut errors: Vec<String> = Vec::new();
+        let ppkts = self.iter_phenopackets(hgvs_dict, structural_dict, settings)?;
+        for (i, (row, ppkt)) in self.iter_ppkt_rows().zip(ppkts).enumerate() {
+            match ppkt {
+                Ok(ppkt) => {
+                    let line = serde_json::to_string(&ppkt).map_err(|e| e.to_string())?;
+                    writeln!(writer, "{line}").map_err(|e| format!("Could not write phenopacket: {}", e))?;
+                },
+                Err(e) => {
+                    errors.push(format!("Row {} ({}): {}", i, row.get_individual_dto().individual_id, e));
+                },
+            }
+        }
+        Ok(errors)
+    }
+
+    /// Check that no two rows yield the same phenopacket identifier. The export functions make colliding identifiers
+    /// unique with a numeric suffix; callers that prefer to fix the individual identifiers can call this check first.
+    pub fn check_unique_phenopacket_ids(&self) -> std::result::Result<(), String> {
+        let duplicates = PpktExporter::find_duplicate_phenopacket_ids(&self.ppkt_rows);
+        if duplicates.is_empty() {
+            return Ok(());
         }
+        let groups: Vec<String> = duplicates.iter()
+            .map(|(ppkt_id, rows)| format!("{} (rows {})", ppkt_id, 
+                rows.iter().map(|r| r.to_string()).collect::<Vec<_>>().join(", ")))
+            .collect();
+        Err(format!("Duplicate phenopacket identifiers: {}", groups.join("; ")))
+    }
+
+    /// Bundle the phenopackets of all rows into a GA4GH Cohort message. The cohort has a single MetaData element
+    /// with the shared resources and the PMIDs of all rows; the resources and external references of the member
+    /// phenopackets (e.g., ancestry) are merged into it, and the MetaData of the members is omitted to avoid duplication.
+    pub fn extract_cohort(
+        &self,
+        cohort_id: &str,
+        description: &str,
+        hgvs_dict: &HashMap<String, HgvsVariant>,
+        structural_dict: &HashMap<String, StructuralVariant>,
+        settings: &ExportSettings) 
+    -> std::result::Result<Cohort, String> {
+        let ppkt_exporter = self.build_exporter(settings)?;
+        let mut meta_data = ppkt_exporter.get_cohort_meta_data(&self.ppkt_rows);
+        let mut members: Vec<Phenopacket> = Vec::with_capacity(self.ppkt_rows.len());
+        let ppkt_ids = PpktExporter::get_unique_phenopacket_ids(&self.ppkt_rows);
+        for (row, ppkt_id) in self.ppkt_rows.iter().zip(ppkt_ids) {
+            let mut ppkt = ppkt_exporter.extract_phenopacket(row, hgvs_dict, structural_dict)
+                .map_err(|e| format!("Could not extract phenopacket: {}", e))?;
+            ppkt.id = ppkt_id;
+            if let Some(member_meta_data) = ppkt.meta_data.take() {
+                PpktExporter::merge_meta_data(&mut meta_data, member_meta_data);
+            }
+            members.push(ppkt);
+        }
+        Ok(Cohort {
+            id: cohort_id.to_string(),
+            description: description.to_string(),
+            members,
+            files: vec![],
+            meta_data: Some(meta_data),
+        })
+    }
+
+    /// Extract the phenopacket of a single row (zero-based index of the individual), e.g., for a preview.
+    /// The identifier is the same as in the export of the whole cohort (see [`PpktExporter::get_unique_phenopacket_ids`]).
+    pub fn extract_phenopacket(
+        &self,
+        row: usize,
+        hgvs_dict: &HashMap<String, HgvsVariant>,
+        structural_dict: &HashMap<String, StructuralVariant>,
+        settings: &ExportSettings) 
+    -> std::result::Result<Phenopacket, String> {
+        let ppkt_row = self.ppkt_rows.get(row)
+            .ok_or_else(|| format!("Row index {row} out of bounds (template has {} rows)", self.ppkt_rows.len()))?;
+        let ppkt_exporter = self.build_exporter(settings)?;
+        let mut ppkt = ppkt_exporter.extract_phenopacket(ppkt_row, hgvs_dict, structural_dict)
+            .map_err(|e| format!("Could not extract phenopacket: {}", e))?;
+        ppkt.id = PpktExporter::get_unique_phenopacket_ids(&self.ppkt_rows).swap_remove(row);
+        Ok(ppkt)
+    }
+
+    /// Extract the phenopackets of the cohort concurrently. Errors are collected for all rows rather
+    /// than stopping at the first error. Phenopackets are returned sorted by their identifier.
+    #[cfg(feature = "rayon")]
+    pub fn extract_phenopackets_parallel(
+        &self,
+        hgvs_dict: &HashMap<String, HgvsVariant>,
+        structural_dict: &HashMap<String, StructuralVariant>,
+        settings: &ExportSettings) 
+    -> std::result::Result<Vec<Phenopacket>, Vec<String>> {
+        use rayon::prelude::*;
+        let ppkt_exporter = self.build_exporter(settings).map_err(|e| vec![e])?;
+        let ppkt_ids = PpktExporter::get_unique_phenopacket_ids(&self.ppkt_rows);
+        let results: Vec<std::result::Result<Phenopacket, String>> = self.ppkt_rows
+            .par_iter()
+            .zip(ppkt_ids.par_iter())
+            .map(|(row, ppkt_id)| ppkt_exporter.extract_phenopacket(row, hgvs_dict, structural_dict)
+                .map(|ppkt| Phenopacket { id: ppkt_id.clone(), ..ppkt })
+                .map_err(|e| format!("Could not extract phenopacket: {}", e)))
+            .collect();
+        let mut ppkt_list: Vec<Phenopacket> = Vec::with_capacity(results.len());
+        let mut errors: Vec<String> = Vec::new();
+        for res in results {
+            match res {
+                Ok(ppkt) => ppkt_list.push(ppkt),
+                Err(e) => errors.push(e),
+            }
+        }
+        if !errors.is_empty() {
+            return Err(errors);
+        }
+        ppkt_list.sort_by(|a, b| a.id.cmp(&b.id));
         Ok(ppkt_list)
     }
 
@@ -418,8 +1574,8 @@ impl PheToolsTemplate {
         // strategy: Make a HashMap with all of the new terms, initialize the values to na. Clone this, pass it to the
         // PpktRow object, and update the map with the current values. The remaining (new) terms will be "na". Then use
         // the new HeaderDupletRow object to write the values.
-        // 3a. Update the HeaderDupletRow object.
-        let update_hdr = self.header.update_old(&arranged_terms);
+        // 3a. Rebuild the HeaderDupletRow object from the arranged terms.
+        let update_hdr = self.rebuild_header(&arranged_terms)?;
         let updated_hdr_arc = Arc::new(update_hdr);
         let mut updated_ppkt_rows: Vec<PpktRow> = Vec::new();
         for ppkt in &self.ppkt_rows {
@@ -439,6 +1595,33 @@ impl PheToolsTemplate {
             Ok(())
         }
     }
+
+    /// Add a row created from a GA4GH phenopacket (the inverse of [`Self::extract_phenopacket`]). HPO terms of the
+    /// phenopacket that are not yet in the template are added as new columns, with the value na for the other rows.
+    /// If the phenopacket cannot be imported, the template is left unchanged.
+    pub fn import_phenopacket(&mut self, ppkt: &Phenopacket) -> std::result::Result<(), ValidationErrors> {
+        let header = self.header.clone();
+        let ppkt_rows = self.ppkt_rows.clone();
+        let result = self.add_imported_row(ppkt);
+        if result.is_err() {
+            self.header = header;
+            self.ppkt_rows = ppkt_rows;
+        }
+        result
+    }
+
+    fn add_imported_row(&mut self, ppkt: &Phenopacket) -> std::result::Result<(), ValidationErrors> {
+        let importer = PpktImporter::from_phenopacket(ppkt)
+            .map_err(|e| ValidationErrors::from_one_err(e.to_string()))?;
+        for duplet in importer.missing_hpo_terms(&self.header) {
+            self.add_hpo_term_to_cohort(duplet.hpo_id(), duplet.hpo_label())?;
+        }
+        let ppkt_row = importer.to_ppkt_row(self.header.clone())
+            .map_err(|e| ValidationErrors::from_one_err(e.to_string()))?;
+        ppkt_row.check_for_errors()?;
+        self.ppkt_rows.push(ppkt_row);
+        self.check_row_structure()
+    }
 }
 
 
@@ -449,8 +1632,11 @@ mod test {
     use ontolius::{io::OntologyLoaderBuilder, ontology::csr::MinimalCsrOntology};
     use polars::io::SerReader;
     use super::*;
+    use crate::{dto::validation_errors::Severity, variant::vcf_var::VcfVar};
     use std::{fs::File, io::BufReader};
     use rstest::{fixture, rstest};
+    use phenopackets::schema::v2::core::genomic_interpretation::Call;
+    use phenopackets::schema::v2::core::OntologyClass;
     use flate2::bufread::GzDecoder;
 
     #[fixture]
@@ -524,6 +1710,23 @@ mod test {
         rows
     }
 
+    /// The original matrix with a second individual that has the same data as the first one
+    #[fixture]
+    fn two_individual_matrix(mut original_matrix: Vec<Vec<String>>, row3: Vec<String>) -> Vec<Vec<String>> {
+        let mut row4 = row3.clone();
+        row4[2] = "individual 2".to_string();
+        original_matrix.push(row4);
+        original_matrix
+    }
+
+    /// Validated HGVS variant for the ACVR1 allele c.617G>A of the test rows
+    #[fixture]
+    fn acvr1_hgvs_dict() -> HashMap<String, HgvsVariant> {
+        let hgvs = HgvsVariant::new("hg38".to_string(), VcfVar::new("chr2", 157774114, "C", "T"), 
+            Some("ACVR1".to_string()), Some("HGNC:171".to_string()), None, None, None, None, None);
+        HashMap::from([("c.617G>A".to_string(), hgvs)])
+    }
+
     /// Make sure that our test matrix is valid before we start changing fields to check if we pick up errors
     #[rstest]
     fn test_factory_valid_input(
@@ -534,6 +1737,1084 @@ mod test {
     }
 
 
+    #[rstest]
+    fn test_get_variant_dto_list(
+        mut two_individual_matrix: Vec<Vec<String>>,
+        hpo: Arc<FullCsrOntology>) {
+        two_individual_matrix[3][10] = "DEL: deletion of exon 5".to_string();
+        let template = PheToolsTemplate::from_mendelian_template(two_individual_matrix, hpo, false).unwrap();
+        let dto_list = template.get_variant_dto_list();
+        assert_eq!(2, dto_list.len());
+        assert_eq!("c.617G>A", dto_list[0].variant_string());
+        assert!(!dto_list[0].is_structural());
+        assert_eq!("HGNC:171", dto_list[0].hgnc_id());
+        assert_eq!("DEL: deletion of exon 5", dto_list[1].variant_string());
+        assert!(dto_list[1].is_structural());
+    }
+
+    #[rstest]
+    fn test_check_row_structure(
+        original_matrix: Vec<Vec<String>>, 
+        hpo: Arc<FullCsrOntology>) {
+        let template = PheToolsTemplate::from_mendelian_template(original_matrix.clone(), hpo.clone(), false).unwrap();
+        assert!(template.check_row_structure().is_ok());
+        // a Mendelian row (one disease, one gene/variant bundle) in a Melded template
+        let template = template_from_parts(original_matrix, hpo.clone(), 1);
+        let ppkt_rows: Vec<PpktRow> = template.ppkt_rows.clone();
+        let template = PheToolsTemplate::from_parts(template.header.clone(), ppkt_rows, TemplateType::Melded, hpo);
+        let errors = template.check_row_structure().unwrap_err().errors();
+        assert_eq!(vec![
+            "Row 0 (individual 0): expected 2 disease(s) for Melded template but found 1".to_string(),
+            "Row 0 (individual 0): expected 2 gene/variant bundle(s) for Melded template but found 1".to_string()
+        ], errors);
+    }
+
+    #[rstest]
+    fn test_get_onset_distribution(
+        original_matrix: Vec<Vec<String>>, 
+        hpo: Arc<FullCsrOntology>) {
+        let template = PheToolsTemplate::from_mendelian_template(original_matrix, hpo, false).unwrap();
+        let onset_counts = template.get_onset_distribution();
+        assert_eq!(1, onset_counts.len());
+        assert_eq!(Some(&1), onset_counts.get("Juvenile onset"));
+    }
+
+    #[rstest]
+    fn test_known_genes_allowlist(
+        disease_gene_dto: DiseaseGeneDto, 
+        hpo: Arc<FullCsrOntology>) {
+        let hpo_ids = vec![TermId::from_str("HP:0001822").unwrap()];
+        let acvr1 = GeneTranscriptDto { 
+            hgnc_id: "HGNC:171".to_string(), 
+            gene_symbol: "ACVR1".to_string(), 
+            transcript: "NM_001111067.4".to_string() 
+        };
+        let result = PheToolsTemplate::create_pyphetools_template(
+            disease_gene_dto.clone(), hpo_ids.clone(), hpo.clone(), Some(&[acvr1.clone()]));
+        assert!(result.is_ok());
+        let other_tx = GeneTranscriptDto { transcript: "NM_001105.5".to_string(), ..acvr1 };
+        let result = PheToolsTemplate::create_pyphetools_template(
+            disease_gene_dto, hpo_ids, hpo, Some(&[other_tx]));
+        assert!(matches!(result, Err(Error::TemplateError { .. })));
+    }
+
+    #[rstest]
+    fn test_get_validation_report(
+        original_matrix: Vec<Vec<String>>, 
+        hpo: Arc<FullCsrOntology>) {
+        let template = PheToolsTemplate::from_mendelian_template(original_matrix, hpo, false).unwrap();
+        let report = template.get_validation_report();
+        // No errors, but some HPO columns (e.g., Clinodactyly of the 5th finger) have no data
+        assert!(!report.contains("Errors"));
+        assert!(report.contains("Warnings"));
+        assert!(report.contains("Clinodactyly of the 5th finger (HP:0004209): no data in any row"));
+    }
+
+    #[rstest]
+    fn test_find_and_merge_duplicate_alleles(
+        mut two_individual_matrix: Vec<Vec<String>>,
+        acvr1_hgvs_dict: HashMap<String, HgvsVariant>,
+        hpo: Arc<FullCsrOntology>) {
+        two_individual_matrix[3][9] = "c.617_617delinsA".to_string();
+        let mut template = PheToolsTemplate::from_mendelian_template(two_individual_matrix, hpo, false).unwrap();
+        let mut hgvs_dict = acvr1_hgvs_dict;
+        hgvs_dict.insert("c.617_617delinsA".to_string(), hgvs_dict["c.617G>A"].clone());
+        let groups = template.find_duplicate_alleles(&hgvs_dict);
+        assert_eq!(1, groups.len());
+        assert_eq!("c.617G>A", groups[0].canonical);
+        assert_eq!(vec!["c.617_617delinsA".to_string()], groups[0].duplicates);
+        assert_eq!(1, template.merge_duplicate_alleles(&groups));
+        assert!(template.find_duplicate_alleles(&hgvs_dict).is_empty());
+        let dto = template.get_template_dto().unwrap();
+        assert_eq!("c.617G>A", dto.rows[1].gene_var_dto_list[0].allele1);
+    }
+
+    #[rstest]
+    fn test_stale_transcript_blocks_export(
+        original_matrix: Vec<Vec<String>>, 
+        hpo: Arc<FullCsrOntology>) {
+        let template = PheToolsTemplate::from_mendelian_template(original_matrix, hpo, false).unwrap();
+        let validated_with = |tx: &str| HgvsVariant::new("hg38".to_string(), VcfVar::new("chr2", 157774114, "C", "T"), 
+            Some("ACVR1".to_string()), Some("HGNC:171".to_string()), None, Some(format!("{tx}:c.617G>A")), None, None, None);
+        let mut hgvs_dict: HashMap<String, HgvsVariant> = HashMap::new();
+        hgvs_dict.insert("c.617G>A".to_string(), validated_with("NM_001111067.4"));
+        assert!(template.check_validated_transcripts(&hgvs_dict).is_ok());
+        hgvs_dict.insert("c.617G>A".to_string(), validated_with("NM_001105.5"));
+        let stale = template.find_stale_transcript_alleles(&hgvs_dict);
+        assert_eq!(vec!["c.617G>A (validated against NM_001105.5, template uses NM_001111067.4)".to_string()], stale);
+        let err = template.check_validated_transcripts(&hgvs_dict).unwrap_err();
+        assert!(err.starts_with("Re-validate against new transcript"));
+    }
+
+    #[rstest]
+    fn test_find_unvalidated_alleles(
+        mut two_individual_matrix: Vec<Vec<String>>,
+        acvr1_hgvs_dict: HashMap<String, HgvsVariant>,
+        hpo: Arc<FullCsrOntology>) {
+        two_individual_matrix[3][10] = "DEL: deletion of exon 5".to_string();
+        let template = PheToolsTemplate::from_mendelian_template(two_individual_matrix, hpo, false).unwrap();
+        let structural_dict: HashMap<String, StructuralVariant> = HashMap::new();
+        assert_eq!(vec![(0, "c.617G>A".to_string()), (1, "c.617G>A".to_string()), (1, "DEL: deletion of exon 5".to_string())], 
+            template.find_unvalidated_alleles(&HashMap::new(), &structural_dict));
+        assert_eq!(vec![(1, "DEL: deletion of exon 5".to_string())], 
+            template.find_unvalidated_alleles(&acvr1_hgvs_dict, &structural_dict));
+    }
+
+    #[rstest]
+    fn test_write_phenopackets_ndjson(
+        mut two_individual_matrix: Vec<Vec<String>>,
+        acvr1_hgvs_dict: HashMap<String, HgvsVariant>,
+        hpo: Arc<FullCsrOntology>) {
+        two_individual_matrix[3][9] = "c.999A>G".to_string();
+        let template = PheToolsTemplate::from_mendelian_template(two_individual_matrix, hpo, false).unwrap();
+        let mut buffer: Vec<u8> = Vec::new();
+        let errors = template.write_phenopackets_ndjson(&mut buffer, &acvr1_hgvs_dict, &HashMap::new(), &ExportSettings::new("0000-0000-0000-0000")).unwrap();
+        assert_eq!(1, errors.len());
+        assert!(errors[0].contains("individual 2"));
+        let output = String::from_utf8(buffer).unwrap();
+        assert_eq!(template.phenopacket_count() - errors.len(), output.lines().count());
+        for line in output.lines() {
+            assert!(serde_json::from_str::<serde_json::Value>(line).is_ok());
+        }
+    }
+
+    #[rstest]
+    fn test_iter_phenopackets(
+        two_individual_matrix: Vec<Vec<String>>,
+        acvr1_hgvs_dict: HashMap<String, HgvsVariant>,
+        hpo: Arc<FullCsrOntology>) {
+        let template = PheToolsTemplate::from_mendelian_template(two_individual_matrix, hpo, false).unwrap();
+        assert_eq!(template.phenopacket_count(), template.iter_ppkt_rows().len());
+        let individual_ids: Vec<String> = template.iter_ppkt_rows()
+            .map(|row| row.get_individual_dto().individual_id)
+            .collect();
+        assert_eq!(vec!["current case".to_string(), "individual 2".to_string()], individual_ids);
+        let batch = template.extract_phenopackets(&acvr1_hgvs_dict, &HashMap::new(), &ExportSettings::new("0000-0000-0000-0000")).unwrap();
+        let streamed: Vec<Phenopacket> = template.iter_phenopackets(&acvr1_hgvs_dict, &HashMap::new(), &ExportSettings::new("0000-0000-0000-0000"))
+            .unwrap()
+            .map(|ppkt| ppkt.unwrap())
+            .collect();
+        assert_eq!(batch.len(), streamed.len());
+        for (a, b) in batch.iter().zip(streamed.iter()) {
+            assert_eq!(a.id, b.id);
+            assert_eq!(a.subject, b.subject);
+            assert_eq!(a.phenotypic_features, b.phenotypic_features);
+        }
+    }
+
+    /// The parallel export yields the same phenopackets as the sequential export (sorted by identifier)
+    #[cfg(feature = "rayon")]
+    #[rstest]
+    fn test_extract_phenopackets_parallel(
+        mut two_individual_matrix: Vec<Vec<String>>,
+        mut row3: Vec<String>,
+        acvr1_hgvs_dict: HashMap<String, HgvsVariant>,
+        hpo: Arc<FullCsrOntology>) {
+        row3[2] = "another case".to_string();
+        two_individual_matrix.push(row3);
+        let template = PheToolsTemplate::from_mendelian_template(two_individual_matrix, hpo, false).unwrap();
+        let settings = ExportSettings::new("0000-0000-0000-0000");
+        let mut sequential = template.extract_phenopackets(&acvr1_hgvs_dict, &HashMap::new(), &settings).unwrap();
+        sequential.sort_by(|a, b| a.id.cmp(&b.id));
+        let parallel = template.extract_phenopackets_parallel(&acvr1_hgvs_dict, &HashMap::new(), &settings).unwrap();
+        // the creation time of the MetaData differs between the two exports
+        let without_created = |mut ppkt: Phenopacket| {
+            if let Some(meta_data) = ppkt.meta_data.as_mut() {
+                meta_data.created = None;
+            }
+            ppkt
+        };
+        let sequential: Vec<Phenopacket> = sequential.into_iter().map(without_created).collect();
+        let parallel: Vec<Phenopacket> = parallel.into_iter().map(without_created).collect();
+        assert_eq!(3, parallel.len());
+        assert_eq!(sequential, parallel);
+    }
+
+    #[rstest]
+    fn test_orphanet_disease_id(
+        mut original_matrix: Vec<Vec<String>>, 
+        acvr1_hgvs_dict: HashMap<String, HgvsVariant>,
+        hpo: Arc<FullCsrOntology>) {
+        original_matrix[2][4] = "ORPHA:337".to_string();
+        let template = PheToolsTemplate::from_mendelian_template(original_matrix, hpo, false).unwrap();
+        let ppkt = template.extract_phenopacket(0, &acvr1_hgvs_dict, &HashMap::new(), &ExportSettings::new("0000-0000-0000-0000")).unwrap();
+        assert_eq!("ORPHA:337", ppkt.diseases[0].term.as_ref().unwrap().id);
+    }
+
+    #[rstest]
+    fn test_curator_meta_data(
+        original_matrix: Vec<Vec<String>>, 
+        acvr1_hgvs_dict: HashMap<String, HgvsVariant>,
+        hpo: Arc<FullCsrOntology>) {
+        let template = PheToolsTemplate::from_mendelian_template(original_matrix, hpo, false).unwrap();
+        let settings = ExportSettings::new("0000-0002-0736-9199")
+            .with_creator_name(Some("Jane Curator".to_string()));
+        let ppkt_list = template.extract_phenopackets(&acvr1_hgvs_dict, &HashMap::new(), &settings).unwrap();
+        let meta_data = ppkt_list[0].meta_data.as_ref().unwrap();
+        assert_eq!("Jane Curator", meta_data.created_by);
+        assert_eq!("ORCID:0000-0002-0736-9199", meta_data.submitted_by);
+        let settings = ExportSettings::new("0000-0002-0736-9199");
+        let ppkt = template.extract_phenopacket(0, &acvr1_hgvs_dict, &HashMap::new(), &settings).unwrap();
+        assert_eq!("ORCID:0000-0002-0736-9199", ppkt.meta_data.unwrap().created_by);
+    }
+
+    /// The source template is recorded as an external reference in the MetaData of each phenopacket
+    #[rstest]
+    fn test_template_provenance_meta_data(
+        original_matrix: Vec<Vec<String>>, 
+        acvr1_hgvs_dict: HashMap<String, HgvsVariant>,
+        hpo: Arc<FullCsrOntology>) {
+        let template = PheToolsTemplate::from_mendelian_template(original_matrix, hpo, false).unwrap();
+        let provenance = TemplateProvenance::new("/data/FOP/FOP_individuals.xlsx", "2025-06-01T10:00:00+00:00");
+        let settings = ExportSettings::new("0000-0002-0736-9199").with_provenance(Some(provenance));
+        let ppkt_list = template.extract_phenopackets(&acvr1_hgvs_dict, &HashMap::new(), &settings).unwrap();
+        let meta_data = ppkt_list[0].meta_data.as_ref().unwrap();
+        let source = meta_data.external_references.iter()
+            .find(|er| er.id == "FOP_individuals.xlsx")
+            .unwrap();
+        assert_eq!("/data/FOP/FOP_individuals.xlsx", source.reference);
+        assert_eq!("Source template loaded at 2025-06-01T10:00:00+00:00", source.description);
+        // without provenance, only the PMID is referenced
+        let settings = ExportSettings::new("0000-0002-0736-9199");
+        let ppkt = template.extract_phenopacket(0, &acvr1_hgvs_dict, &HashMap::new(), &settings).unwrap();
+        let reference_ids: Vec<String> = ppkt.meta_data.unwrap().external_references.into_iter().map(|er| er.id).collect();
+        assert_eq!(vec!["PMID:29482508".to_string()], reference_ids);
+    }
+
+    /// The external references of the members (e.g., ancestry) are merged into the MetaData of the cohort
+    #[rstest]
+    fn test_extract_cohort_meta_data(
+        mut two_individual_matrix: Vec<Vec<String>>,
+        acvr1_hgvs_dict: HashMap<String, HgvsVariant>,
+        hpo: Arc<FullCsrOntology>) {
+        add_ancestry_column(&mut two_individual_matrix, "European");
+        let template = PheToolsTemplate::from_mendelian_template(two_individual_matrix, hpo, false).unwrap();
+        let settings = ExportSettings::new("0000-0002-0736-9199");
+        let cohort = template.extract_cohort("FOP", "FOP cohort", &acvr1_hgvs_dict, &HashMap::new(), &settings).unwrap();
+        assert_eq!(2, cohort.members.len());
+        assert!(cohort.members.iter().all(|ppkt| ppkt.meta_data.is_none()));
+        let meta_data = cohort.meta_data.unwrap();
+        assert_eq!("ORCID:0000-0002-0736-9199", meta_data.created_by);
+        let reference_ids: Vec<&str> = meta_data.external_references.iter().map(|er| er.id.as_str()).collect();
+        assert_eq!(vec!["PMID:29482508", "HANCESTRO:0005"], reference_ids);
+    }
+
+    #[rstest]
+    fn test_duplicate_phenopacket_ids(
+        mut two_individual_matrix: Vec<Vec<String>>,
+        acvr1_hgvs_dict: HashMap<String, HgvsVariant>,
+        hpo: Arc<FullCsrOntology>) {
+        two_individual_matrix[2][2] = "Individual 1".to_string();
+        two_individual_matrix[3][2] = "Individual_1".to_string();
+        let template = PheToolsTemplate::from_mendelian_template(two_individual_matrix, hpo, false).unwrap();
+        let err = template.check_unique_phenopacket_ids().unwrap_err();
+        assert_eq!("Duplicate phenopacket identifiers: PMID_29482508_Individual_1 (rows 0, 1)", err);
+        let ppkt_list = template.extract_phenopackets(&acvr1_hgvs_dict, &HashMap::new(), &ExportSettings::new("0000-0000-0000-0000")).unwrap();
+        let ids: Vec<&str> = ppkt_list.iter().map(|ppkt| ppkt.id.as_str()).collect();
+        assert_eq!(vec!["PMID_29482508_Individual_1", "PMID_29482508_Individual_1_2"], ids);
+        // the preview of a single row has the same identifier as in the export of the cohort
+        let ppkt = template.extract_phenopacket(1, &acvr1_hgvs_dict, &HashMap::new(), &ExportSettings::new("0000-0000-0000-0000")).unwrap();
+        assert_eq!("PMID_29482508_Individual_1_2", ppkt.id);
+    }
+
+    #[rstest]
+    fn test_structural_variant_exported(
+        mut original_matrix: Vec<Vec<String>>, 
+        hpo: Arc<FullCsrOntology>) {
+        let allele = "DEL: deletion exon 5";
+        original_matrix[2][9] = allele.to_string();
+        let template = PheToolsTemplate::from_mendelian_template(original_matrix, hpo, false).unwrap();
+        let sv = StructuralVariant::chromosomal_deletion(allele, "ACVR1", "HGNC:171", None).unwrap();
+        let structural_dict: HashMap<String, StructuralVariant> = HashMap::from([(allele.to_string(), sv.clone())]);
+        let ppkt = template.extract_phenopacket(0, &HashMap::new(), &structural_dict, &ExportSettings::new("0000-0000-0000-0000")).unwrap();
+        let diagnosis = ppkt.interpretations[0].diagnosis.as_ref().unwrap();
+        assert_eq!(1, diagnosis.genomic_interpretations.len());
+        let vdesc = match diagnosis.genomic_interpretations[0].call.as_ref().unwrap() {
+            Call::VariantInterpretation(vi) => vi.variation_descriptor.as_ref().unwrap(),
+            _ => panic!("Expected a VariantInterpretation"),
+        };
+        let so_class = vdesc.structural_type.as_ref().unwrap();
+        assert_eq!(sv.so_id(), so_class.id);
+        assert_eq!(sv.so_label(), so_class.label);
+    }
+
+    #[rstest]
+    fn test_import_phenopacket(
+        original_matrix: Vec<Vec<String>>, 
+        acvr1_hgvs_dict: HashMap<String, HgvsVariant>,
+        hpo: Arc<FullCsrOntology>) {
+        let mut template = PheToolsTemplate::from_mendelian_template(original_matrix, hpo, false).unwrap();
+        let mut ppkt = template.extract_phenopacket(0, &acvr1_hgvs_dict, &HashMap::new(), &ExportSettings::new("0000-0000-0000-0000")).unwrap();
+        ppkt.subject.as_mut().unwrap().id = "imported case".to_string();
+        let mut seizure = ppkt.phenotypic_features[0].clone();
+        seizure.r#type = Some(OntologyClass { id: "HP:0001250".to_string(), label: "Seizure".to_string() });
+        seizure.onset = None;
+        ppkt.phenotypic_features.push(seizure);
+        template.import_phenopacket(&ppkt).unwrap();
+        assert_eq!(2, template.phenopacket_count());
+        let dtos: Vec<RowDto> = template.row_dtos().collect();
+        let (original, imported) = (&dtos[0], &dtos[1]);
+        assert_eq!("imported case", imported.individual_dto.individual_id);
+        assert_eq!(original.individual_dto.pmid, imported.individual_dto.pmid);
+        assert_eq!(original.individual_dto.title, imported.individual_dto.title);
+        assert_eq!(original.individual_dto.age_of_onset, imported.individual_dto.age_of_onset);
+        assert_eq!(original.individual_dto.age_at_last_encounter, imported.individual_dto.age_at_last_encounter);
+        assert_eq!(original.individual_dto.sex, imported.individual_dto.sex);
+        assert_eq!(original.disease_dto_list, imported.disease_dto_list);
+        let (gv_original, gv_imported) = (&original.gene_var_dto_list[0], &imported.gene_var_dto_list[0]);
+        assert_eq!((&gv_original.transcript, &gv_original.allele1), (&gv_imported.transcript, &gv_imported.allele1));
+        assert_eq!("na", gv_imported.allele2);
+        // the new Seizure column is observed in the imported row and na in the original row
+        let seizure_idx = template.header.hpo_duplets().iter().position(|d| d.hpo_id() == "HP:0001250").unwrap();
+        assert_eq!("na", original.hpo_data[seizure_idx].value);
+        assert_eq!("observed", imported.hpo_data[seizure_idx].value);
+        for (i, (a, b)) in original.hpo_data.iter().zip(imported.hpo_data.iter()).enumerate() {
+            if i != seizure_idx {
+                assert_eq!(a.value, b.value);
+            }
+        }
+        // a phenopacket without a PMID cannot be imported and leaves the template unchanged
+        ppkt.meta_data.as_mut().unwrap().external_references.clear();
+        assert!(template.import_phenopacket(&ppkt).is_err());
+        assert_eq!(2, template.phenopacket_count());
+    }
+
+    #[rstest]
+    fn test_check_hgnc_symbols(
+        mut original_matrix: Vec<Vec<String>>, 
+        hpo: Arc<FullCsrOntology>) {
+        let symbol_map = HgncSymbolMap::embedded();
+        let template = PheToolsTemplate::from_mendelian_template(original_matrix.clone(), hpo.clone(), false).unwrap();
+        assert!(template.check_hgnc_symbols(&symbol_map).is_ok());
+        original_matrix[2][7] = "FBN1".to_string();
+        let template = PheToolsTemplate::from_mendelian_template(original_matrix, hpo, false).unwrap();
+        let errors = template.check_hgnc_symbols(&symbol_map).unwrap_err().errors();
+        assert_eq!(vec!["Row 1: Gene symbol mismatch for HGNC:171: expected 'ACVR1' but found 'FBN1'".to_string()], errors);
+    }
+
+    #[rstest]
+    fn test_unknown_sex_x_linked_warnings(
+        mut original_matrix: Vec<Vec<String>>, 
+        hpo: Arc<FullCsrOntology>) {
+        let hgvs = HgvsVariant::new("hg38".to_string(), VcfVar::new("chrX", 1234567, "C", "T"), 
+            None, None, None, None, None, None, None);
+        let mut hgvs_dict: HashMap<String, HgvsVariant> = HashMap::new();
+        hgvs_dict.insert("c.617G>A".to_string(), hgvs);
+        let template = PheToolsTemplate::from_mendelian_template(original_matrix.clone(), hpo.clone(), false).unwrap();
+        assert!(template.get_unknown_sex_x_linked_warnings(&hgvs_dict).is_empty());
+        original_matrix[2][15] = "U".to_string();
+        let template = PheToolsTemplate::from_mendelian_template(original_matrix, hpo, false).unwrap();
+        let warnings = template.get_unknown_sex_x_linked_warnings(&hgvs_dict);
+        assert_eq!(1, warnings.len());
+        let report = template.get_validation_report_with_warnings(warnings);
+        assert!(report.contains("unknown sex (U) with X-chromosomal variant in ACVR1"));
+    }
+
+    #[rstest]
+    fn test_cross_pmid_conflict_warnings(
+        mut original_matrix: Vec<Vec<String>>, 
+        hpo: Arc<FullCsrOntology>) {
+        let mut row4 = original_matrix[2].clone();
+        row4[0] = "PMID:11111111".to_string();
+        original_matrix.push(row4);
+        let template = PheToolsTemplate::from_mendelian_template(original_matrix.clone(), hpo.clone(), false).unwrap();
+        assert!(template.get_cross_pmid_conflict_warnings().is_empty());
+        original_matrix[3][18] = "excluded".to_string();
+        let template = PheToolsTemplate::from_mendelian_template(original_matrix.clone(), hpo.clone(), false).unwrap();
+        let warnings = template.get_cross_pmid_conflict_warnings();
+        assert_eq!(1, warnings.len());
+        assert!(warnings[0].1.contains("is 'P16Y' in PMID:29482508 but 'excluded' in PMID:11111111"));
+        // an empty cell is not available and does not conflict with excluded
+        original_matrix[2][18] = "".to_string();
+        let template = PheToolsTemplate::from_mendelian_template(original_matrix.clone(), hpo.clone(), false).unwrap();
+        assert!(template.get_cross_pmid_conflict_warnings().is_empty());
+        // different individuals are not compared
+        original_matrix[3][2] = "another case".to_string();
+        let template = PheToolsTemplate::from_mendelian_template(original_matrix, hpo, false).unwrap();
+        assert!(template.get_cross_pmid_conflict_warnings().is_empty());
+    }
+
+    /// Build a template with the given number of copies of row3 (with distinct individual ids) using from_parts
+    fn template_from_parts(
+        original_matrix: Vec<Vec<String>>, 
+        hpo: Arc<FullCsrOntology>,
+        n_rows: usize) -> PheToolsTemplate {
+        let template = PheToolsTemplate::from_mendelian_template(original_matrix, hpo.clone(), false).unwrap();
+        let row_dto = template.row_dtos().next().unwrap();
+        let ppkt_rows: Vec<PpktRow> = (0..n_rows).map(|i| {
+            let mut dto = row_dto.clone();
+            dto.individual_dto.individual_id = format!("individual {i}");
+            PpktRow::from_dto(&dto, template.header.clone())
+        }).collect();
+        PheToolsTemplate::from_parts(template.header.clone(), ppkt_rows, TemplateType::Mendelian, hpo)
+    }
+
+    #[rstest]
+    fn test_delete_row(
+        original_matrix: Vec<Vec<String>>, 
+        hpo: Arc<FullCsrOntology>) {
+        let mut template = template_from_parts(original_matrix, hpo, 3);
+        template.delete_row(1).unwrap();
+        let ids: Vec<String> = template.row_dtos().map(|row| row.individual_dto.individual_id).collect();
+        assert_eq!(vec!["individual 0".to_string(), "individual 2".to_string()], ids);
+        assert!(template.delete_row(2).is_err());
+        assert_eq!(2, template.phenopacket_count());
+    }
+
+    #[rstest]
+    fn test_from_parts_invalid_row(
+        original_matrix: Vec<Vec<String>>, 
+        hpo: Arc<FullCsrOntology>) {
+        let template = template_from_parts(original_matrix, hpo.clone(), 1);
+        let mut dto = template.row_dtos().next().unwrap();
+        dto.individual_dto.sex = "male".to_string();
+        let bad_row = PpktRow::from_dto(&dto, template.header.clone());
+        let template = PheToolsTemplate::from_parts(template.header.clone(), vec![bad_row], TemplateType::Mendelian, hpo);
+        let errors = template.check_for_errors().unwrap_err().errors();
+        assert_eq!(vec!["Malformed sex entry: 'male'".to_string()], errors);
+    }
+
+    #[rstest]
+    fn test_check_ancestor_conflicts(
+        original_matrix: Vec<Vec<String>>, 
+        hpo: Arc<FullCsrOntology>) {
+        let template = PheToolsTemplate::from_mendelian_template(original_matrix, hpo.clone(), false).unwrap();
+        assert!(template.check_ancestor_conflicts().is_ok());
+        let mut dto = template.row_dtos().next().unwrap();
+        dto.hpo_data[2].value = "observed".to_string(); // Short 1st metacarpal
+        dto.hpo_data[6].value = "excluded".to_string(); // Short thumb, an ancestor of Short 1st metacarpal
+        let row = PpktRow::from_dto(&dto, template.header.clone());
+        let template = PheToolsTemplate::from_parts(template.header.clone(), vec![row], TemplateType::Mendelian, hpo);
+        let errors = template.check_for_errors().unwrap_err().errors();
+        assert_eq!(vec!["Row 0 (current case): Short 1st metacarpal (HP:0010034) is observed but its ancestor Short thumb (HP:0009778) is excluded".to_string()], errors);
+    }
+
+    #[rstest]
+    fn test_row_dtos(
+        original_matrix: Vec<Vec<String>>, 
+        hpo: Arc<FullCsrOntology>) {
+        let template = PheToolsTemplate::from_mendelian_template(original_matrix, hpo, false).unwrap();
+        let ids: Vec<String> = template.row_dtos().map(|row| row.individual_dto.individual_id).collect();
+        assert_eq!(vec!["current case".to_string()], ids);
+    }
+
+    fn add_ancestry_column(matrix: &mut Vec<Vec<String>>, value: &str) {
+        matrix[0].push("ancestry".to_string());
+        matrix[1].push("optional".to_string());
+        for row in matrix.iter_mut().skip(2) {
+            row.push(value.to_string());
+        }
+    }
+
+    /// The ancestry column is optional and must survive load -> DTO -> template -> matrix
+    #[rstest]
+    fn test_ancestry_column(
+        mut original_matrix: Vec<Vec<String>>, 
+        hpo: Arc<FullCsrOntology>) {
+        let n_hpo = original_matrix[0].len() - 17;
+        add_ancestry_column(&mut original_matrix, "European");
+        let template = PheToolsTemplate::from_mendelian_template(original_matrix.clone(), hpo.clone(), false).unwrap();
+        assert_eq!(n_hpo, template.header.hpo_count());
+        let dto = template.get_template_dto().unwrap();
+        assert_eq!(Some("European".to_string()), dto.rows[0].individual_dto.ancestry);
+        let template = PheToolsTemplate::from_template_dto(&dto, hpo).unwrap();
+        assert_eq!(original_matrix, template.get_string_matrix());
+    }
+
+    #[rstest]
+    fn test_invalid_ancestry(
+        mut original_matrix: Vec<Vec<String>>, 
+        hpo: Arc<FullCsrOntology>) {
+        add_ancestry_column(&mut original_matrix, "Martian");
+        let result = PheToolsTemplate::from_mendelian_template(original_matrix, hpo, false);
+        assert!(result.is_err());
+        let errors = result.unwrap_err().errors();
+        assert!(errors[0].starts_with("Unrecognized ancestry 'Martian'"));
+    }
+
+    fn add_vital_status_columns(matrix: &mut Vec<Vec<String>>, age_at_death: &str, cause_of_death: (&str, &str)) {
+        let columns = [
+            ["age_at_death", "optional", age_at_death],
+            ["cause_of_death_id", "optional", cause_of_death.0],
+            ["cause_of_death_label", "optional", cause_of_death.1],
+        ];
+        for cells in columns {
+            matrix[0].push(cells[0].to_string());
+            matrix[1].push(cells[1].to_string());
+            for row in matrix.iter_mut().skip(2) {
+                row.push(cells[2].to_string());
+            }
+        }
+    }
+
+    /// The age and cause of death columns are optional and must survive load -> save (JSON) -> reload -> matrix
+    #[rstest]
+    fn test_vital_status_columns_round_trip(
+        mut original_matrix: Vec<Vec<String>>, 
+        hpo: Arc<FullCsrOntology>) {
+        let n_hpo = original_matrix[0].len() - 17;
+        original_matrix[2][14] = "yes".to_string();
+        add_ancestry_column(&mut original_matrix, "European");
+        add_vital_status_columns(&mut original_matrix, "P20Y", ("OMIM:135100", "Fibrodysplasia ossificans progressiva"));
+        let template = PheToolsTemplate::from_mendelian_template(original_matrix.clone(), hpo.clone(), false).unwrap();
+        assert_eq!(n_hpo, template.header.hpo_count());
+        assert_eq!(original_matrix[0].len(), template.n_columns());
+        let json = serde_json::to_string(&template.to_dto()).unwrap();
+        let dto: TemplateDto = serde_json::from_str(&json).unwrap();
+        let individual = &dto.rows[0].individual_dto;
+        assert_eq!(Some("P20Y".to_string()), individual.age_at_death);
+        assert_eq!(Some("OMIM:135100"), individual.cause_of_death.as_ref().map(|dx| dx.disease_id.as_str()));
+        let restored = PheToolsTemplate::from_dto(hpo, &dto).unwrap();
+        assert_eq!(original_matrix, restored.get_string_matrix());
+    }
+
+    /// A cause of death of "na" is not exported, but the column is kept
+    #[rstest]
+    fn test_vital_status_columns_na(
+        mut original_matrix: Vec<Vec<String>>, 
+        hpo: Arc<FullCsrOntology>) {
+        add_vital_status_columns(&mut original_matrix, "na", ("na", "na"));
+        let template = PheToolsTemplate::from_mendelian_template(original_matrix.clone(), hpo.clone(), false).unwrap();
+        let dto = template.get_template_dto().unwrap();
+        assert_eq!(Some("na".to_string()), dto.rows[0].individual_dto.age_at_death);
+        assert!(dto.rows[0].individual_dto.cause_of_death.is_none());
+        let template = PheToolsTemplate::from_template_dto(&dto, hpo).unwrap();
+        assert_eq!(original_matrix, template.get_string_matrix());
+    }
+
+    #[rstest]
+    fn test_age_at_death_requires_deceased(
+        mut original_matrix: Vec<Vec<String>>, 
+        hpo: Arc<FullCsrOntology>) {
+        add_vital_status_columns(&mut original_matrix, "P20Y", ("na", "na"));
+        let result = PheToolsTemplate::from_mendelian_template(original_matrix, hpo, false);
+        let errors = result.unwrap_err().errors();
+        assert!(errors[0].starts_with("deceased: 'no' contradicts age at death 'P20Y'"));
+    }
+
+    /// The karyotypic sex column comes after the vital status columns and must survive a save/reload round trip
+    #[rstest]
+    fn test_karyotypic_sex_column_round_trip(
+        mut original_matrix: Vec<Vec<String>>, 
+        hpo: Arc<FullCsrOntology>) {
+        add_vital_status_columns(&mut original_matrix, "na", ("na", "na"));
+        original_matrix[0].push("karyotypic_sex".to_string());
+        original_matrix[1].push("optional".to_string());
+        original_matrix[2].push("47,XXY".to_string());
+        let template = PheToolsTemplate::from_mendelian_template(original_matrix.clone(), hpo.clone(), false).unwrap();
+        assert_eq!(original_matrix[0].len(), template.n_columns());
+        let json = serde_json::to_string(&template.to_dto()).unwrap();
+        let dto: TemplateDto = serde_json::from_str(&json).unwrap();
+        assert_eq!(Some("47,XXY".to_string()), dto.rows[0].individual_dto.karyotypic_sex);
+        let restored = PheToolsTemplate::from_dto(hpo, &dto).unwrap();
+        assert_eq!(original_matrix, restored.get_string_matrix());
+    }
+
+    #[rstest]
+    fn test_invalid_karyotypic_sex(
+        mut original_matrix: Vec<Vec<String>>, 
+        hpo: Arc<FullCsrOntology>) {
+        original_matrix[0].push("karyotypic_sex".to_string());
+        original_matrix[1].push("optional".to_string());
+        original_matrix[2].push("XYZ".to_string());
+        let result = PheToolsTemplate::from_mendelian_template(original_matrix.clone(), hpo, false);
+        assert!(result.is_err());
+        let issues = PheToolsTemplate::locate_cell_issues(&original_matrix);
+        assert_eq!(1, issues.len());
+        assert_eq!(Some(original_matrix[0].len() - 1), issues[0].col);
+        assert_eq!("Malformed karyotypic sex 'XYZ'", issues[0].message);
+    }
+
+    /// Columns after the metadata marker are not interpreted as HPO columns and survive a round trip unchanged
+    #[rstest]
+    fn test_trailing_metadata_round_trip(
+        mut original_matrix: Vec<Vec<String>>,
+        hpo: Arc<FullCsrOntology>) {
+        let n_hpo = original_matrix[0].len() - 17;
+        add_ancestry_column(&mut original_matrix, "European");
+        let trailing = [["metadata", "na", "na"], ["curator", "str", "Jane Doe"], ["notes", "str", "see Fig. 2"]];
+        for cells in trailing {
+            for (row, cell) in original_matrix.iter_mut().zip(cells) {
+                row.push(cell.to_string());
+            }
+        }
+        let template = PheToolsTemplate::from_mendelian_template(original_matrix.clone(), hpo.clone(), false).unwrap();
+        assert_eq!(n_hpo, template.header.hpo_count());
+        assert_eq!(original_matrix[0].len(), template.n_columns());
+        let dto = template.get_template_dto().unwrap();
+        assert_eq!(vec!["Jane Doe".to_string(), "see Fig. 2".to_string()], dto.rows[0].trailing_metadata);
+        assert_eq!(Some("European".to_string()), dto.rows[0].individual_dto.ancestry);
+        let template = PheToolsTemplate::from_template_dto(&dto, hpo).unwrap();
+        assert_eq!(original_matrix, template.get_string_matrix());
+    }
+
+    /// An implausible age is reported as a warning, not as an error
+    #[rstest]
+    fn test_validation_report_implausible_age(
+        mut original_matrix: Vec<Vec<String>>, 
+        hpo: Arc<FullCsrOntology>) {
+        original_matrix[2][13] = "P160Y".to_string();
+        let template = PheToolsTemplate::from_mendelian_template(original_matrix, hpo, false).unwrap();
+        let report = template.get_validation_report();
+        assert!(!report.contains("Errors"));
+        assert!(report.contains("age_at_last_encounter: 1 issue(s)"));
+        assert!(report.contains("Implausible age 'P160Y' (more than 120 years)"));
+    }
+
+    /// A deceased individual without an age at last encounter is a warning; an age at death before the last encounter is an error
+    #[rstest]
+    fn test_deceased_age_consistency(
+        mut original_matrix: Vec<Vec<String>>, 
+        hpo: Arc<FullCsrOntology>) {
+        original_matrix[2][13] = "na".to_string();
+        original_matrix[2][14] = "yes".to_string();
+        let template = PheToolsTemplate::from_mendelian_template(original_matrix, hpo.clone(), false).unwrap();
+        let report = template.get_validation_report();
+        assert!(!report.contains("Errors"));
+        assert!(report.contains("deceased individual without age at last encounter"));
+        let mut dto = template.row_dtos().next().unwrap();
+        dto.individual_dto.age_at_last_encounter = "P16Y".to_string();
+        dto.individual_dto.age_at_death = Some("P10Y".to_string());
+        let errors = PpktRow::from_dto(&dto, template.header.clone()).check_for_errors().unwrap_err().errors();
+        assert_eq!(vec!["Age at last encounter 'P16Y' is later than age at death 'P10Y'".to_string()], errors);
+    }
+
+    #[rstest]
+    fn test_extract_phenopacket_row_out_of_bounds(
+        original_matrix: Vec<Vec<String>>, 
+        hpo: Arc<FullCsrOntology>) {
+        let template = PheToolsTemplate::from_mendelian_template(original_matrix, hpo, false).unwrap();
+        let result = template.extract_phenopacket(42, &HashMap::new(), &HashMap::new(), &ExportSettings::new("0000-0000-0000-0000"));
+        assert!(result.is_err());
+        assert!(result.unwrap_err().starts_with("Row index 42 out of bounds"));
+    }
+
+    /// A trailing non-breaking space (e.g., pasted from a PDF) is normalized and reported as a warning
+    #[rstest]
+    fn test_allele_invisible_whitespace_normalized(
+        mut original_matrix: Vec<Vec<String>>, 
+        hpo: Arc<FullCsrOntology>) {
+        original_matrix[2][9] = "c.617G>A\u{00A0}".to_string();
+        let template = PheToolsTemplate::from_mendelian_template(original_matrix, hpo, false).unwrap();
+        let dto = template.get_template_dto().unwrap();
+        assert_eq!("c.617G>A", dto.rows[0].gene_var_dto_list[0].allele1);
+        let report = template.get_validation_report();
+        assert!(!report.contains("Errors"));
+        assert!(report.contains("allele_1: normalized invisible whitespace (U+00A0) in 'c.617G>A'"));
+    }
+
+    /// The optional comment columns must survive load -> DTO -> template -> matrix -> load
+    #[rstest]
+    fn test_comment_round_trip(
+        mut original_matrix: Vec<Vec<String>>, 
+        hpo: Arc<FullCsrOntology>) {
+        original_matrix[2][INDIVIDUAL_COMMENT] = "sibling of individual 2".to_string();
+        let template = PheToolsTemplate::from_mendelian_template(original_matrix.clone(), hpo.clone(), false).unwrap();
+        let dto = template.get_template_dto().unwrap();
+        assert_eq!("sibling of individual 2", dto.rows[0].individual_dto.comment);
+        assert_eq!("NP_001104537.1:p.(Arg206His)", dto.rows[0].gene_var_dto_list[0].variant_comment);
+        let template = PheToolsTemplate::from_template_dto(&dto, hpo.clone()).unwrap();
+        let matrix = template.get_string_matrix();
+        assert_eq!(original_matrix, matrix);
+        let template = PheToolsTemplate::from_mendelian_template(matrix, hpo, false).unwrap();
+        let dto = template.get_template_dto().unwrap();
+        assert_eq!("sibling of individual 2", dto.rows[0].individual_dto.comment);
+        assert_eq!("NP_001104537.1:p.(Arg206His)", dto.rows[0].gene_var_dto_list[0].variant_comment);
+    }
+
+    #[rstest]
+    fn test_locate_cell_issues(
+        mut original_matrix: Vec<Vec<String>>, 
+        hpo: Arc<FullCsrOntology>) {
+        assert!(PheToolsTemplate::locate_cell_issues(&original_matrix).is_empty());
+        original_matrix[2][15] = "male".to_string();
+        original_matrix[2][20] = "yes".to_string();
+        let issues = PheToolsTemplate::locate_cell_issues(&original_matrix);
+        assert_eq!(2, issues.len());
+        assert_eq!((Some(2), Some(15)), (issues[0].row, issues[0].col));
+        assert_eq!((Some(2), Some(20)), (issues[1].row, issues[1].col));
+        assert_eq!(Severity::Error, issues[0].severity);
+        assert!(PheToolsTemplate::from_mendelian_template(original_matrix, hpo, false).is_err());
+    }
+
+    #[rstest]
+    fn test_obsolete_hpo_id_in_header(
+        mut original_matrix: Vec<Vec<String>>, 
+        hpo: Arc<FullCsrOntology>) {
+        // HP:0004715 is an alternate id of Multicystic kidney dysplasia (HP:0000003)
+        original_matrix[0][17] = "Multicystic kidney dysplasia".to_string();
+        original_matrix[1][17] = "HP:0004715".to_string();
+        let verrs = PheToolsTemplate::from_mendelian_template(original_matrix.clone(), hpo.clone(), false).unwrap_err();
+        assert!(verrs.errors().iter().any(|e| e.contains("Obsolete HPO TermId: HP:0004715; replace with HP:0000003")));
+        let template = PheToolsTemplate::from_mendelian_template(original_matrix, hpo, true).unwrap();
+        assert_eq!("HP:0000003", template.get_template_dto().unwrap().hpo_headers[0].h2);
+    }
+
+    #[rstest]
+    fn test_set_hpo_value(
+        original_matrix: Vec<Vec<String>>, 
+        hpo: Arc<FullCsrOntology>) {
+        let mut template = PheToolsTemplate::from_mendelian_template(original_matrix, hpo, false).unwrap();
+        let pain = TermId::from_str("HP:0012531").unwrap();
+        template.set_hpo_value(0, &pain, "excluded").unwrap();
+        assert_eq!("excluded", template.row_dtos().next().unwrap().hpo_data[5].value);
+        let unknown = TermId::from_str("HP:9999999").unwrap();
+        assert!(matches!(template.set_hpo_value(0, &unknown, "observed"), Err(Error::HpIdNotFound { .. })));
+        assert!(matches!(template.set_hpo_value(0, &pain, "yes"), Err(Error::TemplateError { .. })));
+        assert!(matches!(template.set_hpo_value(1, &pain, "observed"), Err(Error::TemplateError { .. })));
+        let seizure = TermId::from_str("HP:0001250").unwrap(); // not a column of the template
+        assert!(matches!(template.set_hpo_value(0, &seizure, "observed"), Err(Error::ValidationErrors(_))));
+    }
+
+    #[rstest]
+    fn test_add_hpo_term_keeps_row_values(
+        original_matrix: Vec<Vec<String>>, 
+        hpo: Arc<FullCsrOntology>) {
+        let mut template = PheToolsTemplate::from_mendelian_template(original_matrix.clone(), hpo, false).unwrap();
+        template.add_hpo_term_to_cohort("HP:0001250", "Seizure").unwrap();
+        let matrix = template.get_string_matrix();
+        assert_eq!(original_matrix[1].len() + 1, matrix[1].len());
+        for (col, hpo_id) in original_matrix[1].iter().enumerate().skip(17) {
+            let new_col = matrix[1].iter().position(|cell| cell == hpo_id).unwrap();
+            assert_eq!(original_matrix[2][col], matrix[2][new_col]);
+        }
+        let seizure_col = matrix[1].iter().position(|cell| cell == "HP:0001250").unwrap();
+        assert_eq!("Seizure", matrix[0][seizure_col]);
+        assert_eq!("na", matrix[2][seizure_col]);
+        assert!(template.add_hpo_term_to_cohort("HP:0001250", "Seizure").is_err());
+    }
+
+    #[rstest]
+    fn test_header_dtos_match_matrix(
+        original_matrix: Vec<Vec<String>>, 
+        hpo: Arc<FullCsrOntology>) {
+        let template = PheToolsTemplate::from_mendelian_template(original_matrix.clone(), hpo, false).unwrap();
+        let header_dtos = template.header.get_header_dtos();
+        assert_eq!(original_matrix[0], header_dtos.iter().map(|h| h.h1.clone()).collect::<Vec<_>>());
+        assert_eq!(original_matrix[1], header_dtos.iter().map(|h| h.h2.clone()).collect::<Vec<_>>());
+        let template_dto = template.get_template_dto().unwrap();
+        assert_eq!(TemplateType::Mendelian, template_dto.cohort_type);
+        assert_eq!(7, template_dto.hpo_headers.len());
+        assert_eq!(1, template_dto.rows.len());
+    }
+
+    #[rstest]
+    fn test_delete_hpo_column(
+        original_matrix: Vec<Vec<String>>, 
+        hpo: Arc<FullCsrOntology>) {
+        let mut template = PheToolsTemplate::from_mendelian_template(original_matrix, hpo, false).unwrap();
+        template.add_hpo_term_to_cohort("HP:0001250", "Seizure").unwrap();
+        let with_seizure = template.get_string_matrix();
+        let col = with_seizure[1].iter().position(|cell| cell == "HP:0001250").unwrap();
+        template.delete_hpo_column(col).unwrap();
+        let expected: Vec<Vec<String>> = with_seizure.into_iter()
+            .map(|mut row| { row.remove(col); row })
+            .collect();
+        assert_eq!(expected, template.get_string_matrix());
+        assert!(template.delete_hpo_column(9).is_err()); // allele_1
+        assert!(template.delete_hpo_column(template.n_columns()).is_err());
+    }
+
+    #[rstest]
+    fn test_get_row_issues(
+        original_matrix: Vec<Vec<String>>, 
+        hpo: Arc<FullCsrOntology>) {
+        let template = PheToolsTemplate::from_mendelian_template(original_matrix, hpo.clone(), false).unwrap();
+        assert!(template.get_row_issues(0).unwrap().is_empty());
+        assert!(template.get_row_issues(1).is_err());
+        let mut dto = template.row_dtos().next().unwrap();
+        dto.individual_dto.sex = "male".to_string();
+        dto.hpo_data[3].value = "yes".to_string();
+        let ppkt_rows = vec![PpktRow::from_dto(&dto, template.header.clone())];
+        let template = PheToolsTemplate::from_parts(template.header.clone(), ppkt_rows, TemplateType::Mendelian, hpo);
+        let issues = template.get_row_issues(0).unwrap();
+        assert_eq!(2, issues.len());
+        assert_eq!((Some(2), Some(15)), (issues[0].row, issues[0].col));
+        assert_eq!((Some(2), Some(20)), (issues[1].row, issues[1].col));
+    }
+
+    #[rstest]
+    fn test_execute_operation(
+        original_matrix: Vec<Vec<String>>,
+        hpo: Arc<FullCsrOntology>) {
+        let mut template = PheToolsTemplate::from_mendelian_template(original_matrix, hpo, false).unwrap();
+        template.execute_operation(2, 15, "female").unwrap();
+        template.execute_operation(2, 17, "observed").unwrap();
+        template.execute_operation(2, 6, "remove whitespace").unwrap();
+        let matrix = template.get_string_matrix();
+        assert_eq!("F", matrix[2][15]);
+        assert_eq!("observed", matrix[2][17]);
+        assert_eq!("HGNC:171", matrix[2][6]);
+        assert!(template.execute_operation(2, 1, "remove whitespace").is_err());
+        assert!(template.execute_operation(2, 0, "clear").is_err());
+        assert_eq!("PMID:29482508", template.get_string_matrix()[2][0]);
+        assert!(template.execute_operation(2, 15, "edit").is_err());
+        assert!(template.execute_operation(2, 15, "capitalize").is_err());
+        assert!(template.execute_operation(1, 15, "female").is_err());
+    }
+
+    #[rstest]
+    fn test_clear_operation(
+        mut original_matrix: Vec<Vec<String>>,
+        hpo: Arc<FullCsrOntology>) {
+        original_matrix[2][3] = "Patient 3 of the family".to_string();
+        let mut template = PheToolsTemplate::from_mendelian_template(original_matrix, hpo, false).unwrap();
+        template.execute_operation(2, 3, "clear").unwrap();
+        assert_eq!("", template.get_string_matrix()[2][3]);
+        let err = template.execute_operation(2, 5, "clear").unwrap_err();
+        assert_eq!("Cannot clear column 5 (disease_label must not be empty)", err.to_string());
+        assert_eq!("Fibrodysplasia ossificans progressiva", template.get_string_matrix()[2][5]);
+    }
+
+    #[rstest]
+    fn test_cohort_summary(
+        mut two_individual_matrix: Vec<Vec<String>>,
+        hpo: Arc<FullCsrOntology>) {
+        two_individual_matrix[3][17] = "excluded".to_string();
+        let template = PheToolsTemplate::from_mendelian_template(two_individual_matrix, hpo, false).unwrap();
+        let summary = template.cohort_summary();
+        assert_eq!(2, summary.phenopacket_count);
+        assert_eq!(7, summary.hpo_column_count);
+        assert_eq!(vec![DiseaseDto::new("OMIM:135100", "Fibrodysplasia ossificans progressiva")], summary.diseases);
+        assert_eq!(1, summary.genes.len());
+        assert_eq!("ACVR1", summary.genes[0].gene_symbol);
+        assert_eq!("NM_001111067.4", summary.genes[0].transcript);
+        assert_eq!(8, summary.observed_count);
+        assert_eq!(1, summary.excluded_count);
+        assert_eq!(5, summary.na_count);
+        let json = serde_json::to_value(&summary).unwrap();
+        assert_eq!(2, json["phenopacketCount"]);
+    }
+
+    #[rstest]
+    fn test_get_term_frequencies(
+        mut two_individual_matrix: Vec<Vec<String>>,
+        hpo: Arc<FullCsrOntology>) {
+        two_individual_matrix[3][17] = "excluded".to_string();
+        two_individual_matrix[3][18] = "observed".to_string();
+        let template = PheToolsTemplate::from_mendelian_template(two_individual_matrix, hpo, false).unwrap();
+        let frequencies = template.get_term_frequencies().unwrap();
+        assert_eq!(7, frequencies.len());
+        let ids: Vec<String> = frequencies.iter().map(|f| f.term_id.to_string()).collect();
+        assert_eq!(template.header.hpo_duplets().iter().map(|d| d.hpo_id().to_string()).collect::<Vec<_>>(), ids);
+        assert_eq!((0, 1, 1), (frequencies[0].observed, frequencies[0].excluded, frequencies[0].na));
+        assert_eq!((2, 0, 0), (frequencies[1].observed, frequencies[1].excluded, frequencies[1].na));
+        assert!(!frequencies[0].is_unannotated());
+        assert!(frequencies[2].is_unannotated());
+        assert_eq!(2, frequencies[2].na);
+    }
+
+    #[rstest]
+    fn test_prune_empty_hpo_columns(
+        mut two_individual_matrix: Vec<Vec<String>>,
+        hpo: Arc<FullCsrOntology>) {
+        two_individual_matrix[3][17] = "excluded".to_string();
+        let mut template = PheToolsTemplate::from_mendelian_template(two_individual_matrix, hpo, false).unwrap();
+        let unannotated = template.get_unannotated_hpo_terms().unwrap();
+        let labels: Vec<&str> = unannotated.iter().map(|(_, label)| label.as_str()).collect();
+        assert_eq!(2, labels.len());
+        let pruned = template.prune_empty_hpo_columns().unwrap();
+        assert_eq!(unannotated, pruned);
+        assert_eq!(5, template.header.hpo_count());
+        assert!(template.get_unannotated_hpo_terms().unwrap().is_empty());
+        let matrix = template.get_string_matrix();
+        assert_eq!(22, matrix[2].len());
+        assert_eq!("excluded", matrix[3][17]);
+        for (tid, _) in &pruned {
+            assert!(!matrix[1].contains(&tid.to_string()));
+        }
+    }
+
+    #[rstest]
+    fn test_json_round_trip(
+        mut two_individual_matrix: Vec<Vec<String>>,
+        hpo: Arc<FullCsrOntology>) {
+        two_individual_matrix[3][3] = "sibling of current case".to_string();
+        two_individual_matrix[3][17] = "excluded".to_string();
+        two_individual_matrix[3][18] = "P3Y;severity=Mild".to_string();
+        let template = PheToolsTemplate::from_mendelian_template(two_individual_matrix, hpo.clone(), false).unwrap();
+        let json = serde_json::to_string(&template.to_dto()).unwrap();
+        let dto: TemplateDto = serde_json::from_str(&json).unwrap();
+        let restored = PheToolsTemplate::from_dto(hpo.clone(), &dto).unwrap();
+        assert_eq!(template.get_string_matrix(), restored.get_string_matrix());
+        assert!(restored.check_for_errors().is_ok());
+    }
+
+    #[rstest]
+    fn test_json_round_trip_ancestry_without_rows(
+        mut original_matrix: Vec<Vec<String>>,
+        hpo: Arc<FullCsrOntology>) {
+        add_ancestry_column(&mut original_matrix, "European");
+        let mut template = PheToolsTemplate::from_mendelian_template(original_matrix, hpo.clone(), false).unwrap();
+        template.delete_row(0).unwrap();
+        let json = serde_json::to_string(&template.to_dto()).unwrap();
+        let dto: TemplateDto = serde_json::from_str(&json).unwrap();
+        let restored = PheToolsTemplate::from_dto(hpo, &dto).unwrap();
+        assert_eq!(template.get_string_matrix(), restored.get_string_matrix());
+        assert_eq!(Some("ancestry"), restored.get_string_matrix()[0].last().map(String::as_str));
+    }
+
+    #[rstest]
+    fn test_get_cell_kind(
+        original_matrix: Vec<Vec<String>>,
+        hpo: Arc<FullCsrOntology>) {
+        let template = PheToolsTemplate::from_mendelian_template(original_matrix, hpo, false).unwrap();
+        assert_eq!(template.n_columns(), template.header.get_cell_kinds().len());
+        assert_eq!(CellKindDto::Curie { prefix: Some("PMID".to_string()) }, template.get_cell_kind(2, 0).unwrap());
+        assert_eq!(CellKindDto::FreeText, template.get_cell_kind(2, 9).unwrap());
+        assert!(matches!(template.get_cell_kind(2, 12).unwrap(), CellKindDto::Age { allowed_labels } if allowed_labels.contains(&"Juvenile onset".to_string())));
+        let deceased = CellKindDto::Enumerated { allowed_values: vec!["yes".to_string(), "no".to_string(), "na".to_string()] };
+        assert_eq!(deceased, template.get_cell_kind(2, 14).unwrap());
+        match template.get_cell_kind(2, 17).unwrap() {
+            CellKindDto::HpoStatus { allowed_values, .. } => assert_eq!(vec!["observed", "excluded", "na"], allowed_values),
+            other => panic!("Expected an HPO column but got {:?}", other),
+        }
+        assert!(template.get_cell_kind(0, 0).is_err());
+        assert!(template.get_cell_kind(3, 0).is_err());
+        assert!(template.get_cell_kind(2, template.n_columns()).is_err());
+    }
+
+    #[rstest]
+    fn test_qc_report(
+        original_matrix: Vec<Vec<String>>, 
+        hpo: Arc<FullCsrOntology>) {
+        let template = PheToolsTemplate::from_mendelian_template(original_matrix, hpo.clone(), false).unwrap();
+        let report = template.get_qc_report();
+        assert_eq!(vec![0], report.row_error_counts);
+        assert!(report.issues.is_empty());
+        let valid_dto = template.row_dtos().next().unwrap();
+        let mut dto = valid_dto.clone();
+        dto.individual_dto.sex = "male".to_string();
+        dto.hpo_data[3].value = "yes".to_string();
+        let mut late_onset_dto = valid_dto.clone();
+        late_onset_dto.individual_dto.age_of_onset = "P20Y".to_string();
+        let ppkt_rows = vec![
+            PpktRow::from_dto(&valid_dto, template.header.clone()),
+            PpktRow::from_dto(&dto, template.header.clone()),
+            PpktRow::from_dto(&late_onset_dto, template.header.clone()),
+        ];
+        let template = PheToolsTemplate::from_parts(template.header.clone(), ppkt_rows, TemplateType::Mendelian, hpo);
+        let report = template.get_qc_report();
+        assert_eq!(vec![0, 2, 1], report.row_error_counts);
+        assert_eq!(template.header.get_header_dtos().len(), report.column_error_counts.len());
+        assert_eq!(1, report.column_error_counts[15]);
+        assert_eq!(1, report.column_error_counts[20]);
+        assert_eq!(2, report.column_error_counts.iter().sum::<usize>());
+        let row_error = report.issues.last().unwrap();
+        assert_eq!((Some(4), None), (row_error.row, row_error.col));
+    }
+
+    /// The separator column of each data row must be na; observed/excluded there indicates that the HPO block was shifted
+    #[rstest]
+    #[case("observed", "Separator value must be 'na' but was 'observed' (data appears shifted by one column)")]
+    #[case("P3Y", "Separator value must be 'na' but was 'P3Y'")]
+    fn test_separator_in_data_row(
+        mut original_matrix: Vec<Vec<String>>, 
+        hpo: Arc<FullCsrOntology>,
+        #[case] entry: &str,
+        #[case] error_msg: &str) {
+        original_matrix[2][16] = entry.to_string();
+        let result = PheToolsTemplate::from_mendelian_template(original_matrix, hpo, false);
+        assert!(result.is_err());
+        assert_eq!(vec![error_msg.to_string()], result.err().unwrap().errors());
+    }
+
+    #[rstest]
+    fn test_get_long_records(
+        original_matrix: Vec<Vec<String>>, 
+        hpo: Arc<FullCsrOntology>) {
+        let template = PheToolsTemplate::from_mendelian_template(original_matrix, hpo, false).unwrap();
+        assert_eq!(7, template.get_long_records(false).len());
+        let records = template.get_long_records(true);
+        assert_eq!(4, records.len());
+        assert_eq!("HP:0001822", records[0].term_id);
+        assert_eq!("Hallux valgus", records[0].label);
+        assert_eq!("observed", records[0].value);
+        assert_eq!(Some("P16Y".to_string()), records[0].onset);
+        assert_eq!("current case", records[0].individual_id);
+    }
+
+    #[rstest]
+    fn test_get_coverage(
+        original_matrix: Vec<Vec<String>>, 
+        hpo: Arc<FullCsrOntology>) {
+        let template = PheToolsTemplate::from_mendelian_template(original_matrix, hpo, false).unwrap();
+        let hallux_valgus = TermId::from_str("HP:0001822").unwrap();
+        let short_thumb = TermId::from_str("HP:0009778").unwrap();
+        let pain = TermId::from_str("HP:0012531").unwrap();
+        let report = template.get_coverage(&[hallux_valgus.clone(), short_thumb.clone(), pain.clone()]);
+        assert_eq!(vec![hallux_valgus, pain], report.covered);
+        // Short thumb is in the template but is not observed in any individual
+        assert_eq!(vec![short_thumb], report.missing);
+        let extra: Vec<String> = report.extra.iter().map(|tid| tid.to_string()).collect();
+        assert_eq!(vec!["HP:0011987", "HP:0001847"], extra);
+    }
+
+    #[rstest]
+    fn test_get_coverage_empty_cell(
+        mut original_matrix: Vec<Vec<String>>, 
+        hpo: Arc<FullCsrOntology>) {
+        // an empty cell is not available, i.e., Short thumb is still not observed in any individual
+        original_matrix[2][23] = "".to_string();
+        let template = PheToolsTemplate::from_mendelian_template(original_matrix, hpo, false).unwrap();
+        let short_thumb = TermId::from_str("HP:0009778").unwrap();
+        let report = template.get_coverage(&[short_thumb.clone()]);
+        assert!(report.covered.is_empty());
+        assert_eq!(vec![short_thumb], report.missing);
+    }
+
+    #[rstest]
+    fn test_get_related_terms(
+        original_matrix: Vec<Vec<String>>, 
+        hpo: Arc<FullCsrOntology>) {
+        let template = PheToolsTemplate::from_mendelian_template(original_matrix, hpo, false).unwrap();
+        // Short 1st metacarpal is in the template, and Short thumb is one of its ancestors
+        let candidate = TermId::from_str("HP:0010034").unwrap();
+        let short_thumb = TermId::from_str("HP:0009778").unwrap();
+        let related = template.get_related_terms(&candidate);
+        assert_eq!(vec![(candidate, Relation::Same), (short_thumb, Relation::Ancestor)], related);
+        // Abnormality of the hand is an ancestor of Clinodactyly of the 5th finger, Short 1st metacarpal, and Short thumb
+        let candidate = TermId::from_str("HP:0001155").unwrap();
+        let related = template.get_related_terms(&candidate);
+        assert_eq!(3, related.len());
+        assert!(related.iter().all(|(_, rel)| *rel == Relation::Descendant));
+        // Abnormal nervous system physiology is not related to any column
+        let candidate = TermId::from_str("HP:0012638").unwrap();
+        assert!(template.get_related_terms(&candidate).is_empty());
+    }
+
+    #[rstest]
+    fn test_apply_hpo_profile(
+        original_matrix: Vec<Vec<String>>, 
+        hpo: Arc<FullCsrOntology>) {
+        let mut template = PheToolsTemplate::from_mendelian_template(original_matrix, hpo, false).unwrap();
+        let n_hpo = template.header.hpo_count();
+        let hpo_dtos = vec![
+            HpoTermDto::new("HP:0001822", "Hallux valgus", "excluded"),
+            HpoTermDto::new("HP:0001250", "Seizure", "observed"),
+        ];
+        template.apply_hpo_profile(0, hpo_dtos).unwrap();
+        assert_eq!(n_hpo + 1, template.header.hpo_count());
+        let values: HashMap<String, String> = template.ppkt_rows[0].get_hpo_term_dto_list().unwrap()
+            .into_iter()
+            .map(|dto| (dto.term_id().to_string(), dto.entry().to_string()))
+            .collect();
+        assert_eq!("excluded", values["HP:0001822"]);
+        assert_eq!("observed", values["HP:0001250"]);
+        assert_eq!("P16Y", values["HP:0011987"]); // unchanged
+    }
+
+    #[rstest]
+    fn test_apply_hpo_profile_rejects_bad_value(
+        original_matrix: Vec<Vec<String>>, 
+        hpo: Arc<FullCsrOntology>) {
+        let mut template = PheToolsTemplate::from_mendelian_template(original_matrix, hpo, false).unwrap();
+        let hpo_dtos = vec![HpoTermDto::new("HP:0001822", "Hallux valgus", "obsreved")];
+        assert!(template.apply_hpo_profile(0, hpo_dtos).is_err());
+        let hpo_dtos = vec![HpoTermDto::new("HP:0001822", "Hallux valgus", "observed")];
+        assert!(template.apply_hpo_profile(5, hpo_dtos).is_err());
+    }
+
     #[rstest]
     fn test_malformed_hpo_label(mut original_matrix: Vec<Vec<String>>, hpo: Arc<FullCsrOntology>) {
         // "Hallux valgus" has extra white space
@@ -0,0 +1,165 @@
+//! PubMed
+//!
+//! Optional online check that the PMIDs referenced in a template resolve to real articles.
+//! The titles returned by PubMed are compared with the title column of the template.
+//! The HTTP layer is injectable ([`PubMedClient`]) so that the check can be tested offline.
+
+use reqwest::blocking::get;
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::error::Error;
+
+/// NCBI E-utilities endpoint used by default
+pub const DEFAULT_EUTILS_URL: &str = "https://eutils.ncbi.nlm.nih.gov/entrez/eutils";
+
+/// The HTTP layer used to query PubMed.
+/// The default implementation uses blocking reqwest calls to the NCBI E-utilities; tests can supply canned titles.
+pub trait PubMedClient: Send + Sync {
+    /// Retrieve the title of the article with the given PMID (digits only, e.g., 29482508).
+    /// Returns `Ok(None)` if PubMed does not have an article with this PMID.
+    fn fetch_title(&self, pmid: &str) -> Result<Option<String>, String>;
+}
+
+/// Query the esummary service of the NCBI E-utilities (or a compatible mirror) with blocking reqwest calls
+pub struct EutilsPubMedClient {
+    base_url: String,
+}
+
+impl EutilsPubMedClient {
+    /// Create a client for a configurable endpoint, e.g., [`DEFAULT_EUTILS_URL`]
+    pub fn new(base_url: impl Into<String>) -> Self {
+        Self { base_url: base_url.into() }
+    }
+
+    fn get_esummary_url(&self, pmid: &str) -> String {
+        format!("{}/esummary.fcgi?db=pubmed&id={}&retmode=json", self.base_url.trim_end_matches('/'), pmid)
+    }
+
+    /// Extract the title from an esummary response; records of unknown PMIDs have an "error" field instead
+    fn extract_title(response: &Value, pmid: &str) -> Result<Option<String>, String> {
+        let record = response.get("result")
+            .and_then(|result| result.get(pmid))
+            .ok_or_else(|| format!("Unexpected PubMed response for PMID:{pmid}"))?;
+        if record.get("error").is_some() {
+            return Ok(None);
+        }
+        Ok(record.get("title").and_then(Value::as_str).map(str::to_string))
+    }
+}
+
+impl Default for EutilsPubMedClient {
+    fn default() -> Self {
+        Self::new(DEFAULT_EUTILS_URL)
+    }
+}
+
+impl PubMedClient for EutilsPubMedClient {
+    fn fetch_title(&self, pmid: &str) -> Result<Option<String>, String> {
+        let response: Value = get(self.get_esummary_url(pmid))
+            .and_then(|r| r.error_for_status())
+            .map_err(request_error)?
+            .json()
+            .map_err(|e| format!("Could not parse JSON from PubMed: {e}"))?;
+        Self::extract_title(&response, pmid)
+    }
+}
+
+/// Distinguish transport errors (the service could not be reached) from HTTP errors (the service responded with an error status)
+fn request_error(e: reqwest::Error) -> String {
+    if e.is_connect() || e.is_timeout() {
+        Error::network_unreachable("PubMed", &e).to_string()
+    } else if let Some(status) = e.status() {
+        format!("PubMed returned HTTP {status}")
+    } else {
+        format!("Could not retrieve PubMed response: {e}")
+    }
+}
+
+/// Result of looking up one PMID of the template in PubMed
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase", tag = "status")]
+pub enum PmidStatus {
+    /// The article exists and its title matches the title column
+    Found { title: String },
+    /// PubMed does not have an article with this PMID
+    NotFound,
+    /// The article exists, but its title differs from the title column of the template
+    TitleMismatch { expected: String, actual: String },
+}
+
+/// Titles are compared ignoring case, runs of whitespace, and a final period (which PubMed adds)
+fn normalize_title(title: &str) -> String {
+    title.split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .trim_end_matches('.')
+        .to_lowercase()
+}
+
+/// Look up each (PMID, title) pair, e.g., (PMID:29482508, Difficult diagnosis...) in PubMed
+pub fn verify_pmids(
+    client: &dyn PubMedClient,
+    pmid_titles: &[(String, String)]
+) -> Result<Vec<(String, PmidStatus)>, String> {
+    let mut status_list = Vec::with_capacity(pmid_titles.len());
+    for (pmid, expected) in pmid_titles {
+        let id = pmid.strip_prefix("PMID:")
+            .ok_or_else(|| format!("Malformed PMID '{pmid}'"))?;
+        let status = match client.fetch_title(id)? {
+            None => PmidStatus::NotFound,
+            Some(actual) if normalize_title(&actual) == normalize_title(expected) => PmidStatus::Found { title: actual },
+            Some(actual) => PmidStatus::TitleMismatch { expected: expected.clone(), actual },
+        };
+        status_list.push((pmid.clone(), status));
+    }
+    Ok(status_list)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+
+    /// Returns canned titles instead of querying PubMed
+    struct FixtureClient {
+        titles: HashMap<String, String>,
+    }
+
+    impl PubMedClient for FixtureClient {
+        fn fetch_title(&self, pmid: &str) -> Result<Option<String>, String> {
+            Ok(self.titles.get(pmid).cloned())
+        }
+    }
+
+    #[test]
+    fn test_verify_pmids() {
+        let title = "Difficult diagnosis and genetic analysis of fibrodysplasia ossificans progressiva: a case report";
+        let client = FixtureClient { titles: HashMap::from([
+            ("29482508".to_string(), format!("{title}.")),
+            ("11111111".to_string(), "Another article.".to_string()),
+        ])};
+        let pmid_titles = vec![
+            ("PMID:29482508".to_string(), title.to_string()),
+            ("PMID:11111111".to_string(), "A different title".to_string()),
+            ("PMID:22222222".to_string(), "Missing article".to_string()),
+        ];
+        let status_list = verify_pmids(&client, &pmid_titles).unwrap();
+        assert_eq!(PmidStatus::Found { title: format!("{title}.") }, status_list[0].1);
+        assert_eq!(PmidStatus::TitleMismatch {
+            expected: "A different title".to_string(),
+            actual: "Another article.".to_string() }, status_list[1].1);
+        assert_eq!(PmidStatus::NotFound, status_list[2].1);
+    }
+
+    #[test]
+    fn test_extract_title() {
+        let response = serde_json::json!({"result": {"uids": ["29482508", "1"],
+            "29482508": {"uid": "29482508", "title": "A case report."},
+            "1": {"uid": "1", "error": "cannot get document summary"}}});
+        assert_eq!(Some("A case report.".to_string()), EutilsPubMedClient::extract_title(&response, "29482508").unwrap());
+        assert_eq!(None, EutilsPubMedClient::extract_title(&response, "1").unwrap());
+    }
+}
@@ -21,6 +21,9 @@ pub struct HgvsVariant {
     hgvs: Option<String>,
     transcript: Option<String>,
     g_hgvs: Option<String>,
+    /// Predicted protein consequence, e.g., NP_000129.3:p.(Glu2748Ter)
+    #[serde(default)]
+    p_hgvs: Option<String>,
     genotype: Option<String>,
     variant_id: String,
 }
@@ -34,6 +37,7 @@ impl HgvsVariant {
         hgvs: Option<String>,
         transcript: Option<String>,
         g_hgvs: Option<String>,
+        p_hgvs: Option<String>,
         variant_id: Option<String>,
     ) -> Self {
         let chr = vcf_var.chrom();
@@ -61,6 +65,7 @@ impl HgvsVariant {
             hgvs,
             transcript,
             g_hgvs,
+            p_hgvs,
             genotype: None,
             variant_id,
         }
@@ -74,6 +79,11 @@ impl HgvsVariant {
         self.chr.as_ref()
     }
 
+    /// True if the variant is located on the X chromosome (VariantValidator reports both "X" and "chrX")
+    pub fn is_x_chromosomal(&self) -> bool {
+        self.chr.trim_start_matches("chr") == "X"
+    }
+
     pub fn position(&self) -> u32 {
         self.position
     }
@@ -105,6 +115,16 @@ impl HgvsVariant {
     pub fn g_hgvs(&self) -> Option<&str> {
         self.g_hgvs.as_deref()
     }
+
+    pub fn p_hgvs(&self) -> Option<&str> {
+        self.p_hgvs.as_deref()
+    }
+
+    /// Predicted protein consequence without the protein accession, e.g., p.(Glu2748Ter) for display next to the c. notation
+    pub fn protein_hgvs(&self) -> Option<&str> {
+        self.p_hgvs.as_deref()
+            .map(|p_hgvs| p_hgvs.rsplit_once(':').map_or(p_hgvs, |(_, p)| p))
+    }
     pub fn genotype(&self) ->  Option<&str> {
         self.genotype.as_deref()
     }
@@ -1,8 +1,9 @@
 use ontolius::{term::{simple::SimpleMinimalTerm, MinimalTerm}, Identified, TermId};
 use rand::{distr::Alphanumeric, Rng};
 use serde::{Serialize, Deserialize};
-use std::{collections::HashMap, str::FromStr};
+use std::{collections::HashMap, fmt, str::FromStr};
 use once_cell::sync::Lazy;
+use regex::Regex;
 use crate::{dto::variant_dto::VariantDto, error::{Error, Result}};
 const ACCEPTABLE_GENOMES: [&str; 2] = [ "GRCh38",  "hg38"];
 
@@ -58,6 +59,58 @@ static CHROMOSOMAL_INVERSION: Lazy<SimpleMinimalTerm> = Lazy::new(|| {
     )
 });
 
+/// ISCN notation of a balanced translocation, e.g., t(2;7)(q33;q11.23)
+static ISCN_TRANSLOCATION_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"t\(([1-9]|1\d|2[0-2]|X|Y);([1-9]|1\d|2[0-2]|X|Y)\)\(([pq]\d+(?:\.\d+)?);([pq]\d+(?:\.\d+)?)\)").unwrap()
+});
+
+/// A chromosomal band with an optional chr prefix, e.g., chr2q1 or 7q11.23
+static CHROMOSOMAL_BAND_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"\b(?:chr)?([1-9]|1\d|2[0-2]|X|Y)([pq]\d+(?:\.\d+)?)\b").unwrap()
+});
+
+/// One of the two breakpoints of a translocation, e.g., chromosome 7, band q11.23
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct Breakpoint {
+    chromosome: String,
+    band: String,
+}
+
+impl Breakpoint {
+    fn new(chromosome: &str, band: &str) -> Self {
+        Self { chromosome: chromosome.to_string(), band: band.to_string() }
+    }
+
+    pub fn chromosome(&self) -> &str {
+        &self.chromosome
+    }
+
+    pub fn band(&self) -> &str {
+        &self.band
+    }
+}
+
+impl fmt::Display for Breakpoint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}", self.chromosome, self.band)
+    }
+}
+
+/// Extract the two partner loci of a translocation from the free-text description, e.g.,
+/// t(2;7)(q33;q11.23) or trans(chr2q1, chr4p2). Returns None unless exactly two breakpoints can be identified.
+pub fn parse_translocation_breakpoints(description: &str) -> Option<(Breakpoint, Breakpoint)> {
+    if let Some(caps) = ISCN_TRANSLOCATION_RE.captures(description) {
+        return Some((Breakpoint::new(&caps[1], &caps[3]), Breakpoint::new(&caps[2], &caps[4])));
+    }
+    let bands: Vec<Breakpoint> = CHROMOSOMAL_BAND_RE.captures_iter(description)
+        .map(|caps| Breakpoint::new(&caps[1], &caps[2]))
+        .collect();
+    match bands.as_slice() {
+        [first, second] => Some((first.clone(), second.clone())),
+        _ => None,
+    }
+}
+
 #[derive(Serialize, Deserialize, Clone)]
 pub struct StructuralVariant {
     variant_id: String,
@@ -67,6 +120,9 @@ pub struct StructuralVariant {
     so_id: String,
     so_label: String,
     genotype: Option<String>,
+    /// Partner loci of a translocation, if they could be parsed from the label
+    #[serde(default)]
+    breakpoints: Option<(Breakpoint, Breakpoint)>,
 }
 
 impl StructuralVariant {
@@ -101,6 +157,7 @@ impl StructuralVariant {
             so_id: so_term.identifier().to_string(),
             so_label: so_term.name().to_string(),
             genotype: None,
+            breakpoints: None,
         })
     }
 
@@ -138,7 +195,9 @@ impl StructuralVariant {
         gene_id: impl Into<String>,  
         variant_id: Option<String>
     ) -> std::result::Result<Self, String> {
-        Self::new(cell_contents.into(), gene_symbol.into(), gene_id.into(), &CHROMOSOMAL_TRANSLOCATION, variant_id)
+        let mut sv = Self::new(cell_contents.into(), gene_symbol.into(), gene_id.into(), &CHROMOSOMAL_TRANSLOCATION, variant_id)?;
+        sv.breakpoints = parse_translocation_breakpoints(&sv.label);
+        Ok(sv)
     }
 
     pub fn chromosomal_structure_variation(
@@ -214,5 +273,60 @@ impl StructuralVariant {
         self.genotype.as_deref()
     }
 
+    /// The two breakpoints of a translocation (None for other structural variants or if the label could not be parsed)
+    pub fn breakpoints(&self) -> Option<(&Breakpoint, &Breakpoint)> {
+        self.breakpoints.as_ref().map(|(first, second)| (first, second))
+    }
+
+    /// ISCN representation of a translocation with known breakpoints, e.g., t(2;7)(q33;q11.23)
+    pub fn iscn(&self) -> Option<String> {
+        self.breakpoints().map(|(first, second)| {
+            format!("t({};{})({};{})", first.chromosome(), second.chromosome(), first.band(), second.band())
+        })
+    }
+
     
 }
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case("t(2;7)(q33;q11.23)", Some(("2q33", "7q11.23")))]
+    #[case("46,XY,t(X;12)(p11.2;q13) de novo", Some(("Xp11.2", "12q13")))]
+    #[case("trans(chr2q1, chr4p2", Some(("2q1", "4p2")))]
+    #[case("breakpoints at 9q34 and 22q11", Some(("9q34", "22q11")))]
+    #[case("balanced translocation", None)]
+    #[case("chr2q1", None)]
+    #[case("1q21, 2p13, 3q26", None)]
+    fn test_parse_translocation_breakpoints(#[case] description: &str, #[case] expected: Option<(&str, &str)>) {
+        let breakpoints = parse_translocation_breakpoints(description);
+        let breakpoints = breakpoints.as_ref().map(|(a, b)| (a.to_string(), b.to_string()));
+        assert_eq!(expected.map(|(a, b)| (a.to_string(), b.to_string())), breakpoints, "Failed on input: {}", description);
+    }
+
+    #[rstest]
+    fn test_translocation_with_breakpoints() {
+        let sv = StructuralVariant::chromosomal_translocation("trans(chr2q1, chr4p2", "ACVR1", "HGNC:171", None).unwrap();
+        assert_eq!("SO:1000044", sv.so_id());
+        assert_eq!("chromosomal_translocation", sv.so_label());
+        let (first, second) = sv.breakpoints().unwrap();
+        assert_eq!("2", first.chromosome());
+        assert_eq!("p2", second.band());
+        assert_eq!(Some("t(2;4)(q1;p2)".to_string()), sv.iscn());
+    }
+
+    #[rstest]
+    fn test_translocation_label_only() {
+        let sv = StructuralVariant::chromosomal_translocation("balanced translocation", "ACVR1", "HGNC:171", None).unwrap();
+        assert_eq!("SO:1000044", sv.so_id());
+        assert!(sv.breakpoints().is_none());
+        assert!(sv.iscn().is_none());
+        let del = StructuralVariant::chromosomal_deletion("deletion 2q33", "ACVR1", "HGNC:171", None).unwrap();
+        assert!(del.breakpoints().is_none());
+    }
+}
@@ -1,15 +1,18 @@
 use std::fs::{File, OpenOptions};
 use std::hash::Hash;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc;
+use std::time::Instant;
 use std::ops::DerefMut;
 use std::{collections::HashMap, path::PathBuf};
 
 use phenopackets::schema::v1::core::variant;
 
 use crate::dto::validation_errors::ValidationErrors;
-use crate::dto::variant_dto::VariantListDto;
+use crate::dto::variant_dto::{FailedVariantDto, VariantListDto, VariantValidationReport};
 use crate::variant::hgvs_variant::HgvsVariant;
 use crate::variant::structural_validator::StructuralValidator;
-use crate::{dto::variant_dto::VariantDto, variant::variant_validator::VariantValidator};
+use crate::{dto::variant_dto::VariantDto, variant::variant_validator::{VariantApi, VariantValidator}};
 
 
 use crate::variant::structural_variant::{StructuralVariant, DELETION as DEL};
@@ -26,8 +29,10 @@ pub struct VariantManager {
     structural_cache_file_path: PathBuf,
     structural_cache: StructuralCache,
     variant_map: HashMap<String, VariantDto>,
-    validator: VariantValidator,
-    structural_validator: StructuralValidator
+    validator: Box<dyn VariantApi>,
+    structural_validator: StructuralValidator,
+    /// Errors while reading the cache files; caches that could not be read are replaced by an empty cache
+    cache_load_errors: Vec<String>
 }
 
 
@@ -35,20 +40,28 @@ pub struct VariantManager {
 
 impl VariantManager {
     pub fn new(path_buf: &PathBuf) -> Self {
+        Self::with_variant_api(path_buf, Box::new(VariantValidator::hg38()))
+    }
+
+    /// Create a manager that encodes HGVS variants with `variant_api`, e.g., an [`OfflineVariantApi`](crate::variant::variant_validator::OfflineVariantApi)
+    /// with canned variants for tests or for working without network access
+    pub fn with_variant_api(path_buf: &PathBuf, variant_api: Box<dyn VariantApi>) -> Self {
+        let mut cache_load_errors: Vec<String> = Vec::new();
         let hgvs_cache_file_path = path_buf.join("hgvs_cache.txt");
         let cache_obj: VariantCache = 
-            Self::load_hgvs(&hgvs_cache_file_path).unwrap_or_else(|_| HashMap::new());
+            Self::load_cache_or_empty(&hgvs_cache_file_path, Self::load_hgvs, &mut cache_load_errors);
         let structural_cache_file_path = path_buf.join("structural_cache.txt");
-        let structural_cache_obj = 
-            Self::load_structural(&structural_cache_file_path).unwrap_or_else(|_| HashMap::new());
+        let structural_cache_obj: StructuralCache = 
+            Self::load_cache_or_empty(&structural_cache_file_path, Self::load_structural, &mut cache_load_errors);
         Self {
             hgvs_cache_file_path,
             hgvs_cache: cache_obj,
             structural_cache_file_path,
             structural_cache: structural_cache_obj,
             variant_map: HashMap::new(),
-            validator: VariantValidator::hg38(),
-            structural_validator: StructuralValidator::hg38()
+            validator: variant_api,
+            structural_validator: StructuralValidator::hg38(),
+            cache_load_errors
         }
     }
 
@@ -62,16 +75,45 @@ impl VariantManager {
         }
     }
 
-    fn save_hgvs(&self) -> Result<(), String> {
+    /// Load a cache file if it exists. A corrupt or partially written cache file is recorded in `errors` and
+    /// ignored, so that the variants are simply validated again.
+    fn load_cache_or_empty<T: Default>(
+        path: &PathBuf, 
+        load: fn(&PathBuf) -> Result<T, String>,
+        errors: &mut Vec<String>
+    ) -> T {
+        if !path.exists() {
+            return T::default();
+        }
+        load(path).unwrap_or_else(|e| {
+            errors.push(format!("Could not read variant cache {}, starting with an empty cache: {}", path.display(), e));
+            T::default()
+        })
+    }
+
+    /// Errors while reading the cache files (e.g., a corrupt file), so that applications can warn the user
+    /// that the affected variants will be validated again
+    pub fn cache_load_errors(&self) -> &[String] {
+        &self.cache_load_errors
+    }
+
+    /// Write the cache as JSON to a temporary file that is then renamed, so that an interrupted write
+    /// does not leave a partial cache file behind
+    fn write_cache<T: serde::Serialize>(path: &PathBuf, cache: &T) -> Result<(), String> {
+        let tmp_path = path.with_extension("tmp");
         let file = OpenOptions::new()
             .write(true)
             .create(true)
             .truncate(true)
-            .open(&self.hgvs_cache_file_path)
+            .open(&tmp_path)
             .map_err(|e| e.to_string())?;
-        serde_json::to_writer_pretty(file, &self.hgvs_cache)
+        serde_json::to_writer_pretty(file, cache)
             .map_err(|e| e.to_string())?; 
-        Ok(())
+        std::fs::rename(&tmp_path, path).map_err(|e| e.to_string())
+    }
+
+    fn save_hgvs(&self) -> Result<(), String> {
+        Self::write_cache(&self.hgvs_cache_file_path, &self.hgvs_cache)
     }
     
     fn load_hgvs(path: &PathBuf) -> Result<VariantCache, String> {
@@ -82,15 +124,7 @@ impl VariantManager {
     }
 
     fn save_structural(&self) -> Result<(), String> {
-        let file = OpenOptions::new()
-            .write(true)
-            .create(true)
-            .truncate(true)
-            .open(&self.structural_cache_file_path)
-            .map_err(|e| e.to_string())?;
-        serde_json::to_writer_pretty(file, &self.structural_cache)
-            .map_err(|e| e.to_string())?; 
-        Ok(())
+        Self::write_cache(&self.structural_cache_file_path, &self.structural_cache)
     }
 
     fn load_structural(structural_cache_file_path: &PathBuf) -> Result<StructuralCache, String> {
@@ -151,15 +185,15 @@ impl VariantManager {
             } else {
                 let sv = self.structural_validator.validate_sv(dto)?;
                 self.structural_cache.insert(key.to_string(), sv);
-                self.save_structural();
+                self.save_structural()?;
                 Ok(dto.clone_validated())
             }
-        } else if self.hgvs_cache.contains_key(key) {
+        } else if self.is_cached_hgvs(dto) {
             Ok(dto.clone_validated())
         } else {
             let hgvs = self.validator.validate_hgvs(dto)?;
             self.hgvs_cache.insert(key.to_string(), hgvs);
-            self.save_hgvs();
+            self.save_hgvs()?;
             Ok(dto.clone_validated())
         }
     }
@@ -190,7 +224,7 @@ impl VariantManager {
                 } else {
                     evaluated_dto_list.push(dto.clone_unvalidated());
                 }
-            } else if self.hgvs_cache.contains_key(variant) {
+            } else if self.is_cached_hgvs(dto) {
                 evaluated_dto_list.push(dto.clone_validated());
             } else {
                 evaluated_dto_list.push(dto.clone_unvalidated());
@@ -199,8 +233,20 @@ impl VariantManager {
         VariantListDto::new(evaluated_dto_list)
     }
 
+    /// Validate a list of variants (using the cache where possible) and return the evaluated variants
     pub fn validate_variant_dto_list(&mut self, variant_dto_list: Vec<VariantDto>) -> Vec<VariantDto> {
+        self.validate_variant_dto_list_with_report(variant_dto_list).variant_dto_list
+    }
+
+    /// Validate a list of variants (using the cache where possible) and summarize the results in a report
+    pub fn validate_variant_dto_list_with_report(&mut self, variant_dto_list: Vec<VariantDto>) -> VariantValidationReport {
+        let started_at = chrono::Utc::now().to_rfc3339();
+        let timer = Instant::now();
+        let total = variant_dto_list.len();
+        let mut failed: Vec<FailedVariantDto> = Vec::new();
         let mut evaluated_dto_list: Vec<VariantDto> = Vec::with_capacity(variant_dto_list.len());
+        // the first error while writing the cache, if any
+        let mut cache_result: Result<(), String> = Ok(());
         for dto in variant_dto_list {
             let variant = dto.variant_string();
             if dto.is_structural() {
@@ -210,31 +256,165 @@ impl VariantManager {
                     match self.structural_validator.validate_sv(&dto) {
                         Ok(sv) => {
                             self.structural_cache.insert(variant.to_string(), sv);
+                            cache_result = cache_result.and(self.save_structural());
                             evaluated_dto_list.push(dto.clone_validated());
                         },
                         Err(e) => {
+                            failed.push(Self::failed_variant(&dto, e));
                             evaluated_dto_list.push(dto.clone_unvalidated());
                         },
                     }
                 }
-            } else if self.hgvs_cache.contains_key(variant) {
+            } else if self.is_cached_hgvs(&dto) {
                 evaluated_dto_list.push(dto.clone_validated());
             } else {
                 match self.validator.validate_hgvs(&dto) {
                     Ok(hgvs) => {
                         self.hgvs_cache.insert(variant.to_string(), hgvs);
+                        cache_result = cache_result.and(self.save_hgvs());
                         evaluated_dto_list.push(dto.clone_validated());
                     },
                     Err(e) => {
+                        failed.push(Self::failed_variant(&dto, e));
                         evaluated_dto_list.push(dto.clone_unvalidated());
                     },
                 }
             }
         }
-        self.save_hgvs();
-        self.save_structural(); // write variants to cache.
+        // write variants to cache.
+        let cache_error = cache_result
+            .and(self.save_hgvs())
+            .and(self.save_structural())
+            .err();
         VariantDto::sort_variant_dtos(&mut evaluated_dto_list);
-        evaluated_dto_list
+        VariantValidationReport {
+            total,
+            validated: total - failed.len(),
+            failed,
+            started_at,
+            elapsed_ms: timer.elapsed().as_millis(),
+            variant_dto_list: evaluated_dto_list,
+            cache_error
+        }
+    }
+
+    /// Validate a batch of variants with up to `max_concurrency` concurrent requests to VariantValidator.
+    /// Identical (transcript, variant) pairs are validated only once, and variants that are already in the cache
+    /// for the same transcript are not validated again. Failures do not abort the batch; they are listed in the report
+    /// together with the reason, as in [`Self::validate_variant_dto_list_with_report`]. The evaluated DTOs of the report
+    /// are in the input order. Structural variants do not require network calls and are validated sequentially.
+    /// The cache is written to disk once after all results have arrived.
+    pub fn validate_variant_dto_list_concurrent(
+        &mut self, 
+        variant_dto_list: Vec<VariantDto>,
+        max_concurrency: usize
+    ) -> VariantValidationReport {
+        let started_at = chrono::Utc::now().to_rfc3339();
+        let timer = Instant::now();
+        let total = variant_dto_list.len();
+        // Ok if the (transcript, variant) pair was validated, otherwise the reason
+        let mut outcome: HashMap<(String, String), Result<(), String>> = HashMap::new();
+        let mut pending: Vec<VariantDto> = Vec::new();
+        for dto in &variant_dto_list {
+            let key = Self::batch_key(dto);
+            if outcome.contains_key(&key) {
+                continue;
+            }
+            let variant = dto.variant_string();
+            if dto.is_structural() {
+                if self.structural_cache.contains_key(variant) {
+                    outcome.insert(key, Ok(()));
+                } else {
+                    match self.structural_validator.validate_sv(dto) {
+                        Ok(sv) => {
+                            self.structural_cache.insert(variant.to_string(), sv);
+                            outcome.insert(key, Ok(()));
+                        },
+                        Err(e) => {
+                            outcome.insert(key, Err(e));
+                        },
+                    }
+                }
+            } else if self.is_cached_hgvs(dto) {
+                outcome.insert(key, Ok(()));
+            } else {
+                // replaced below when the result arrives
+                outcome.insert(key, Err("No result from VariantValidator".to_string()));
+                pending.push(dto.clone());
+            }
+        }
+        let n_workers = max_concurrency.clamp(1, pending.len().max(1));
+        let next_job = AtomicUsize::new(0);
+        let (sender, receiver) = mpsc::channel::<(usize, Result<HgvsVariant, String>)>();
+        let validator = &self.validator;
+        let pending = &pending;
+        std::thread::scope(|scope| {
+            for _ in 0..n_workers {
+                let sender = sender.clone();
+                let next_job = &next_job;
+                scope.spawn(move || {
+                    loop {
+                        let i = next_job.fetch_add(1, Ordering::SeqCst);
+                        let Some(dto) = pending.get(i) else { break; };
+                        if sender.send((i, validator.validate_hgvs(dto))).is_err() {
+                            break;
+                        }
+                    }
+                });
+            }
+            drop(sender);
+            for (i, result) in receiver {
+                let dto = &pending[i];
+                let validated = result.map(|hgvs| {
+                    self.hgvs_cache.insert(dto.variant_string().to_string(), hgvs);
+                });
+                outcome.insert(Self::batch_key(dto), validated);
+            }
+        });
+        // write variants to cache.
+        let cache_error = self.save_hgvs()
+            .and(self.save_structural())
+            .err();
+        let mut failed: Vec<FailedVariantDto> = Vec::new();
+        let evaluated_dto_list: Vec<VariantDto> = variant_dto_list.into_iter()
+            .map(|dto| match &outcome[&Self::batch_key(&dto)] {
+                Ok(()) => dto.clone_validated(),
+                Err(e) => {
+                    failed.push(Self::failed_variant(&dto, e.clone()));
+                    dto.clone_unvalidated()
+                },
+            })
+            .collect();
+        VariantValidationReport {
+            total,
+            validated: total - failed.len(),
+            failed,
+            started_at,
+            elapsed_ms: timer.elapsed().as_millis(),
+            variant_dto_list: evaluated_dto_list,
+            cache_error
+        }
+    }
+
+    /// True if the HGVS variant is in the cache and was validated for the same transcript. The cache is keyed by
+    /// the variant string, so the transcript of the cached variant (e.g., NM_000138.5:c.8242G>T) is compared as well;
+    /// cached variants without a transcript are accepted.
+    fn is_cached_hgvs(&self, dto: &VariantDto) -> bool {
+        self.hgvs_cache.get(dto.variant_string())
+            .is_some_and(|hgvs| hgvs.transcript()
+                .is_none_or(|transcript| transcript.split(':').next() == Some(dto.transcript())))
+    }
+
+    fn batch_key(dto: &VariantDto) -> (String, String) {
+        (dto.transcript().to_string(), dto.variant_string().to_string())
+    }
+
+    fn failed_variant(dto: &VariantDto, reason: String) -> FailedVariantDto {
+        FailedVariantDto {
+            variant_string: dto.variant_string().to_string(),
+            transcript: dto.transcript().to_string(),
+            reason
+        }
     }
 
 
@@ -247,4 +427,88 @@ impl VariantManager {
     }
 
 
-}
\ No newline at end of file
+}
+
+
+#[cfg(test)]
+mod tests {
+    use serde_json::Value;
+
+    use super::*;
+    use crate::variant::variant_validator::VvHttpClient;
+
+    /// Returns the same canned VariantValidator response for every request and counts the requests
+    struct CountingClient {
+        response: Value,
+        n_requests: AtomicUsize,
+    }
+
+    impl VvHttpClient for CountingClient {
+        fn get_json(&self, url: &str) -> Result<Value, String> {
+            self.n_requests.fetch_add(1, Ordering::SeqCst);
+            if url.contains("NM_000138.5") {
+                Ok(self.response.clone())
+            } else {
+                Err("VariantValidator returned HTTP 400 Bad Request".to_string())
+            }
+        }
+    }
+
+    #[test]
+    fn test_cache_persisted_and_corrupt_cache_ignored() {
+        let dir = std::env::temp_dir().join(format!("rphetools-cache-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("hgvs_cache.txt"), "{\"c.8242G>T\": {\"assembly\": ").unwrap();
+        let response: Value = serde_json::from_str(include_str!("../../resources/variant_validator/fbn1_c8242G_T.json")).unwrap();
+        let client = CountingClient { response, n_requests: AtomicUsize::new(0) };
+        let validator = VariantValidator::with_client("hg38", Box::new(client)).unwrap();
+        let mut manager = VariantManager::with_variant_api(&dir, Box::new(validator));
+        assert_eq!(0, manager.n_hgvs());
+        assert_eq!(1, manager.cache_load_errors().len());
+        assert!(manager.cache_load_errors()[0].starts_with("Could not read variant cache"));
+        let dto = VariantDto::new_hgvs("c.8242G>T", "NM_000138.5", "HGNC:3603", "FBN1");
+        assert!(manager.validate_variant(&dto).unwrap().validated());
+        // re-opening the directory restores the validated variant from disk
+        let manager = VariantManager::new(&dir);
+        assert_eq!(1, manager.n_hgvs());
+        assert!(manager.cache_load_errors().is_empty());
+        assert!(manager.get_hgvs_variant("c.8242G>T").is_some());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_validate_variant_dto_list_concurrent() {
+        let dir = std::env::temp_dir().join(format!("rphetools-concurrent-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let response: Value = serde_json::from_str(include_str!("../../resources/variant_validator/fbn1_c8242G_T.json")).unwrap();
+        let client = CountingClient { response, n_requests: AtomicUsize::new(0) };
+        let validator = VariantValidator::with_client("hg38", Box::new(client)).unwrap();
+        let mut manager = VariantManager::with_variant_api(&dir, Box::new(validator));
+        manager.clear_cache();
+        let valid = VariantDto::new_hgvs("c.8242G>T", "NM_000138.5", "HGNC:3603", "FBN1");
+        let invalid = VariantDto::new_hgvs("c.8242G>T", "NM_999999.1", "HGNC:3603", "FBN1");
+        let invalid_sv = VariantDto::new_sv("DEL: arr 16q24.3 DEL89,754,790 −89,757,400", "NM_052988.5", "HGNC:1770", "CDK10");
+        let dto_list = vec![valid.clone(), invalid, valid, invalid_sv];
+        let report = manager.validate_variant_dto_list_concurrent(dto_list, 4);
+        assert_eq!(4, report.total);
+        assert_eq!(2, report.validated);
+        assert!(report.cache_error.is_none());
+        let evaluated = report.variant_dto_list;
+        assert_eq!(4, evaluated.len());
+        assert!(evaluated[0].validated());
+        assert_eq!("NM_999999.1", evaluated[1].transcript());
+        assert!(!evaluated[1].validated());
+        assert!(evaluated[2].validated());
+        assert!(!evaluated[3].validated());
+        assert!(manager.get_hgvs_variant("c.8242G>T").is_some());
+        // the reasons of the HGVS and of the structural failure are reported
+        let failed: Vec<&str> = report.failed.iter().map(|f| f.transcript.as_str()).collect();
+        assert_eq!(vec!["NM_999999.1", "NM_052988.5"], failed);
+        assert!(report.failed[1].reason.contains("non-ASCII"));
+        // the cached variant was validated for NM_000138.5, so it does not validate the allele for another transcript
+        let report = manager.validate_variant_dto_list_concurrent(vec![evaluated[1].clone()], 4);
+        assert!(!report.variant_dto_list[0].validated());
+        assert_eq!(1, report.failed.len());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}